@@ -66,6 +66,11 @@ pub trait CliInputOutput {
     fn read_password(&mut self) -> IoResult<SafeString>;
     fn prompt_password(&mut self, prompt: impl ToString) -> IoResult<SafeString>;
 
+    /// Whether the standard output is a terminal rather than a pipe or a file
+    fn is_stdout_tty(&self) -> bool {
+        false
+    }
+
     fn nl(&mut self, output_type: OutputType);
     fn write(&mut self, s: impl ToString, output_type: OutputType);
     fn writeln(&mut self, s: impl ToString, output_type: OutputType);
@@ -162,6 +167,10 @@ impl<'a> CliInputOutput for RegularInputOutput<'a> {
         Ok(SafeString::from_string(prompt_password(prompt)?))
     }
 
+    fn is_stdout_tty(&self) -> bool {
+        atty::is(atty::Stream::Stdout)
+    }
+
     fn nl(&mut self, output_type: OutputType) {
         match output_type {
             OutputType::Standard => {
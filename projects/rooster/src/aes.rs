@@ -7,18 +7,12 @@
 // except according to those terms.
 
 use rtoolbox::safe_vec::SafeVec;
-use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
 
-type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
 type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
 
-pub fn encrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, ()> {
-    Ok(
-        Aes256CbcEnc::new(key.into(), iv.into())
-        .encrypt_padded_vec_mut::<Pkcs7>(data)
-    )
-}
-
 pub fn decrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<SafeVec, ()> {
     let mut buf = data.to_vec();
     Aes256CbcDec::new(key.into(), iv.into())
@@ -26,3 +20,46 @@ pub fn decrypt(data: &[u8], key: &[u8], iv: &[u8]) -> Result<SafeVec, ()> {
         .map_err(|_| ())
         .map(|vec| SafeVec::new(vec))
 }
+
+/// Encrypts `data` with AES-256-GCM, authenticating `associated_data` along with it.
+///
+/// The 128 bits authentication tag is appended to the encrypted data.
+pub fn encrypt_authenticated(
+    data: &[u8],
+    associated_data: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+) -> Result<Vec<u8>, ()> {
+    Aes256Gcm::new_from_slice(key)
+        .map_err(|_| ())?
+        .encrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: data,
+                aad: associated_data,
+            },
+        )
+        .map_err(|_| ())
+}
+
+/// Decrypts data encrypted with `encrypt_authenticated`.
+///
+/// Fails if the key is wrong or if either the data or the associated data was tampered with.
+pub fn decrypt_authenticated(
+    data: &[u8],
+    associated_data: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+) -> Result<SafeVec, ()> {
+    Aes256Gcm::new_from_slice(key)
+        .map_err(|_| ())?
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: data,
+                aad: associated_data,
+            },
+        )
+        .map_err(|_| ())
+        .map(SafeVec::new)
+}
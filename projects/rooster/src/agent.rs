@@ -0,0 +1,490 @@
+//! The agent keeps derived keys in memory for a while, so that the master password doesn't have
+//! to be typed for every command, like ssh-agent does for SSH keys.
+//!
+//! Keys are identified by `PasswordStore::key_id`, which reveals nothing about them. The agent
+//! listens on a Unix socket that only the user can access, and both ends check that the other end
+//! runs as the same user. Keys live in memory that can't be swapped out, and are forgotten once
+//! the agent hasn't been used for a while, or right away with `rooster lock`.
+//!
+//! The client writes a request and closes its end, then the agent writes a response:
+//! - `G` + key id: get a key, answered with `K` + key, or `N` if the agent doesn't have it;
+//! - `P` + key id + key: remember a key, answered with `O`;
+//! - `L`: forget all keys, answered with `O`.
+//!
+//! Requests that fail are answered with `E`.
+
+use crate::password::kdf::KEY_LEN;
+use crate::password::v3::{PasswordStore, KEY_ID_LEN};
+use rtoolbox::safe_vec::SafeVec;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Read, Result as IoResult, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub const SOCKET_ENV_VAR: &str = "ROOSTER_AGENT_SOCK";
+
+/// How long either end waits for the other to read or write
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+const REQUEST_GET: u8 = b'G';
+const REQUEST_PUT: u8 = b'P';
+const REQUEST_LOCK: u8 = b'L';
+const RESPONSE_KEY: u8 = b'K';
+const RESPONSE_NO_KEY: u8 = b'N';
+const RESPONSE_OK: u8 = b'O';
+const RESPONSE_ERROR: u8 = b'E';
+
+const MAX_MESSAGE_LEN: usize = 1 + KEY_ID_LEN + KEY_LEN;
+
+/// Where the agent listens: `ROOSTER_AGENT_SOCK` if set, or a directory of the user's own.
+pub fn socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(SOCKET_ENV_VAR) {
+        return PathBuf::from(path);
+    }
+
+    let directory = match dirs::runtime_dir() {
+        Some(runtime_dir) => runtime_dir.join("rooster"),
+        None => std::env::temp_dir().join(format!("rooster-{}", unsafe { libc::geteuid() })),
+    };
+    directory.join("agent.sock")
+}
+
+/// A key in memory that is never swapped to disk, and is wiped when dropped.
+struct LockedKey {
+    bytes: Box<[u8; KEY_LEN]>,
+}
+
+impl LockedKey {
+    fn new(key: &[u8]) -> IoResult<LockedKey> {
+        if key.len() != KEY_LEN {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "the key has the wrong length",
+            ));
+        }
+
+        let mut bytes = Box::new([0u8; KEY_LEN]);
+        if unsafe { libc::mlock(bytes.as_ptr() as *const libc::c_void, KEY_LEN) } != 0 {
+            return Err(Error::last_os_error());
+        }
+        bytes.copy_from_slice(key);
+        Ok(LockedKey { bytes })
+    }
+}
+
+impl Drop for LockedKey {
+    fn drop(&mut self) {
+        for byte in self.bytes.iter_mut() {
+            unsafe { std::ptr::write_volatile(byte, 0) };
+        }
+        unsafe { libc::munlock(self.bytes.as_ptr() as *const libc::c_void, KEY_LEN) };
+    }
+}
+
+struct Agent {
+    keys: HashMap<[u8; KEY_ID_LEN], LockedKey>,
+    /// `None` keeps the keys until `rooster lock`
+    timeout: Option<Duration>,
+    last_used: Instant,
+}
+
+impl Agent {
+    fn new(timeout: Option<Duration>) -> Agent {
+        Agent {
+            keys: HashMap::new(),
+            timeout,
+            last_used: Instant::now(),
+        }
+    }
+
+    /// How long until the keys are forgotten, if there are any.
+    fn time_left(&self, now: Instant) -> Option<Duration> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        self.timeout
+            .map(|timeout| timeout.saturating_sub(now.duration_since(self.last_used)))
+    }
+
+    fn forget_if_idle(&mut self, now: Instant) {
+        if self.time_left(now) == Some(Duration::ZERO) {
+            self.keys.clear();
+        }
+    }
+
+    fn handle(&mut self, request: &[u8], now: Instant) -> SafeVec {
+        self.forget_if_idle(now);
+
+        let mut response = SafeVec::new(Vec::new());
+        match (request.first(), request.len()) {
+            (Some(&REQUEST_GET), len) if len == 1 + KEY_ID_LEN => {
+                match self.keys.get(&request[1..]) {
+                    Some(key) => {
+                        self.last_used = now;
+                        response.inner_mut().push(RESPONSE_KEY);
+                        response.inner_mut().extend_from_slice(key.bytes.as_ref());
+                    }
+                    None => response.inner_mut().push(RESPONSE_NO_KEY),
+                }
+            }
+            (Some(&REQUEST_PUT), len) if len == 1 + KEY_ID_LEN + KEY_LEN => {
+                let mut key_id = [0u8; KEY_ID_LEN];
+                key_id.copy_from_slice(&request[1..1 + KEY_ID_LEN]);
+                match LockedKey::new(&request[1 + KEY_ID_LEN..]) {
+                    Ok(key) => {
+                        self.keys.insert(key_id, key);
+                        self.last_used = now;
+                        response.inner_mut().push(RESPONSE_OK);
+                    }
+                    Err(_) => response.inner_mut().push(RESPONSE_ERROR),
+                }
+            }
+            (Some(&REQUEST_LOCK), 1) => {
+                self.keys.clear();
+                response.inner_mut().push(RESPONSE_OK);
+            }
+            _ => response.inner_mut().push(RESPONSE_ERROR),
+        }
+        response
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> IoResult<libc::uid_t> {
+    let mut credentials: libc::ucred = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut credentials as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if result != 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(credentials.uid)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> IoResult<libc::uid_t> {
+    let mut uid: libc::uid_t = 0;
+    let mut gid: libc::gid_t = 0;
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(uid)
+}
+
+/// Makes sure the other end of the socket runs as the same user as we do.
+fn check_peer(stream: &UnixStream) -> IoResult<()> {
+    if peer_uid(stream)? != unsafe { libc::geteuid() } {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "the other end of the agent socket belongs to another user",
+        ));
+    }
+    Ok(())
+}
+
+fn read_message(stream: &mut UnixStream) -> IoResult<SafeVec> {
+    let mut message = SafeVec::new(Vec::with_capacity(MAX_MESSAGE_LEN + 1));
+    stream
+        .take(MAX_MESSAGE_LEN as u64 + 1)
+        .read_to_end(message.inner_mut())?;
+    Ok(message)
+}
+
+fn request(socket_path: &Path, request: &[u8]) -> IoResult<SafeVec> {
+    let mut stream = UnixStream::connect(socket_path)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    check_peer(&stream)?;
+
+    stream.write_all(request)?;
+    stream.shutdown(Shutdown::Write)?;
+    let response = read_message(&mut stream)?;
+    if response.first() == Some(&RESPONSE_ERROR) {
+        return Err(Error::other("the agent could not do what was asked"));
+    }
+    Ok(response)
+}
+
+fn unexpected_response() -> Error {
+    Error::new(ErrorKind::InvalidData, "unexpected response from the agent")
+}
+
+pub fn get_key(socket_path: &Path, key_id: &[u8; KEY_ID_LEN]) -> IoResult<Option<SafeVec>> {
+    let mut message = vec![REQUEST_GET];
+    message.extend_from_slice(key_id);
+    let response = request(socket_path, message.as_slice())?;
+
+    match (response.first(), response.len()) {
+        (Some(&RESPONSE_KEY), len) if len == 1 + KEY_LEN => {
+            Ok(Some(SafeVec::new(response[1..].to_vec())))
+        }
+        (Some(&RESPONSE_NO_KEY), 1) => Ok(None),
+        _ => Err(unexpected_response()),
+    }
+}
+
+pub fn put_key(socket_path: &Path, key_id: &[u8; KEY_ID_LEN], key: &[u8]) -> IoResult<()> {
+    let mut message = SafeVec::new(vec![REQUEST_PUT]);
+    message.inner_mut().extend_from_slice(key_id);
+    message.inner_mut().extend_from_slice(key);
+    match request(socket_path, message.as_ref())?.as_ref() {
+        [RESPONSE_OK] => Ok(()),
+        _ => Err(unexpected_response()),
+    }
+}
+
+pub fn lock(socket_path: &Path) -> IoResult<()> {
+    match request(socket_path, &[REQUEST_LOCK])?.as_ref() {
+        [RESPONSE_OK] => Ok(()),
+        _ => Err(unexpected_response()),
+    }
+}
+
+/// Opens the password file with a key from the agent, if it is running and has the right one.
+pub fn unlock(input: &SafeVec) -> Option<PasswordStore> {
+    let key_id = PasswordStore::key_id_from_input(input.as_ref()).ok()?;
+    let key = get_key(&socket_path(), &key_id).ok()??;
+    // The key is stale if the master password was changed since.
+    PasswordStore::from_input_with_key(key, input.clone()).ok()
+}
+
+/// Hands the key of the password file to the agent, if it is running.
+pub fn remember(store: &PasswordStore) {
+    // The agent is optional, the key is simply not cached without it.
+    let _ = put_key(&socket_path(), &store.key_id(), store.key().as_ref());
+}
+
+/// Removes the socket of an agent that is gone, and fails if one is still running.
+fn remove_stale_socket(socket_path: &Path) -> IoResult<()> {
+    let metadata = match fs::symlink_metadata(socket_path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", socket_path.display()),
+        ));
+    }
+    if UnixStream::connect(socket_path).is_ok() {
+        return Err(Error::new(
+            ErrorKind::AddrInUse,
+            format!("an agent is already listening on {}", socket_path.display()),
+        ));
+    }
+    fs::remove_file(socket_path)
+}
+
+/// Creates the directory of the socket if needed, and makes sure other users can't get in.
+fn prepare_socket_directory(directory: &Path) -> IoResult<()> {
+    match fs::DirBuilder::new().mode(0o700).create(directory) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+        Err(err) => return Err(err),
+    }
+
+    let metadata = fs::symlink_metadata(directory)?;
+    if !metadata.is_dir() || metadata.uid() != unsafe { libc::geteuid() } {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} is not a directory of yours", directory.display()),
+        ));
+    }
+    if metadata.mode() & 0o077 != 0 {
+        fs::set_permissions(directory, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+fn bind(socket_path: &Path) -> IoResult<UnixListener> {
+    remove_stale_socket(socket_path)?;
+
+    let listener = UnixListener::bind(socket_path)?;
+    fs::set_permissions(socket_path, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Starts listening on `socket_path()`.
+pub fn listen() -> IoResult<(PathBuf, UnixListener)> {
+    // Better to fail now than on the first key.
+    LockedKey::new(&[0u8; KEY_LEN]).map_err(|err| {
+        Error::new(
+            err.kind(),
+            format!("memory can't be locked, check `ulimit -l` ({})", err),
+        )
+    })?;
+
+    let socket_path = socket_path();
+    // A socket set with `ROOSTER_AGENT_SOCK` goes wherever the user wants it.
+    if std::env::var_os(SOCKET_ENV_VAR).is_none() {
+        if let Some(directory) = socket_path.parent() {
+            prepare_socket_directory(directory)?;
+        }
+    }
+    let listener = bind(&socket_path)?;
+    Ok((socket_path, listener))
+}
+
+/// Waits until a client connects, or until `timeout` is over. Returns whether a client is there.
+fn wait_for_client(listener: &UnixListener, timeout: Option<Duration>) -> IoResult<bool> {
+    let mut poll_fd = libc::pollfd {
+        fd: listener.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout_ms = match timeout {
+        // Round up, so that we don't wake up right before the keys expire.
+        Some(timeout) => timeout.as_millis().saturating_add(1).min(i32::MAX as u128) as i32,
+        None => -1,
+    };
+    match unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) } {
+        -1 => {
+            let err = Error::last_os_error();
+            if err.kind() == ErrorKind::Interrupted {
+                Ok(false)
+            } else {
+                Err(err)
+            }
+        }
+        0 => Ok(false),
+        _ => Ok(true),
+    }
+}
+
+fn serve_client(agent: &mut Agent, mut stream: UnixStream) -> IoResult<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    check_peer(&stream)?;
+
+    let message = read_message(&mut stream)?;
+    let response = agent.handle(message.as_ref(), Instant::now());
+    stream.write_all(response.as_ref())
+}
+
+/// Keeps keys for clients until the process is stopped.
+pub fn serve(listener: UnixListener, timeout: Option<Duration>) -> IoResult<()> {
+    // Keep other processes of the user from reading the keys through a core dump or a debugger.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    unsafe {
+        libc::prctl(libc::PR_SET_DUMPABLE, 0);
+    }
+
+    let mut agent = Agent::new(timeout);
+    loop {
+        if wait_for_client(&listener, agent.time_left(Instant::now()))? {
+            // One client failing must not stop the agent.
+            if let Ok((stream, _)) = listener.accept() {
+                let _ = serve_client(&mut agent, stream);
+            }
+        }
+        agent.forget_if_idle(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bind, get_key, lock, put_key, serve, Agent, KEY_ID_LEN};
+    use crate::password::kdf::KEY_LEN;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_agent_handle() {
+        let mut agent = Agent::new(None);
+        let now = Instant::now();
+        let key_id = [1u8; KEY_ID_LEN];
+
+        let mut get = vec![b'G'];
+        get.extend_from_slice(&key_id);
+        assert_eq!(agent.handle(&get, now).as_ref(), b"N");
+
+        let mut put = vec![b'P'];
+        put.extend_from_slice(&key_id);
+        put.extend_from_slice(&[2u8; KEY_LEN]);
+        assert_eq!(agent.handle(&put, now).as_ref(), b"O");
+
+        let response = agent.handle(&get, now);
+        assert_eq!(response[0], b'K');
+        assert_eq!(&response[1..], &[2u8; KEY_LEN]);
+
+        assert_eq!(agent.handle(b"L", now).as_ref(), b"O");
+        assert_eq!(agent.handle(&get, now).as_ref(), b"N");
+
+        // Malformed requests
+        assert_eq!(agent.handle(b"", now).as_ref(), b"E");
+        assert_eq!(agent.handle(&get[..KEY_ID_LEN], now).as_ref(), b"E");
+        assert_eq!(agent.handle(&put[..put.len() - 1], now).as_ref(), b"E");
+        assert_eq!(agent.handle(b"X", now).as_ref(), b"E");
+    }
+
+    #[test]
+    fn test_agent_forgets_keys_when_idle() {
+        let mut agent = Agent::new(Some(Duration::from_secs(60)));
+        let start = Instant::now();
+        let key_id = [1u8; KEY_ID_LEN];
+        let mut get = vec![b'G'];
+        get.extend_from_slice(&key_id);
+        let mut put = vec![b'P'];
+        put.extend_from_slice(&key_id);
+        put.extend_from_slice(&[2u8; KEY_LEN]);
+
+        assert_eq!(agent.time_left(start), None);
+        agent.handle(&put, start);
+        assert_eq!(agent.time_left(start), Some(Duration::from_secs(60)));
+
+        // Using the key keeps it around for longer
+        assert_eq!(agent.handle(&get, start + Duration::from_secs(50))[0], b'K');
+        assert_eq!(
+            agent.handle(&get, start + Duration::from_secs(100))[0],
+            b'K'
+        );
+        assert_eq!(
+            agent.time_left(start + Duration::from_secs(130)),
+            Some(Duration::from_secs(30))
+        );
+
+        agent.forget_if_idle(start + Duration::from_secs(160));
+        assert_eq!(agent.time_left(start + Duration::from_secs(160)), None);
+        assert_eq!(
+            agent
+                .handle(&get, start + Duration::from_secs(160))
+                .as_ref(),
+            b"N"
+        );
+    }
+
+    #[test]
+    fn test_agent_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("agent.sock");
+        let listener = bind(&socket_path).unwrap();
+        std::thread::spawn(move || serve(listener, None));
+
+        // Only one agent at a time
+        assert!(bind(&socket_path).is_err());
+
+        let key_id = [3u8; KEY_ID_LEN];
+        assert!(get_key(&socket_path, &key_id).unwrap().is_none());
+        put_key(&socket_path, &key_id, &[4u8; KEY_LEN]).unwrap();
+        assert_eq!(
+            get_key(&socket_path, &key_id).unwrap().unwrap().as_ref(),
+            &[4u8; KEY_LEN]
+        );
+        assert!(put_key(&socket_path, &key_id, &[4u8; 3]).is_err());
+
+        lock(&socket_path).unwrap();
+        assert!(get_key(&socket_path, &key_id).unwrap().is_none());
+    }
+}
@@ -0,0 +1,174 @@
+//! Crash-safe replacement of files on disk.
+//!
+//! Instead of truncating a file and rewriting it in place, the new contents are written to a
+//! temporary file in the same directory, flushed to disk, and then renamed over the original.
+//! If anything goes wrong before the rename, the original file is left untouched.
+
+use std::fs::{self, File};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Result as IoResult, Write};
+use std::path::{Path, PathBuf};
+
+/// How many symbolic links we follow before giving up, same as Linux's `MAXSYMLINKS`.
+const MAX_SYMLINKS: usize = 40;
+
+/// Follows symbolic links until we reach a path that is not a symbolic link.
+///
+/// Unlike `std::fs::canonicalize`, this works when the final target does not exist yet.
+pub fn resolve_symlinks(path: &Path) -> IoResult<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_SYMLINKS {
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let target = fs::read_link(&path)?;
+                // Relative targets are relative to the directory containing the link.
+                path = match path.parent() {
+                    Some(parent) => parent.join(target),
+                    None => target,
+                };
+            }
+            Ok(_) => return Ok(path),
+            Err(err) if err.kind() == IoErrorKind::NotFound => return Ok(path),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Err(IoError::other("too many levels of symbolic links"))
+}
+
+/// Atomically replaces the contents of the file at `path`, following symbolic links.
+pub fn write(path: &Path, contents: &[u8]) -> IoResult<()> {
+    write_with(path, |file| file.write_all(contents))
+}
+
+/// Same as `write`, but lets the caller write the contents to the temporary file itself.
+pub fn write_with<F>(path: &Path, write_contents: F) -> IoResult<()>
+where
+    F: FnOnce(&mut File) -> IoResult<()>,
+{
+    let path = resolve_symlinks(path)?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| IoError::new(IoErrorKind::InvalidInput, "not a file path"))?
+        .to_string_lossy()
+        .into_owned();
+    let dir = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    // The temporary file must be in the same directory, otherwise the rename isn't atomic.
+    // It is removed automatically if we return early.
+    let mut temp = tempfile::Builder::new()
+        .prefix(&format!(".{}.", file_name))
+        .suffix(".tmp")
+        .tempfile_in(&dir)?;
+
+    // Keep the permissions of the file we are replacing. New files keep the temporary
+    // file's permissions, which are only readable by the current user.
+    if let Ok(metadata) = fs::metadata(&path) {
+        temp.as_file().set_permissions(metadata.permissions())?;
+    }
+
+    write_contents(temp.as_file_mut())?;
+    temp.as_file().sync_all()?;
+
+    temp.persist(&path).map_err(|err| err.error)?;
+
+    sync_dir(&dir)
+}
+
+/// Makes sure the rename is on disk, not just the file contents.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> IoResult<()> {
+    File::open(dir)?.sync_all()
+}
+
+// Directories can't be opened like regular files on Windows, and NTFS journals renames.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> IoResult<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{resolve_symlinks, write, write_with};
+    use std::fs;
+    use std::io::{Error as IoError, ErrorKind as IoErrorKind, Write};
+
+    fn temp_files_in(dir: &std::path::Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect()
+    }
+
+    #[test]
+    fn test_write_creates_and_replaces_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+
+        write(&path, b"first").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"first");
+
+        write(&path, b"second").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert!(temp_files_in(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_interrupted_write_keeps_original_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        write(&path, b"original contents").unwrap();
+
+        let result = write_with(&path, |file| {
+            file.write_all(b"half of the new")?;
+            Err(IoError::new(IoErrorKind::Other, "disk full"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original contents");
+        assert!(temp_files_in(dir.path()).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        write(&path, b"first").unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        write(&path, b"second").unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_follows_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target");
+        let link = dir.path().join("link");
+        let link_to_link = dir.path().join("link-to-link");
+        write(&target, b"first").unwrap();
+        std::os::unix::fs::symlink("target", &link).unwrap();
+        std::os::unix::fs::symlink(&link, &link_to_link).unwrap();
+
+        assert_eq!(resolve_symlinks(&link_to_link).unwrap(), target);
+
+        write(&link_to_link, b"second").unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert!(fs::symlink_metadata(&link_to_link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read(&target).unwrap(), b"second");
+    }
+}
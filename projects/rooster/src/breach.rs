@@ -0,0 +1,195 @@
+//! Offline lookups in a copy of the Have I Been Pwned password hashes.
+//!
+//! Two layouts are supported, as made by the official downloader:
+//! - a single file of `HASH:COUNT` lines sorted by hash, which is searched with a binary search;
+//! - a directory of range files named after the first 5 characters of the hash (optionally with a
+//!   `.txt` extension), each holding `SUFFIX:COUNT` lines.
+//!
+//! Hashes are SHA-1 in uppercase hexadecimal. Passwords never leave the machine.
+
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Result as IoResult, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+const RANGE_PREFIX_LEN: usize = 5;
+
+pub enum BreachDb {
+    SortedFile(PathBuf),
+    RangeDirectory(PathBuf),
+}
+
+/// Uppercase hexadecimal SHA-1 of the password, as used by Have I Been Pwned.
+pub fn sha1_hex(password: &str) -> String {
+    Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect()
+}
+
+/// Splits a `HASH:COUNT` line, ignoring the `\r` of files downloaded on Windows.
+fn parse_line(line: &str) -> Option<(&str, u64)> {
+    let mut parts = line.trim_end().splitn(2, ':');
+    let hash = parts.next()?;
+    let count = parts.next()?.parse::<u64>().ok()?;
+    Some((hash, count))
+}
+
+/// Where the first line starting at or after `position` begins.
+fn next_line_start(file: &mut File, position: u64) -> IoResult<u64> {
+    if position == 0 {
+        return Ok(0);
+    }
+    file.seek(SeekFrom::Start(position - 1))?;
+    let mut skipped = Vec::new();
+    BufReader::new(file).read_until(b'\n', &mut skipped)?;
+    Ok(position - 1 + skipped.len() as u64)
+}
+
+fn read_line_at(file: &mut File, position: u64) -> IoResult<String> {
+    file.seek(SeekFrom::Start(position))?;
+    let mut line = String::new();
+    BufReader::new(file).read_line(&mut line)?;
+    Ok(line)
+}
+
+fn search_sorted_file(path: &Path, hash: &str) -> IoResult<Option<u64>> {
+    let mut file = File::open(path)?;
+
+    // The line we look for, if any, starts somewhere in [low, high)
+    let mut low = 0;
+    let mut high = file.metadata()?.len();
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let line_start = next_line_start(&mut file, middle)?;
+        if line_start >= high {
+            high = middle;
+            continue;
+        }
+
+        let line = read_line_at(&mut file, line_start)?;
+        let (line_hash, count) = parse_line(&line).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("unexpected line at byte {}", line_start),
+            )
+        })?;
+        match line_hash.to_uppercase().as_str().cmp(hash) {
+            std::cmp::Ordering::Equal => return Ok(Some(count)),
+            std::cmp::Ordering::Less => low = line_start + line.len() as u64,
+            std::cmp::Ordering::Greater => high = middle,
+        }
+    }
+
+    Ok(None)
+}
+
+fn search_range_directory(directory: &Path, hash: &str) -> IoResult<Option<u64>> {
+    let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LEN);
+    let path = [prefix.to_string(), format!("{}.txt", prefix)]
+        .iter()
+        .map(|name| directory.join(name))
+        .find(|path| path.is_file())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("there is no range file for {}", prefix),
+            )
+        })?;
+
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if let Some((line_suffix, count)) = parse_line(&line) {
+            if line_suffix.eq_ignore_ascii_case(suffix) {
+                return Ok(Some(count));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+impl BreachDb {
+    pub fn open(path: &Path) -> IoResult<BreachDb> {
+        if path.is_dir() {
+            Ok(BreachDb::RangeDirectory(path.to_path_buf()))
+        } else if path.is_file() {
+            Ok(BreachDb::SortedFile(path.to_path_buf()))
+        } else {
+            Err(Error::new(
+                ErrorKind::NotFound,
+                format!("{} is neither a file nor a directory", path.display()),
+            ))
+        }
+    }
+
+    /// How many times the password was seen in data breaches, if it was.
+    pub fn count(&self, password: &str) -> IoResult<Option<u64>> {
+        let hash = sha1_hex(password);
+        match *self {
+            BreachDb::SortedFile(ref path) => search_sorted_file(path, &hash),
+            BreachDb::RangeDirectory(ref path) => search_range_directory(path, &hash),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{sha1_hex, BreachDb};
+    use std::fs;
+
+    #[test]
+    fn test_sha1_hex() {
+        assert_eq!(
+            sha1_hex("password"),
+            "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8"
+        );
+    }
+
+    #[test]
+    fn test_sorted_file() {
+        let passwords: Vec<String> = (0..500).map(|i| format!("password{}", i)).collect();
+        let mut lines: Vec<(String, usize)> = passwords
+            .iter()
+            .enumerate()
+            .map(|(i, password)| (sha1_hex(password), i * 1000 + 1))
+            .collect();
+        lines.sort();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pwned-passwords-sha1-ordered-by-hash.txt");
+        let contents: String = lines
+            .iter()
+            .map(|(hash, count)| format!("{}:{}\r\n", hash, count))
+            .collect();
+        fs::write(&path, contents).unwrap();
+
+        let db = BreachDb::open(&path).unwrap();
+        for (i, password) in passwords.iter().enumerate() {
+            assert_eq!(db.count(password).unwrap(), Some(i as u64 * 1000 + 1));
+        }
+        assert_eq!(db.count("not breached").unwrap(), None);
+        assert_eq!(db.count("").unwrap(), None);
+    }
+
+    #[test]
+    fn test_range_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let hash = sha1_hex("password");
+        fs::write(
+            dir.path().join(format!("{}.txt", &hash[..5])),
+            format!(
+                "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n{}:42\r\n",
+                &hash[5..]
+            ),
+        )
+        .unwrap();
+        let other_hash = sha1_hex("not breached");
+        fs::write(dir.path().join(&other_hash[..5]), "").unwrap();
+
+        let db = BreachDb::open(dir.path()).unwrap();
+        assert_eq!(db.count("password").unwrap(), Some(42));
+        assert_eq!(db.count("not breached").unwrap(), None);
+        assert!(db.count("no range file").is_err());
+    }
+}
@@ -1,108 +1,416 @@
+use crate::fields;
 use crate::password;
 use rclio::{CliInputOutput, OutputType};
 use rtoolbox::safe_string::SafeString;
 
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::io::Write;
 use std::ops::Deref;
+use std::process::{Command, Stdio};
 
-// On Windows and Mac, we'll use the native solutions provided by the OS libraries
-#[cfg(any(windows, target_os = "macos"))]
-pub fn copy_to_clipboard(s: &SafeString) -> Result<(), ()> {
-    use clipboard::ClipboardContext;
-    use clipboard::ClipboardProvider;
+/// How many seconds passwords stay in the clipboard, 0 to keep them there.
+const CLEAR_AFTER_ENV_VAR: &str = "ROOSTER_CLIPBOARD_TIMEOUT";
+const CLEAR_AFTER_DEFAULT: u64 = 45;
 
-    let mut context: ClipboardContext = ClipboardProvider::new().map_err(|_| ())?;
-    context.set_contents(s.deref().to_owned()).map_err(|_| ())?;
-    Ok(())
+/// A command that copies what it reads on stdin, used instead of the usual clipboard tools.
+const CLIPBOARD_CMD_ENV_VAR: &str = "ROOSTER_CLIPBOARD_CMD";
+/// A command that prints the clipboard on stdout, so that it can be wiped later.
+const CLIPBOARD_PASTE_CMD_ENV_VAR: &str = "ROOSTER_CLIPBOARD_PASTE_CMD";
+
+/// Somewhere to put passwords for the user to paste them.
+pub trait ClipboardBackend {
+    fn copy(&self, s: &SafeString) -> Result<(), ()>;
+
+    /// What the clipboard holds, so that we only wipe it if it still holds our value.
+    fn paste(&self) -> Result<SafeString, ()>;
+
+    /// Whether `paste` can work at all, otherwise the clipboard is never wiped.
+    fn can_paste(&self) -> bool {
+        true
+    }
 }
 
-// On UNIX, the most stable way to copy to the clipboard is using one of the existing
-// and battle tested tools: xsel and xclip.
-#[cfg(all(unix, not(target_os = "macos")))]
-pub fn copy_to_clipboard(s: &SafeString) -> Result<(), ()> {
-    use crate::quale::which;
-    use crate::shell_escape;
-    use std::env;
-    use std::process::Command;
-
-    let password = SafeString::from_string(shell_escape::escape(s.deref().into()).into());
-
-    fn wayland_clipboards(password: &SafeString) -> Result<(), ()> {
-        match which("wl-copy") {
-            Some(wl_copy) => {
-                let shell = format!(
-                    "printf '%s' {} | {} 2> /dev/null",
-                    password.deref(),
-                    wl_copy.to_string_lossy()
-                );
-                if Command::new("sh")
-                    .args(&["-c", shell.as_str()])
-                    .status()
-                    .map_err(|_| ())?
-                    .success()
-                {
-                    Ok(())
-                } else {
-                    Err(())
-                }
+/// A clipboard tool, like xsel or wl-copy. The value is written to its stdin so that it never
+/// shows up on a command line.
+pub struct CommandClipboard {
+    copy_command: Vec<OsString>,
+    paste_command: Option<Vec<OsString>>,
+}
+
+impl CommandClipboard {
+    /// Splits command lines on whitespace. They are run as is, without a shell.
+    pub fn from_command_lines(copy: &str, paste: Option<&str>) -> Option<CommandClipboard> {
+        fn split(command_line: &str) -> Option<Vec<OsString>> {
+            let command: Vec<OsString> = command_line
+                .split_whitespace()
+                .map(OsString::from)
+                .collect();
+            if command.is_empty() {
+                None
+            } else {
+                Some(command)
             }
-            None => Err(()),
         }
+
+        Some(CommandClipboard {
+            copy_command: split(copy)?,
+            paste_command: paste.and_then(split),
+        })
     }
 
-    fn x11_clipboards(password: &SafeString) -> Result<(), ()> {
-        match which("xsel") {
-            Some(xsel) => {
-                let shell = format!(
-                    "printf '%s' {} | {} -ib 2> /dev/null",
-                    password.deref(),
-                    xsel.to_string_lossy()
-                );
-                if Command::new("sh")
-                    .args(&["-c", shell.as_str()])
-                    .status()
-                    .map_err(|_| ())?
-                    .success()
-                {
-                    Ok(())
-                } else {
-                    Err(())
-                }
-            }
-            None => match which("xclip") {
-                Some(xclip) => {
-                    let shell = format!(
-                        "printf '%s' {} | {} -selection clipboard 2> /dev/null",
-                        password.deref(),
-                        xclip.to_string_lossy()
-                    );
-                    if Command::new("sh")
-                        .args(&["-c", shell.as_str()])
-                        .status()
-                        .map_err(|_| ())?
-                        .success()
-                    {
-                        Ok(())
-                    } else {
-                        Err(())
-                    }
-                }
-                None => Err(()),
-            },
+    /// Uses a tool if it is installed.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn from_tool(
+        tool: &str,
+        copy_args: &[&str],
+        paste_tool: &str,
+        paste_args: &[&str],
+    ) -> Option<CommandClipboard> {
+        use crate::quale::which;
+
+        fn command(path: std::path::PathBuf, args: &[&str]) -> Vec<OsString> {
+            let mut command = vec![path.into_os_string()];
+            command.extend(args.iter().map(OsString::from));
+            command
+        }
+
+        Some(CommandClipboard {
+            copy_command: command(which(tool)?, copy_args),
+            paste_command: which(paste_tool).map(|path| command(path, paste_args)),
+        })
+    }
+}
+
+impl ClipboardBackend for CommandClipboard {
+    fn copy(&self, s: &SafeString) -> Result<(), ()> {
+        let mut child = Command::new(&self.copy_command[0])
+            .args(&self.copy_command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|_| ())?;
+        {
+            let mut stdin = child.stdin.take().ok_or(())?;
+            stdin.write_all(s.deref().as_bytes()).map_err(|_| ())?;
+            // Closing stdin tells the tool it has everything
+        }
+        if child.wait().map_err(|_| ())?.success() {
+            Ok(())
+        } else {
+            Err(())
         }
     }
 
-    match env::var_os("XDG_SESSION_TYPE") {
-        Some(s) if s == "wayland" => {
-            let s = wayland_clipboards(&password);
-            match s {
-                Ok(_) => Ok(()),
-                Err(_) => x11_clipboards(&password),
+    fn paste(&self) -> Result<SafeString, ()> {
+        let paste_command = self.paste_command.as_ref().ok_or(())?;
+        let output = Command::new(&paste_command[0])
+            .args(&paste_command[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .map_err(|_| ())?;
+        if !output.status.success() {
+            return Err(());
+        }
+        String::from_utf8(output.stdout)
+            .map(SafeString::from_string)
+            .map_err(|_| ())
+    }
+
+    fn can_paste(&self) -> bool {
+        self.paste_command.is_some()
+    }
+}
+
+const BASE64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(input: &[u8]) -> String {
+    let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let group = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(BASE64_ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                output.push('=');
             }
         }
-        _ => x11_clipboards(&password),
+    }
+    output
+}
+
+/// Screen drops escape sequences longer than this, so longer ones are split.
+const SCREEN_CHUNK_LEN: usize = 76;
+
+/// The OSC 52 escape sequence that sets the clipboard of the terminal, wrapped so that tmux or
+/// screen pass it on to the terminal instead of handling it themselves.
+fn osc52_sequence(s: &str, tmux: bool, screen: bool) -> String {
+    let osc52 = format!("\x1b]52;c;{}\x07", base64_encode(s.as_bytes()));
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", osc52.replace('\x1b', "\x1b\x1b"))
+    } else if screen {
+        osc52
+            .as_bytes()
+            .chunks(SCREEN_CHUNK_LEN)
+            .map(|chunk| format!("\x1bP{}\x1b\\", String::from_utf8_lossy(chunk)))
+            .collect()
+    } else {
+        osc52
+    }
+}
+
+/// The clipboard of the terminal, set with an OSC 52 escape sequence written to the terminal.
+/// This works over SSH, as long as the terminal supports it. Terminals don't let us read it.
+pub struct Osc52Clipboard {
+    tty: std::path::PathBuf,
+}
+
+impl Osc52Clipboard {
+    fn new() -> Osc52Clipboard {
+        Osc52Clipboard {
+            tty: std::path::PathBuf::from("/dev/tty"),
+        }
+    }
+}
+
+impl ClipboardBackend for Osc52Clipboard {
+    fn copy(&self, s: &SafeString) -> Result<(), ()> {
+        let sequence = SafeString::from_string(osc52_sequence(
+            s.deref(),
+            std::env::var_os("TMUX").is_some(),
+            std::env::var_os("STY").is_some(),
+        ));
+        let mut tty = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&self.tty)
+            .map_err(|_| ())?;
+        tty.write_all(sequence.as_bytes()).map_err(|_| ())?;
+        tty.flush().map_err(|_| ())
+    }
+
+    fn paste(&self) -> Result<SafeString, ()> {
+        Err(())
+    }
+
+    fn can_paste(&self) -> bool {
+        false
+    }
+}
+
+// On Windows and Mac, we'll use the native solutions provided by the OS libraries
+#[cfg(any(windows, target_os = "macos"))]
+struct NativeClipboard;
+
+#[cfg(any(windows, target_os = "macos"))]
+impl ClipboardBackend for NativeClipboard {
+    fn copy(&self, s: &SafeString) -> Result<(), ()> {
+        use clipboard::ClipboardContext;
+        use clipboard::ClipboardProvider;
+
+        let mut context: ClipboardContext = ClipboardProvider::new().map_err(|_| ())?;
+        context.set_contents(s.deref().to_owned()).map_err(|_| ())?;
+        Ok(())
+    }
+
+    fn paste(&self) -> Result<SafeString, ()> {
+        use clipboard::ClipboardContext;
+        use clipboard::ClipboardProvider;
+
+        let mut context: ClipboardContext = ClipboardProvider::new().map_err(|_| ())?;
+        Ok(SafeString::from_string(
+            context.get_contents().map_err(|_| ())?,
+        ))
+    }
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn default_backends() -> Vec<Box<dyn ClipboardBackend>> {
+    vec![Box::new(NativeClipboard)]
+}
+
+// On UNIX, the most stable way to copy to the clipboard is using one of the existing
+// and battle tested tools: wl-copy, xsel and xclip.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn default_backends() -> Vec<Box<dyn ClipboardBackend>> {
+    let mut tools = vec![
+        CommandClipboard::from_tool("xsel", &["-ib"], "xsel", &["-ob"]),
+        CommandClipboard::from_tool(
+            "xclip",
+            &["-selection", "clipboard"],
+            "xclip",
+            &["-selection", "clipboard", "-o"],
+        ),
+    ];
+    if std::env::var_os("XDG_SESSION_TYPE").is_some_and(|s| s == "wayland") {
+        tools.insert(
+            0,
+            CommandClipboard::from_tool("wl-copy", &[], "wl-paste", &["-n"]),
+        );
+    }
+
+    tools
+        .into_iter()
+        .flatten()
+        .map(|tool| Box::new(tool) as Box<dyn ClipboardBackend>)
+        .collect()
+}
+
+/// The clipboards to try, in order:
+/// - the terminal's if `osc52` is set, for instance with `--osc52`;
+/// - the ROOSTER_CLIPBOARD_CMD command if it is set;
+/// - the terminal's over SSH, since the local tools would copy on the remote machine;
+/// - the usual ones for the platform, or the terminal's if there are none.
+fn clipboard_backends(osc52: bool) -> Vec<Box<dyn ClipboardBackend>> {
+    if osc52 {
+        return vec![Box::new(Osc52Clipboard::new())];
+    }
+    if let Ok(copy) = std::env::var(CLIPBOARD_CMD_ENV_VAR) {
+        return CommandClipboard::from_command_lines(
+            &copy,
+            std::env::var(CLIPBOARD_PASTE_CMD_ENV_VAR).ok().as_deref(),
+        )
+        .into_iter()
+        .map(|backend| Box::new(backend) as Box<dyn ClipboardBackend>)
+        .collect();
+    }
+    if std::env::var_os("SSH_TTY").is_some() {
+        return vec![Box::new(Osc52Clipboard::new())];
+    }
+
+    let backends = default_backends();
+    if backends.is_empty() {
+        return vec![Box::new(Osc52Clipboard::new())];
+    }
+    backends
+}
+
+/// Copies with the first clipboard that works, and returns it.
+fn set_clipboard<'a>(
+    backends: &'a [Box<dyn ClipboardBackend>],
+    s: &SafeString,
+) -> Result<&'a dyn ClipboardBackend, ()> {
+    backends
+        .iter()
+        .find(|backend| backend.copy(s).is_ok())
+        .map(|backend| backend.as_ref())
+        .ok_or(())
+}
+
+fn get_clipboard(backends: &[Box<dyn ClipboardBackend>]) -> Result<SafeString, ()> {
+    backends
+        .iter()
+        .find_map(|backend| backend.paste().ok())
+        .ok_or(())
+}
+
+fn clipboard_digest(s: &str) -> String {
+    Sha256::digest(s.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// How long passwords stay in the clipboard, from the ROOSTER_CLIPBOARD_TIMEOUT environment
+/// variable. 0 means forever.
+fn clear_after() -> u64 {
+    parse_clear_after(std::env::var(CLEAR_AFTER_ENV_VAR).ok())
+}
+
+fn parse_clear_after(value: Option<String>) -> u64 {
+    value
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(CLEAR_AFTER_DEFAULT)
+}
+
+/// Starts a process that outlives Rooster to wipe the clipboard later, if it still holds `s`.
+/// Returns after how many seconds, or `None` if the clipboard won't be wiped.
+fn clear_clipboard_later(s: &SafeString) -> Option<u64> {
+    let seconds = clear_after();
+    if seconds == 0 {
+        return None;
+    }
+
+    let mut command = Command::new(std::env::current_exe().ok()?);
+    command
+        .args(["clear-clipboard", seconds.to_string().as_str()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // Keep running when the terminal goes away or Ctrl+C is pressed, by leaving the session of
+    // the terminal: a process group of its own would still get SIGHUP
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // setsid is async-signal-safe, and can't fail as the child isn't a group leader yet
+        unsafe {
+            command.pre_exec(|| {
+                libc::setsid();
+                Ok(())
+            });
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x00000008;
+        command.creation_flags(DETACHED_PROCESS);
+    }
+
+    let mut child = command.spawn().ok()?;
+    // Only a digest of the password is handed over, through a pipe rather than the command line
+    let mut stdin = child.stdin.take()?;
+    writeln!(stdin, "{}", clipboard_digest(s.deref())).ok()?;
+    Some(seconds)
+}
+
+/// Copies to the clipboard, which is wiped after a while unless ROOSTER_CLIPBOARD_TIMEOUT is 0.
+/// Returns after how many seconds the clipboard will be wiped, if it will.
+pub fn copy_to_clipboard(s: &SafeString, matches: &clap::ArgMatches) -> Result<Option<u64>, ()> {
+    let backends = clipboard_backends(matches.get_flag("osc52"));
+    let backend = set_clipboard(&backends, s)?;
+    if !backend.can_paste() {
+        return Ok(None);
+    }
+    Ok(clear_clipboard_later(s))
+}
+
+/// Tells the user when the clipboard will be wiped.
+pub fn print_clear_notice(clear_after: Option<u64>, io: &mut impl CliInputOutput) {
+    if let Some(seconds) = clear_after {
+        io.info(
+            format!("I'll wipe it from your clipboard in {} seconds.", seconds),
+            OutputType::Standard,
+        );
     }
 }
 
+fn clear_clipboard_if_unchanged(
+    backends: &[Box<dyn ClipboardBackend>],
+    digest: &str,
+) -> Result<(), ()> {
+    let current = get_clipboard(backends)?;
+    if clipboard_digest(current.deref()) != digest.trim() {
+        // The user copied something else since, leave it alone
+        return Ok(());
+    }
+    set_clipboard(backends, &SafeString::from_string(String::new()))?;
+    Ok(())
+}
+
+/// Waits, then wipes the clipboard if it still holds the value whose digest is `digest`. This
+/// runs in the background process started by `copy_to_clipboard`.
+pub fn clear_clipboard_after(seconds: u64, digest: &str) -> Result<(), ()> {
+    std::thread::sleep(std::time::Duration::from_secs(seconds));
+    clear_clipboard_if_unchanged(&clipboard_backends(false), digest)
+}
+
 #[cfg(target_os = "macos")]
 pub fn paste_keys() -> &'static str {
     "Cmd+V"
@@ -114,10 +422,11 @@ pub fn paste_keys() -> &'static str {
 }
 
 pub fn confirm_password_retrieved(
-    show: bool,
-    password: &password::v2::Password,
+    matches: &clap::ArgMatches,
+    password: &password::v3::Password,
     io: &mut impl CliInputOutput,
 ) {
+    let show = matches.get_flag("show");
     if show {
         io.success(
             format!("Alright! Here is your password for {}:", password.name),
@@ -131,8 +440,10 @@ pub fn confirm_password_retrieved(
             format!("Password: {}", password.password.deref()),
             OutputType::Standard,
         );
+        fields::print(show, password, io);
     } else {
-        if copy_to_clipboard(&password.password).is_err() {
+        let copied = copy_to_clipboard(&password.password, matches);
+        if copied.is_err() {
             io.success(
                 format!(
                     "Hmm, I tried to copy your new password to your clipboard, but \
@@ -157,6 +468,170 @@ pub fn confirm_password_retrieved(
                 ),
                 OutputType::Standard,
             );
+            fields::print(show, password, io);
+            print_clear_notice(copied.ok().flatten(), io);
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        base64_encode, clear_clipboard_if_unchanged, clipboard_digest, osc52_sequence,
+        parse_clear_after, ClipboardBackend, CommandClipboard, Osc52Clipboard, CLEAR_AFTER_DEFAULT,
+    };
+    use rtoolbox::safe_string::SafeString;
+
+    #[test]
+    fn test_parse_clear_after() {
+        assert_eq!(parse_clear_after(None), CLEAR_AFTER_DEFAULT);
+        assert_eq!(parse_clear_after(Some("10".to_string())), 10);
+        assert_eq!(parse_clear_after(Some("0".to_string())), 0);
+        assert_eq!(
+            parse_clear_after(Some("soon".to_string())),
+            CLEAR_AFTER_DEFAULT
+        );
+    }
+
+    #[test]
+    fn test_clipboard_digest() {
+        assert_eq!(clipboard_digest("abcd"), clipboard_digest("abcd"));
+        assert_ne!(clipboard_digest("abcd"), clipboard_digest("abce"));
+        assert!(!clipboard_digest("abcd").contains("abcd"));
+    }
+
+    #[test]
+    fn test_command_clipboard_from_command_lines() {
+        assert!(CommandClipboard::from_command_lines("", None).is_none());
+        assert!(CommandClipboard::from_command_lines("  ", Some("cat")).is_none());
+
+        let clipboard =
+            CommandClipboard::from_command_lines("tmux load-buffer -", Some("tmux save-buffer -"))
+                .unwrap();
+        assert_eq!(clipboard.copy_command, vec!["tmux", "load-buffer", "-"]);
+        assert_eq!(
+            clipboard.paste_command,
+            Some(vec!["tmux".into(), "save-buffer".into(), "-".into()])
+        );
+    }
+
+    /// A clipboard that is a file, like the fake commands of the integration tests.
+    #[cfg(unix)]
+    fn fake_clipboard(dir: &std::path::Path) -> Vec<Box<dyn ClipboardBackend>> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let clipboard_file = dir.join("clipboard");
+        for (name, script) in [
+            (
+                "copy",
+                format!("#!/bin/sh\ncat > '{}'\n", clipboard_file.display()),
+            ),
+            (
+                "paste",
+                format!("#!/bin/sh\ncat '{}'\n", clipboard_file.display()),
+            ),
+        ]
+        .iter()
+        {
+            let path = dir.join(name);
+            std::fs::write(&path, script).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        vec![Box::new(
+            CommandClipboard::from_command_lines(
+                dir.join("copy").to_str().unwrap(),
+                Some(dir.join("paste").to_str().unwrap()),
+            )
+            .unwrap(),
+        )]
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_clipboard_copies_through_stdin() {
+        let dir = tempfile::tempdir().unwrap();
+        let backends = fake_clipboard(dir.path());
+
+        // Characters a shell would interpret are copied as they are
+        let password = SafeString::from_string("'$(touch pwned)\"; `id` \\ %s".to_string());
+        backends[0].copy(&password).unwrap();
+        assert_eq!(backends[0].paste().unwrap(), password);
+        assert!(!dir.path().join("pwned").exists());
+        assert!(!std::path::Path::new("pwned").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_clear_clipboard_if_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let backends = fake_clipboard(dir.path());
+
+        let password = SafeString::from_string("abcd".to_string());
+        backends[0].copy(&password).unwrap();
+        clear_clipboard_if_unchanged(&backends, &clipboard_digest("abcd")).unwrap();
+        assert_eq!(
+            backends[0].paste().unwrap(),
+            SafeString::from_string(String::new())
+        );
+
+        // Something else was copied since
+        let other = SafeString::from_string("something else".to_string());
+        backends[0].copy(&other).unwrap();
+        clear_clipboard_if_unchanged(&backends, &clipboard_digest("abcd")).unwrap();
+        assert_eq!(backends[0].paste().unwrap(), other);
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foob"), "Zm9vYg==");
+        assert_eq!(base64_encode(b"fooba"), "Zm9vYmE=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode(&[0xfb, 0xff, 0xbf]), "+/+/");
+    }
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(
+            osc52_sequence("abcd", false, false),
+            "\x1b]52;c;YWJjZA==\x07"
+        );
+        assert_eq!(
+            osc52_sequence("abcd", true, false),
+            "\x1bPtmux;\x1b\x1b]52;c;YWJjZA==\x07\x1b\\"
+        );
+        assert_eq!(
+            osc52_sequence("abcd", false, true),
+            "\x1bP\x1b]52;c;YWJjZA==\x07\x1b\\"
+        );
+
+        // Screen gets small chunks
+        let sequence = osc52_sequence(&"a".repeat(100), false, true);
+        assert_eq!(sequence.matches("\x1bP").count(), 2);
+        assert_eq!(
+            sequence.replace("\x1bP", "").replace("\x1b\\", ""),
+            osc52_sequence(&"a".repeat(100), false, false)
+        );
+    }
+
+    #[test]
+    fn test_osc52_clipboard() {
+        let dir = tempfile::tempdir().unwrap();
+        let clipboard = Osc52Clipboard {
+            tty: dir.path().join("tty"),
+        };
+        std::fs::write(&clipboard.tty, "").unwrap();
+
+        clipboard
+            .copy(&SafeString::from_string("abcd".to_string()))
+            .unwrap();
+        let written = std::fs::read_to_string(&clipboard.tty).unwrap();
+        assert!(written.contains("]52;c;YWJjZA==\x07"));
+        assert!(!written.contains("abcd"));
+        assert!(!clipboard.can_paste());
+    }
+}
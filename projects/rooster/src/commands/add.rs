@@ -1,30 +1,40 @@
-use crate::clip::{copy_to_clipboard, paste_keys};
+use crate::clip::{copy_to_clipboard, paste_keys, print_clear_notice};
+use crate::exit_code;
+use crate::fields;
 use crate::password;
+use crate::strength;
 use rclio::CliInputOutput;
 use rclio::OutputType;
 use std::ops::Deref;
 
 pub fn callback_exec(
     matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     let app_name = matches.get_one::<String>("app").unwrap();
     let username = matches.get_one::<String>("username").unwrap();
 
-    if store.has_password(app_name.deref()) {
+    if store.has_password(app_name, username) {
         io.error(
-            "Woops, there is already an app with that name.",
+            "Woops, there is already a password for this app and username.",
             OutputType::Error,
         );
-        return Err(1);
+        return Err(exit_code::ERROR);
     }
 
     match io.prompt_password(format!("What password do you want for \"{}\"? ", app_name)) {
         Ok(password_as_string) => {
+            strength::confirm_strength(
+                password_as_string.deref(),
+                &[app_name, username],
+                "password",
+                io,
+            )?;
             let password_as_string_clipboard = password_as_string.clone();
-            let password =
-                password::v2::Password::new(app_name.clone(), username, password_as_string);
+            let mut password =
+                password::v3::Password::new(app_name.clone(), username, password_as_string);
+            fields::apply(matches, &mut password, io)?;
             match store.add_password(password) {
                 Ok(_) => {
                     if matches.get_flag("show") {
@@ -38,7 +48,8 @@ pub fn callback_exec(
                         return Ok(());
                     }
 
-                    if copy_to_clipboard(&password_as_string_clipboard).is_err() {
+                    let copied = copy_to_clipboard(&password_as_string_clipboard, matches);
+                    if copied.is_err() {
                         io.success(
                             format!(
                                 "Hmm, I tried to copy your new password to your clipboard, \
@@ -57,6 +68,7 @@ pub fn callback_exec(
                             ),
                             OutputType::Standard,
                         );
+                        print_clear_notice(copied.ok().flatten(), io);
                     }
                 }
                 Err(err) => {
@@ -64,7 +76,7 @@ pub fn callback_exec(
                         format!("Woops, I couldn't add the password (reason: {:?}).", err),
                         OutputType::Error,
                     );
-                    return Err(1);
+                    return Err(exit_code::ERROR);
                 }
             }
             Ok(())
@@ -74,7 +86,7 @@ pub fn callback_exec(
                 format!("\nI couldn't read the app's password (reason: {:?}).", err),
                 OutputType::Error,
             );
-            Err(1)
+            Err(exit_code::ERROR)
         }
     }
 }
@@ -0,0 +1,125 @@
+use crate::agent;
+use crate::exit_code;
+use rclio::{CliInputOutput, OutputType};
+use std::os::unix::io::FromRawFd;
+use std::os::unix::net::UnixListener;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+pub fn callback_exec(matches: &clap::ArgMatches, io: &mut impl CliInputOutput) -> Result<(), i32> {
+    let seconds = *matches.get_one::<u64>("timeout").unwrap();
+    let timeout = match seconds {
+        0 => None,
+        seconds => Some(Duration::from_secs(seconds)),
+    };
+
+    // The process started by --detach, which serves the socket its parent is listening on
+    if matches.get_flag("listener-on-stdin") {
+        let listener = unsafe { UnixListener::from_raw_fd(0) };
+        if let Err(err) = listener.local_addr() {
+            io.error(
+                format!("Woops, stdin is not a socket (reason: {}).", err),
+                OutputType::Error,
+            );
+            return Err(exit_code::ERROR);
+        }
+        return serve(listener, timeout, io);
+    }
+
+    let (socket_path, listener) = agent::listen().map_err(|err| {
+        io.error(
+            format!("Woops, I couldn't start the agent (reason: {}).", err),
+            OutputType::Error,
+        );
+        exit_code::ERROR
+    })?;
+
+    if matches.get_flag("detach") {
+        // The socket is already listening, so commands run right after this one find the agent
+        let pid = detach(listener, seconds).map_err(|err| {
+            let _ = std::fs::remove_file(&socket_path);
+            io.error(
+                format!(
+                    "Woops, I couldn't start the agent in the background (reason: {}).",
+                    err
+                ),
+                OutputType::Error,
+            );
+            exit_code::ERROR
+        })?;
+        print_listening(&socket_path, seconds, timeout, io);
+        io.info(
+            format!(
+                "It runs in the background with PID {}, `kill {}` stops it.",
+                pid, pid
+            ),
+            OutputType::Standard,
+        );
+        return Ok(());
+    }
+
+    print_listening(&socket_path, seconds, timeout, io);
+    serve(listener, timeout, io)
+}
+
+fn print_listening(
+    socket_path: &Path,
+    seconds: u64,
+    timeout: Option<Duration>,
+    io: &mut impl CliInputOutput,
+) {
+    io.success(
+        format!("The agent is listening on {}.", socket_path.display()),
+        OutputType::Standard,
+    );
+    io.info(
+        match timeout {
+            Some(_) => format!(
+                "I'll remember your master password until I'm unused for {} seconds or you run `rooster lock`.",
+                seconds
+            ),
+            None => "I'll remember your master password until you run `rooster lock`.".to_string(),
+        },
+        OutputType::Standard,
+    );
+}
+
+/// Hands the listening socket over to a copy of Rooster that keeps running once this one exits.
+/// Returns its PID.
+fn detach(listener: UnixListener, seconds: u64) -> std::io::Result<u32> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args([
+            "agent",
+            "--listener-on-stdin",
+            "--timeout",
+            seconds.to_string().as_str(),
+        ])
+        .stdin(Stdio::from(std::os::unix::io::OwnedFd::from(listener)))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // Keep running when the terminal goes away, like the process that wipes the clipboard
+    unsafe {
+        command.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+    Ok(command.spawn()?.id())
+}
+
+fn serve(
+    listener: UnixListener,
+    timeout: Option<Duration>,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    agent::serve(listener, timeout).map_err(|err| {
+        io.error(
+            format!("Woops, the agent stopped (reason: {}).", err),
+            OutputType::Error,
+        );
+        exit_code::ERROR
+    })
+}
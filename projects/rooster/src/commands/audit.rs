@@ -0,0 +1,320 @@
+use crate::breach::BreachDb;
+use crate::exit_code;
+use crate::ffi;
+use crate::output;
+use crate::password;
+use crate::password::v3::Password;
+use crate::strength;
+use rclio::{CliInputOutput, OutputType};
+use serde::Serialize;
+use std::ops::Deref;
+use std::path::PathBuf;
+
+const SECONDS_PER_DAY: u32 = 60 * 60 * 24;
+
+#[derive(Serialize)]
+struct AuditedPassword {
+    name: String,
+    username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+}
+
+impl AuditedPassword {
+    fn new(password: &Password, show: bool) -> AuditedPassword {
+        AuditedPassword {
+            name: password.name.clone(),
+            username: password.username.clone(),
+            password: if show {
+                Some(password.password.deref().clone())
+            } else {
+                None
+            },
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.password {
+            Some(ref password) => {
+                format!("{} ({}, password: {})", self.name, self.username, password)
+            }
+            None => format!("{} ({})", self.name, self.username),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct WeakPassword {
+    #[serde(flatten)]
+    password: AuditedPassword,
+    score: u8,
+    crack_time: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    warning: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct StalePassword {
+    #[serde(flatten)]
+    password: AuditedPassword,
+    updated_at: ffi::time_t,
+    age_days: u32,
+}
+
+#[derive(Serialize)]
+struct BreachedPassword {
+    #[serde(flatten)]
+    password: AuditedPassword,
+    count: u64,
+}
+
+#[derive(Serialize)]
+struct AuditReport {
+    /// Groups of passwords that are the same
+    reused: Vec<Vec<AuditedPassword>>,
+    weak: Vec<WeakPassword>,
+    stale: Vec<StalePassword>,
+    /// Only there when a breach database was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    breached: Option<Vec<BreachedPassword>>,
+}
+
+impl AuditReport {
+    fn is_empty(&self) -> bool {
+        self.reused.is_empty()
+            && self.weak.is_empty()
+            && self.stale.is_empty()
+            && self
+                .breached
+                .as_ref()
+                .is_none_or(|breached| breached.is_empty())
+    }
+}
+
+fn find_reused(passwords: &[&Password], show: bool) -> Vec<Vec<AuditedPassword>> {
+    let mut groups: Vec<Vec<&Password>> = Vec::new();
+    for password in passwords.iter() {
+        match groups
+            .iter_mut()
+            .find(|group| group[0].password == password.password)
+        {
+            Some(group) => group.push(password),
+            None => groups.push(vec![password]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|group| group.len() > 1)
+        .map(|group| {
+            group
+                .into_iter()
+                .map(|password| AuditedPassword::new(password, show))
+                .collect()
+        })
+        .collect()
+}
+
+fn find_weak(passwords: &[&Password], min_score: u8, show: bool) -> Vec<WeakPassword> {
+    passwords
+        .iter()
+        .filter_map(|password| {
+            let estimate = strength::estimate(
+                password.password.deref(),
+                &[&password.name, &password.username],
+            );
+            if estimate.score >= min_score {
+                return None;
+            }
+            Some(WeakPassword {
+                password: AuditedPassword::new(password, show),
+                score: estimate.score,
+                crack_time: estimate.crack_time(),
+                warning: estimate.warning,
+            })
+        })
+        .collect()
+}
+
+fn find_stale(passwords: &[&Password], max_age_days: u32, show: bool) -> Vec<StalePassword> {
+    let now = ffi::time();
+    passwords
+        .iter()
+        .filter_map(|password| {
+            let age_days = now.saturating_sub(password.updated_at) / SECONDS_PER_DAY;
+            if age_days <= max_age_days {
+                return None;
+            }
+            Some(StalePassword {
+                password: AuditedPassword::new(password, show),
+                updated_at: password.updated_at,
+                age_days,
+            })
+        })
+        .collect()
+}
+
+fn find_breached(
+    passwords: &[&Password],
+    db: &BreachDb,
+    show: bool,
+    io: &mut impl CliInputOutput,
+) -> Result<Vec<BreachedPassword>, i32> {
+    let mut breached = Vec::new();
+    for password in passwords.iter() {
+        let count = db.count(password.password.deref()).map_err(|err| {
+            io.error(
+                format!(
+                    "Woops, I couldn't search the breach database (reason: {}).",
+                    err
+                ),
+                OutputType::Error,
+            );
+            exit_code::ERROR
+        })?;
+        if let Some(count) = count {
+            breached.push(BreachedPassword {
+                password: AuditedPassword::new(password, show),
+                count,
+            });
+        }
+    }
+    Ok(breached)
+}
+
+fn print_report(report: &AuditReport, io: &mut impl CliInputOutput) {
+    if !report.reused.is_empty() {
+        io.title("Reused passwords", OutputType::Standard);
+        for group in report.reused.iter() {
+            io.info(
+                format!(
+                    "The same password is used for {}",
+                    group
+                        .iter()
+                        .map(|password| password.describe())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+                OutputType::Standard,
+            );
+        }
+        io.nl(OutputType::Standard);
+    }
+
+    if !report.weak.is_empty() {
+        io.title("Weak passwords", OutputType::Standard);
+        for weak in report.weak.iter() {
+            io.info(
+                format!(
+                    "{}: could be guessed in about {}. {}",
+                    weak.password.describe(),
+                    weak.crack_time,
+                    weak.warning.unwrap_or("")
+                )
+                .trim_end(),
+                OutputType::Standard,
+            );
+        }
+        io.nl(OutputType::Standard);
+    }
+
+    if !report.stale.is_empty() {
+        io.title("Old passwords", OutputType::Standard);
+        for stale in report.stale.iter() {
+            io.info(
+                format!(
+                    "{}: last changed {} days ago, on {}",
+                    stale.password.describe(),
+                    stale.age_days,
+                    ffi::format_time(stale.updated_at)
+                ),
+                OutputType::Standard,
+            );
+        }
+        io.nl(OutputType::Standard);
+    }
+
+    if let Some(ref breached) = report.breached {
+        if !breached.is_empty() {
+            io.title("Breached passwords", OutputType::Standard);
+            for password in breached.iter() {
+                io.info(
+                    format!(
+                        "{}: seen {} times in data breaches",
+                        password.password.describe(),
+                        password.count
+                    ),
+                    OutputType::Standard,
+                );
+            }
+            io.nl(OutputType::Standard);
+        }
+    }
+}
+
+pub fn callback_exec(
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let show = matches.get_flag("show");
+    let min_score = *matches.get_one::<u8>("min-score").unwrap();
+    let max_age_days = *matches.get_one::<u32>("max-age").unwrap();
+
+    let breach_db = match matches.get_one::<PathBuf>("breach-db") {
+        Some(path) => Some(BreachDb::open(path).map_err(|err| {
+            io.error(
+                format!("Woops, I can't use this breach database (reason: {}).", err),
+                OutputType::Error,
+            );
+            exit_code::ERROR
+        })?),
+        None => None,
+    };
+
+    let passwords = store.get_all_passwords();
+    let breached = match breach_db {
+        Some(ref db) => Some(find_breached(&passwords, db, show, io)?),
+        None => None,
+    };
+    let report = AuditReport {
+        reused: find_reused(&passwords, show),
+        weak: find_weak(&passwords, min_score, show),
+        stale: find_stale(&passwords, max_age_days, show),
+        breached,
+    };
+
+    // --json is a hidden alias of --format json
+    if matches.get_flag("json") || output::format(matches) == output::Format::Json {
+        let report_json = serde_json::to_string(&report).map_err(|json_err| {
+            io.error(
+                format!(
+                    "Woops, I could not encode the audit into JSON (reason: {:?}).",
+                    json_err
+                ),
+                OutputType::Error,
+            );
+            exit_code::ERROR
+        })?;
+        io.writeln(report_json, OutputType::Standard);
+    } else if report.is_empty() {
+        io.success(
+            "Good news, none of your passwords are reused, weak, old or breached.",
+            OutputType::Standard,
+        );
+    } else {
+        print_report(&report, io);
+        if !show {
+            io.info(
+                "Passwords are hidden, see them with --show.",
+                OutputType::Standard,
+            );
+        }
+    }
+
+    if report.is_empty() {
+        Ok(())
+    } else {
+        Err(exit_code::AUDIT_FINDINGS)
+    }
+}
@@ -1,13 +1,17 @@
 use crate::clip;
+use crate::exit_code;
 use crate::ffi;
+use crate::fields;
 use crate::list;
 use crate::password;
+use crate::strength;
 use rclio::CliInputOutput;
 use rclio::OutputType;
+use std::ops::Deref;
 
 pub fn callback_exec(
     matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     let query = matches.get_one::<String>("app").unwrap();
@@ -18,31 +22,60 @@ pub fn callback_exec(
         list::WITH_NUMBERS,
         "Which password would like to update?",
         io,
-    )
-    .ok_or(1)?
+    )?
     .clone();
 
-    let password_as_string = io
-        .prompt_password(format!(
-            "What password do you want for \"{}\"? ",
-            password.name
-        ))
-        .map_err(|err| {
-            io.error(
-                format!("\nI couldn't read the app's password (reason: {:?}).", err),
-                OutputType::Error,
-            );
-            1
-        })?;
+    let removed_fields: Vec<String> = matches
+        .get_many::<String>("remove-field")
+        .map(|names| names.cloned().collect())
+        .unwrap_or_default();
+
+    // When only the URL, notes or custom fields change, we keep the password as it is
+    let mut new_password = password.clone();
+    if fields::given(matches) || !removed_fields.is_empty() {
+        for name in removed_fields.iter() {
+            if !new_password.custom_fields.iter().any(|f| &f.name == name) {
+                io.error(
+                    format!("Woops, there is no field named \"{}\".", name),
+                    OutputType::Error,
+                );
+                return Err(exit_code::ERROR);
+            }
+            new_password.custom_fields.retain(|f| &f.name != name);
+        }
+        fields::apply(matches, &mut new_password, io)?;
+    } else {
+        new_password.password = io
+            .prompt_password(format!(
+                "What password do you want for \"{}\"? ",
+                password.name
+            ))
+            .map_err(|err| {
+                io.error(
+                    format!("\nI couldn't read the app's password (reason: {:?}).", err),
+                    OutputType::Error,
+                );
+                exit_code::ERROR
+            })?;
+        strength::confirm_strength(
+            new_password.password.deref(),
+            &[&password.name, &password.username],
+            "password",
+            io,
+        )?;
+    }
 
     let password = store
-        .change_password(&password.name, &|old_password: password::v2::Password| {
-            password::v2::Password {
-                name: old_password.name,
-                username: old_password.username,
-                password: password_as_string.clone(),
-                created_at: old_password.created_at,
-                updated_at: ffi::time(),
+        .change_password(&password.name, &password.username, &|old_password: password::v3::Password| {
+            // Only a new password counts as an update, not new fields
+            let updated_at = if new_password.password != old_password.password {
+                ffi::time()
+            } else {
+                old_password.updated_at
+            };
+            password::v3::Password {
+                updated_at,
+                ..new_password.clone()
             }
         })
         .map_err(|err| {
@@ -53,10 +86,9 @@ pub fn callback_exec(
                 ),
                 OutputType::Error,
             );
-            1
+            exit_code::ERROR
         })?;
 
-    let show = matches.get_flag("show");
-    clip::confirm_password_retrieved(show, &password, io);
+    clip::confirm_password_retrieved(matches, &password, io);
     Ok(())
 }
@@ -0,0 +1,13 @@
+use crate::clip;
+use crate::exit_code;
+
+/// Runs in the background after a password was copied, see `clip::copy_to_clipboard`. The digest
+/// of the copied value is read from stdin.
+pub fn callback_exec(matches: &clap::ArgMatches) -> Result<(), i32> {
+    let seconds = *matches.get_one::<u64>("seconds").unwrap();
+
+    let mut digest = String::new();
+    std::io::stdin().read_line(&mut digest).map_err(|_| exit_code::ERROR)?;
+
+    clip::clear_clipboard_after(seconds, &digest).map_err(|_| exit_code::ERROR)
+}
@@ -1,3 +1,4 @@
+use crate::exit_code;
 use crate::list;
 use crate::password;
 use rclio::CliInputOutput;
@@ -5,7 +6,7 @@ use rclio::OutputType;
 
 pub fn callback_exec(
     matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     let query = matches.get_one::<String>("app").unwrap();
@@ -16,11 +17,10 @@ pub fn callback_exec(
         list::WITH_NUMBERS,
         "Which password would you like me to delete?",
         io,
-    )
-    .ok_or(1)?
+    )?
     .clone();
 
-    if let Err(err) = store.delete_password(&password.name) {
+    if let Err(err) = store.delete_password(&password.name, &password.username) {
         io.error(
             format!(
                 "Woops, I couldn't delete this password (reason: {:?}).",
@@ -28,7 +28,7 @@ pub fn callback_exec(
             ),
             OutputType::Error,
         );
-        return Err(1);
+        return Err(exit_code::ERROR);
     }
 
     io.success(
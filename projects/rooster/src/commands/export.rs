@@ -1,5 +1,6 @@
+use crate::exit_code;
 use crate::password;
-use crate::password::v2::Password;
+use crate::password::v3::Password;
 use csv::Writer;
 use rclio::CliInputOutput;
 use rclio::OutputType;
@@ -16,7 +17,7 @@ pub struct JsonExport {
 
 pub fn callback_exec(
     matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     let subcommand_name = matches.subcommand_name().unwrap();
@@ -35,7 +36,7 @@ pub fn callback_exec(
 
 fn export_to_csv(
     _matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     let passwords_ref = store.get_all_passwords();
@@ -48,7 +49,7 @@ fn export_to_csv(
             password.password.deref().as_str(),
         ]) {
             Ok(_) => {}
-            Err(_) => return Err(1),
+            Err(_) => return Err(exit_code::ERROR),
         }
     }
     io.write(
@@ -61,7 +62,7 @@ fn export_to_csv(
 
 fn export_to_json(
     _matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     let export = JsonExport {
@@ -81,7 +82,7 @@ fn export_to_json(
                 ),
                 OutputType::Error,
             );
-            return Err(1);
+            return Err(exit_code::ERROR);
         }
     };
 
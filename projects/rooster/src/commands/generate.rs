@@ -1,5 +1,6 @@
-use crate::clip::{copy_to_clipboard, paste_keys};
-use crate::generate::{check_password_len, PasswordSpec};
+use crate::clip::{copy_to_clipboard, paste_keys, print_clear_notice};
+use crate::exit_code;
+use crate::generate::{print_entropy, GenerationPolicy};
 use crate::password;
 use rclio::CliInputOutput;
 use rclio::OutputType;
@@ -8,42 +9,27 @@ use std::ops::Deref;
 
 pub fn callback_exec(
     matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     let app_name = matches.get_one::<String>("app").unwrap();
     let username = matches.get_one::<String>("username").unwrap();
 
-    if store.has_password(app_name.deref()) {
+    if store.has_password(app_name, username) {
         io.error(
-            "Woops: alnum, there is already an app with that name.",
+            "Woops, there is already a password for this app and username.",
             OutputType::Error,
         );
-        return Err(1);
+        return Err(exit_code::ERROR);
     }
 
-    let pwspec = PasswordSpec::new(
-        matches.get_flag("alnum"),
-        check_password_len(*matches.get_one::<usize>("length").unwrap(), io),
-    );
-
-    let password_as_string = match pwspec.generate_hard_password() {
-        Ok(password_as_string) => password_as_string,
-        Err(io_err) => {
-            io.error(
-                format!(
-                    "Woops, I could not generate the password (reason: {:?}).",
-                    io_err
-                ),
-                OutputType::Error,
-            );
-            return Err(1);
-        }
-    };
+    let policy = GenerationPolicy::from_matches(matches, None);
+    let (password_as_string, entropy_bits) = policy.generate(io)?;
 
     // Read the master password and try to save the new password.
     let password_as_string_clipboard = password_as_string.clone();
-    let password = password::v2::Password::new(app_name.clone(), username, password_as_string);
+    let mut password = password::v3::Password::new(app_name.clone(), username, password_as_string);
+    password.generation_policy = Some(policy);
 
     match store.add_password(password) {
         Ok(_) => {
@@ -55,10 +41,12 @@ pub fn callback_exec(
                     ),
                     OutputType::Standard,
                 );
+                print_entropy(entropy_bits, io);
                 return Ok(());
             }
 
-            if copy_to_clipboard(&password_as_string_clipboard).is_err() {
+            let copied = copy_to_clipboard(&password_as_string_clipboard, matches);
+            if copied.is_err() {
                 io.success(
                     format!(
                         "Hmm, I tried to copy your new password to your clipboard, but \
@@ -76,7 +64,9 @@ pub fn callback_exec(
                     ),
                     OutputType::Standard,
                 );
+                print_clear_notice(copied.ok().flatten(), io);
             }
+            print_entropy(entropy_bits, io);
 
             Ok(())
         }
@@ -85,7 +75,7 @@ pub fn callback_exec(
                 format!("\nI couldn't add this password (reason: {:?}).", err),
                 OutputType::Error,
             );
-            Err(1)
+            Err(exit_code::ERROR)
         }
     }
 }
@@ -1,12 +1,14 @@
 use crate::clip;
+use crate::exit_code;
 
 use crate::list;
+use crate::output;
 use crate::password;
-use rclio::CliInputOutput;
+use rclio::{CliInputOutput, OutputType};
 
 pub fn callback_exec(
     matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     let show = matches.get_flag("show");
@@ -20,10 +22,33 @@ pub fn callback_exec(
             "to copy to your clipboard"
         },
     );
-    let password =
-        list::search_and_choose_password(store, query, list::WITH_NUMBERS, &prompt, io).ok_or(1)?;
+    let tag = matches.get_one::<String>("tag").map(|tag| tag.as_str());
+    let password = list::search_and_choose_password_with_tag(
+        store,
+        query,
+        tag,
+        list::WITH_NUMBERS,
+        &prompt,
+        io,
+    )?;
 
-    clip::confirm_password_retrieved(show, &password, io);
+    let format = output::format(matches);
+    if format == output::Format::Text {
+        clip::confirm_password_retrieved(matches, &password, io);
+        return Ok(());
+    }
 
-    Ok(())
+    // The password is only printed when asked for, it goes to the clipboard otherwise.
+    if !show && clip::copy_to_clipboard(&password.password, matches).is_err() {
+        io.error(
+            format!(
+                "Woops, I couldn't copy the password to your clipboard. \
+                 You can see it with `rooster get '{}' --show`.",
+                password.name
+            ),
+            OutputType::Error,
+        );
+        return Err(exit_code::ERROR);
+    }
+    output::print_password(password, show, format, io)
 }
@@ -0,0 +1,97 @@
+use crate::clip;
+use crate::exit_code;
+use crate::ffi;
+use crate::list;
+use crate::password;
+use rclio::{CliInputOutput, OutputType};
+use std::ops::Deref;
+
+pub fn callback_exec(
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let show = matches.get_flag("show");
+    let query = matches.get_one::<String>("app").unwrap();
+
+    let password = list::search_and_choose_password(
+        store,
+        query,
+        list::WITH_NUMBERS,
+        "Which password's history would you like to see? ",
+        io,
+    )?
+    .clone();
+
+    if password.history.is_empty() {
+        io.info(
+            format!("There are no previous passwords for {}.", password.name),
+            OutputType::Standard,
+        );
+        return match matches.get_one::<usize>("restore") {
+            Some(_) => Err(exit_code::ERROR),
+            None => Ok(()),
+        };
+    }
+
+    if let Some(number) = matches.get_one::<usize>("restore") {
+        if *number == 0 || *number > password.history.len() {
+            io.error(
+                format!(
+                    "Woops, there is no previous password number {} for {}. Pick one between 1 and {}.",
+                    number,
+                    password.name,
+                    password.history.len()
+                ),
+                OutputType::Error,
+            );
+            return Err(exit_code::ERROR);
+        }
+
+        let password = store
+            .restore_previous_password(&password.name, &password.username, number - 1)
+            .map_err(|err| {
+                io.error(
+                    format!(
+                        "Woops, I couldn't restore the password (reason: {:?}).",
+                        err
+                    ),
+                    OutputType::Error,
+                );
+                exit_code::ERROR
+            })?;
+
+        clip::confirm_password_retrieved(matches, &password, io);
+        return Ok(());
+    }
+
+    io.title(
+        format!("Previous passwords for {}", password.name),
+        OutputType::Standard,
+    );
+    for (i, previous) in password.history.iter().enumerate() {
+        io.info(
+            format!(
+                "{}. {} (replaced on {})",
+                i + 1,
+                if show {
+                    previous.password.deref().as_str()
+                } else {
+                    "********"
+                },
+                ffi::format_time(previous.replaced_at)
+            ),
+            OutputType::Standard,
+        );
+    }
+    io.nl(OutputType::Standard);
+    io.info(
+        format!(
+            "You can make one the current password again with `rooster history '{}' --restore <number>`.",
+            password.name
+        ),
+        OutputType::Standard,
+    );
+
+    Ok(())
+}
@@ -1,7 +1,8 @@
-use crate::ffi;
+use crate::exit_code;
 use crate::password;
-use crate::password::v2::{Password, PasswordStore};
+use crate::password::v3::{Password, PasswordStore};
 use rclio::{CliInputOutput, OutputType};
+use rtoolbox::safe_string::SafeString;
 use serde::{Deserialize, Serialize};
 use serde_json;
 use std::fs::File;
@@ -13,7 +14,7 @@ pub struct JsonExport {
 
 pub fn callback_exec(
     matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     let subcommand_name = matches.subcommand_name().unwrap();
@@ -49,9 +50,12 @@ fn import_passwords(
         errors += 1;
     }
     for password in valid {
-        if let Some(_) = store.get_password(&password.name) {
+        if store.has_password(&password.name, &password.username) {
             io.warning(
-                format!("{}, already in password store, skipping", password.name),
+                format!(
+                    "{} ({}), already in password store, skipping",
+                    password.name, password.username
+                ),
                 OutputType::Error,
             );
             warnings += 1;
@@ -90,20 +94,14 @@ fn create_imported_passwords_from_csv(
                 format!("Uh oh, could not open or read the file (reason: {})", err),
                 OutputType::Error,
             );
-            1
+            exit_code::ERROR
         })?;
     let mut valid = vec![];
     for record_result in reader.records() {
         if let Ok(record) = record_result {
-            valid.push(Password {
-                name: record[0].into(),
-                username: record[1].into(),
-                password: record[2].into(),
-                created_at: ffi::time(),
-                updated_at: ffi::time(),
-            });
+            valid.push(Password::new(&record[0], &record[1], &record[2]));
         } else {
-            return Err(1);
+            return Err(exit_code::ERROR);
         }
     }
     return Ok((valid, vec![]));
@@ -122,33 +120,25 @@ fn create_imported_passwords_from_1password(
                 format!("Uh oh, could not open or read the file (reason: {})", err),
                 OutputType::Error,
             );
-            1
+            exit_code::ERROR
         })?;
     let mut valid = vec![];
     let mut invalid = vec![];
     for record_result in reader.records() {
         if let Ok(record) = record_result {
             if &record[3] != "Login" {
-                invalid.push(Password {
-                    name: record[2].into(),
-                    username: record[5].into(),
-                    password: record[1].into(),
-                    created_at: ffi::time(),
-                    updated_at: ffi::time(),
-                });
+                invalid.push(Password::new(&record[2], &record[5], &record[1]));
                 continue;
             }
 
             // Fields are, in order: 0/Notes, 1/Password, 2/Title, 3/Type (we can only import "Login"), 4/URL, 5/Username
             valid.push(Password {
-                name: record[2].into(),
-                username: record[5].into(),
-                password: record[1].into(),
-                created_at: ffi::time(),
-                updated_at: ffi::time(),
+                url: Some(record[4].to_string()).filter(|url| !url.is_empty()),
+                notes: Some(record[0].into()).filter(|notes: &SafeString| !notes.is_empty()),
+                ..Password::new(&record[2], &record[5], &record[1])
             });
         } else {
-            return Err(1);
+            return Err(exit_code::ERROR);
         }
     }
     return Ok((valid, invalid));
@@ -164,7 +154,7 @@ fn create_imported_passwords_from_json(
             format!("Uh oh, could not open the file (reason: {})", err),
             OutputType::Error,
         );
-        1
+        exit_code::ERROR
     })?;
     let export: JsonExport = serde_json::from_reader(dump_file).map_err(|json_err| {
         io.error(
@@ -174,7 +164,7 @@ fn create_imported_passwords_from_json(
             ),
             OutputType::Error,
         );
-        1
+        exit_code::ERROR
     })?;
     Ok((export.passwords, vec![]))
 }
@@ -1,7 +1,9 @@
 use rclio::CliInputOutput;
 use rclio::OutputType;
+use std::ops::Deref;
 use std::path::PathBuf;
 use crate::example_environment_variable_configuration;
+use crate::exit_code;
 
 pub fn callback_exec(
     matches: &clap::ArgMatches,
@@ -20,9 +22,16 @@ pub fn callback_exec(
             "Type `rooster --help` to see what Rooster can do for you.",
             OutputType::Error,
         );
-        return Err(1);
+        return Err(exit_code::ERROR);
     }
 
+    // Unlike other commands, ROOSTER_KEYFILE is ignored: a keyfile must be asked for explicitly.
+    let keyfile_digest = match matches.get_one::<PathBuf>("keyfile") {
+        Some(path) => Some(crate::read_keyfile_digest(path, io)?),
+        None => None,
+    };
+    let has_keyfile = keyfile_digest.is_some();
+
     io.title("Welcome to Rooster", OutputType::Standard);
     io.nl(OutputType::Standard);
     io.info("Rooster is a simple password manager. Let's get started! Type ENTER to continue.", OutputType::Standard);
@@ -32,7 +41,7 @@ pub fn callback_exec(
             format!("Woops, I didn't see the ENTER key (reason: {:?}).", err),
             OutputType::Error,
         );
-        return Err(1);
+        return Err(exit_code::ERROR);
     }
 
     io.title("The master password", OutputType::Standard);
@@ -51,15 +60,22 @@ pub fn callback_exec(
                 format!("Woops, I couldn't read the master passwords ({:?}).", err),
                 OutputType::Error,
             );
-            1
+            exit_code::ERROR
         })?;
 
     if master_password.len() == 0 {
         io.error("Your master password cannot be empty.", OutputType::Error);
-        return Err(1);
+        return Err(exit_code::ERROR);
     }
 
-    let store = match crate::password::v2::PasswordStore::new(master_password) {
+    crate::strength::confirm_strength(
+        master_password.deref(),
+        &["rooster"],
+        "master password",
+        io,
+    )?;
+
+    let store = match crate::password::v3::PasswordStore::with_keyfile(master_password, keyfile_digest) {
         Ok(store) => store,
         Err(err) => {
             io.error(
@@ -70,37 +86,11 @@ pub fn callback_exec(
                 ),
                 OutputType::Error,
             );
-            return Err(1);
+            return Err(exit_code::ERROR);
         }
     };
 
-    let mut file = match crate::create_password_file(filename_as_string.as_str()).map_err(|_| 1) {
-        Ok(file) => file,
-        Err(err) => {
-            io.error(
-                format!(
-                    "Woops, I couldn't create a new password file (reason: {:?})",
-                    err
-                ),
-                OutputType::Error,
-            );
-            return Err(1);
-        }
-    };
-
-    if let Err(err) = store.sync(&mut file) {
-        if let Err(err) = ::std::fs::remove_file(rooster_file_path) {
-            io.error(
-                format!(
-                    "Woops, I was able to create a new password file but couldn't save \
-                     it (reason: {:?}). You may want to remove this dangling file:",
-                    err
-                ),
-                OutputType::Error,
-            );
-            io.error(format!("    {}", filename_as_string), OutputType::Error);
-            return Err(1);
-        }
+    if let Err(err) = store.sync(rooster_file_path) {
         io.error(
             format!(
                 "Woops, I couldn't create a new password file (reason: {:?}).",
@@ -108,7 +98,7 @@ pub fn callback_exec(
             ),
             OutputType::Error,
         );
-        return Err(1);
+        return Err(exit_code::ERROR);
     }
 
     io.nl(OutputType::Standard);
@@ -116,6 +106,9 @@ pub fn callback_exec(
     io.nl(OutputType::Standard);
     io.success("You passwords will be saved in:", OutputType::Standard);
     io.success(format!("    {}", filename_as_string), OutputType::Standard);
+    if has_keyfile {
+        crate::commands::set_keyfile::print_keyfile_backup_notice(io);
+    }
     io.nl(OutputType::Standard);
     io.info(
         "If you wish to change the location of your password file, you can set it in the \
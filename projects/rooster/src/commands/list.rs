@@ -1,16 +1,34 @@
 use crate::list;
+use crate::output;
 use crate::password;
 use rclio::CliInputOutput;
 use rclio::OutputType;
 
 pub fn callback_exec(
-    _matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
-    let passwords = store.get_all_passwords();
+    let mut passwords = store.get_all_passwords();
+    let format = output::list_format(matches, io);
 
-    if passwords.len() == 0 {
+    if let Some(tag) = matches.get_one::<String>("tag") {
+        passwords.retain(|p| p.has_tag(tag));
+        if passwords.is_empty() && format == output::Format::Text {
+            io.info(
+                format!(
+                    "No passwords tagged \"{}\" yet. Tag one with `rooster tag add <app> {}`.",
+                    tag, tag
+                ),
+                OutputType::Standard,
+            );
+            return Ok(());
+        }
+    }
+
+    if format != output::Format::Text {
+        output::print_passwords(&passwords, false, format, io)?;
+    } else if passwords.len() == 0 {
         io.info(
             "No passwords on record yet. Add one with `rooster add <app> <username>`.",
             OutputType::Standard,
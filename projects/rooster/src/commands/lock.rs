@@ -0,0 +1,32 @@
+use crate::agent;
+use crate::exit_code;
+use rclio::{CliInputOutput, OutputType};
+use std::io::ErrorKind;
+
+pub fn callback_exec(io: &mut impl CliInputOutput) -> Result<(), i32> {
+    match agent::lock(&agent::socket_path()) {
+        Ok(()) => {
+            io.success(
+                "Done, the agent forgot your master password.",
+                OutputType::Standard,
+            );
+            Ok(())
+        }
+        Err(err)
+            if err.kind() == ErrorKind::NotFound || err.kind() == ErrorKind::ConnectionRefused =>
+        {
+            io.info(
+                "The agent is not running, so there is nothing to lock.",
+                OutputType::Standard,
+            );
+            Ok(())
+        }
+        Err(err) => {
+            io.error(
+                format!("Woops, I couldn't talk to the agent (reason: {}).", err),
+                OutputType::Error,
+            );
+            Err(exit_code::ERROR)
+        }
+    }
+}
@@ -1,14 +1,28 @@
 pub mod add;
+#[cfg(unix)]
+pub mod agent;
+pub mod audit;
 pub mod change;
+pub mod clear_clipboard;
 pub mod delete;
 pub mod export;
 pub mod generate;
 pub mod get;
+pub mod history;
 pub mod import;
 pub mod init;
 pub mod list;
+#[cfg(unix)]
+pub mod lock;
+pub mod otp;
+pub mod passphrase;
 pub mod regenerate;
 pub mod rename;
+pub mod search;
+pub mod set_kdf;
+pub mod set_keyfile;
 pub mod set_master_password;
 pub mod set_scrypt_params;
+pub mod slot;
+pub mod tag;
 pub mod transfer;
@@ -0,0 +1,80 @@
+use crate::clip::{copy_to_clipboard, paste_keys, print_clear_notice};
+use crate::exit_code;
+use crate::ffi;
+use crate::list;
+use crate::password;
+use rclio::{CliInputOutput, OutputType};
+use rtoolbox::safe_string::SafeString;
+
+pub fn callback_exec(
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let show = matches.get_flag("show");
+    let query = matches.get_one::<String>("app").unwrap();
+
+    let password = list::search_and_choose_password(
+        store,
+        query,
+        list::WITH_NUMBERS,
+        "Which one-time code would you like? ",
+        io,
+    )?;
+
+    let otp = match password.otp {
+        Some(ref otp) => otp,
+        None => {
+            io.error(
+                format!(
+                    "Woops, there is no secret for one-time codes for {}. You can add one with `rooster change '{}' --otp`.",
+                    password.name, password.name
+                ),
+                OutputType::Error,
+            );
+            return Err(exit_code::ERROR);
+        }
+    };
+
+    let now = ffi::time() as u64;
+    let code = SafeString::from_string(otp.code_at(now));
+    let seconds_remaining = otp.seconds_remaining(now);
+
+    if show {
+        io.success(
+            format!(
+                "Alright! Here is your one-time code for {}: {} (valid for {} more seconds)",
+                password.name,
+                code.as_str(),
+                seconds_remaining
+            ),
+            OutputType::Standard,
+        );
+    } else {
+        match copy_to_clipboard(&code, matches) {
+            Err(_) => io.success(
+                format!(
+                    "Hmm, I tried to copy your one-time code to your clipboard, but something went \
+                     wrong. You can see it with `rooster otp '{}' --show`",
+                    password.name
+                ),
+                OutputType::Standard,
+            ),
+            Ok(clear_after) => {
+                io.success(
+                    format!(
+                        "Alright! Your one-time code for {} is in your clipboard, paste it with {} \
+                         (valid for {} more seconds)",
+                        password.name,
+                        paste_keys(),
+                        seconds_remaining
+                    ),
+                    OutputType::Standard,
+                );
+                print_clear_notice(clear_after, io);
+            }
+        }
+    }
+
+    Ok(())
+}
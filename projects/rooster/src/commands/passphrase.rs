@@ -0,0 +1,40 @@
+use crate::clip::{copy_to_clipboard, paste_keys, print_clear_notice};
+use crate::exit_code;
+use crate::generate::{check_passphrase_words, print_entropy, PassphraseSpec};
+use rclio::CliInputOutput;
+use rclio::OutputType;
+
+use std::ops::Deref;
+
+pub fn callback_exec(matches: &clap::ArgMatches, io: &mut impl CliInputOutput) -> Result<(), i32> {
+    let spec = PassphraseSpec::from_matches(matches).unwrap();
+    check_passphrase_words(spec.words, io)?;
+
+    let passphrase = spec.generate_passphrase();
+
+    if matches.get_flag("show") {
+        io.success(
+            format!("Alright! Here is your passphrase: {}", passphrase.deref()),
+            OutputType::Standard,
+        );
+    } else {
+        let clear_after = copy_to_clipboard(&passphrase, matches).map_err(|_| {
+            io.error(
+                "Woops, I couldn't copy your passphrase to your clipboard. Try again with --show.",
+                OutputType::Error,
+            );
+            exit_code::ERROR
+        })?;
+        io.success(
+            format!(
+                "Alright! Your passphrase is in your clipboard, paste it with {}. I didn't save it anywhere.",
+                paste_keys()
+            ),
+            OutputType::Standard,
+        );
+        print_clear_notice(clear_after, io);
+    }
+    print_entropy(Some(spec.entropy_bits()), io);
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use crate::clip;
+use crate::exit_code;
 use crate::ffi;
-use crate::generate::{check_password_len, PasswordSpec};
+use crate::generate::{print_entropy, GenerationPolicy};
 use crate::list;
 use crate::password;
 use rclio::CliInputOutput;
@@ -8,7 +9,7 @@ use rclio::OutputType;
 
 pub fn callback_exec(
     matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     let query = matches.get_one::<String>("app").unwrap();
@@ -19,44 +20,27 @@ pub fn callback_exec(
         list::WITH_NUMBERS,
         "Which password would you like to regenerate?",
         io,
-    )
-    .ok_or(1)?
+    )?
     .clone();
 
-    let pwspec = PasswordSpec::new(
-        matches.get_flag("alnum"),
-        check_password_len(*matches.get_one::<usize>("length").unwrap(), io),
-    );
-
-    let password_as_string = match pwspec.generate_hard_password() {
-        Ok(password_as_string) => password_as_string,
-        Err(io_err) => {
-            io.error(
-                format!(
-                    "Woops, I could not generate the password (reason: {:?}).",
-                    io_err
-                ),
-                OutputType::Error,
-            );
-            return Err(1);
-        }
-    };
+    // Make the same kind of password as last time, unless asked otherwise
+    let policy = GenerationPolicy::from_matches(matches, password.generation_policy.as_ref());
+    let (password_as_string, entropy_bits) = policy.generate(io)?;
 
     let change_result =
-        store.change_password(&password.name, &|old_password: password::v2::Password| {
-            password::v2::Password {
-                name: old_password.name.clone(),
-                username: old_password.username.clone(),
+        store.change_password(&password.name, &password.username, &|old_password: password::v3::Password| {
+            password::v3::Password {
                 password: password_as_string.clone(),
-                created_at: old_password.created_at,
                 updated_at: ffi::time(),
+                generation_policy: Some(policy.clone()),
+                ..old_password
             }
         });
 
     match change_result {
         Ok(password) => {
-            let show = matches.get_flag("show");
-            clip::confirm_password_retrieved(show, &password, io);
+            clip::confirm_password_retrieved(matches, &password, io);
+            print_entropy(entropy_bits, io);
             Ok(())
         }
         Err(err) => {
@@ -67,7 +51,7 @@ pub fn callback_exec(
                 ),
                 OutputType::Error,
             );
-            Err(1)
+            Err(exit_code::ERROR)
         }
     }
 }
@@ -1,3 +1,4 @@
+use crate::exit_code;
 use crate::ffi;
 use crate::list;
 use crate::password;
@@ -6,7 +7,7 @@ use rclio::OutputType;
 
 pub fn callback_exec(
     matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     let query = matches.get_one::<String>("app").unwrap();
@@ -18,18 +19,15 @@ pub fn callback_exec(
         list::WITH_NUMBERS,
         "Which password would you like to rename?",
         io,
-    )
-    .ok_or(1)?
+    )?
     .clone();
 
     let change_result =
-        store.change_password(&password.name, &|old_password: password::v2::Password| {
-            password::v2::Password {
+        store.change_password(&password.name, &password.username, &|old_password: password::v3::Password| {
+            password::v3::Password {
                 name: new_name.clone(),
-                username: old_password.username.clone(),
-                password: old_password.password.clone(),
-                created_at: old_password.created_at,
                 updated_at: ffi::time(),
+                ..old_password
             }
         });
 
@@ -49,7 +47,7 @@ pub fn callback_exec(
                 ),
                 OutputType::Error,
             );
-            Err(1)
+            Err(exit_code::ERROR)
         }
     }
 }
@@ -0,0 +1,31 @@
+use crate::list;
+use crate::output;
+use crate::password;
+use rclio::CliInputOutput;
+use rclio::OutputType;
+
+pub fn callback_exec(
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let query = matches.get_one::<String>("app").unwrap();
+    let mut passwords = store.search_passwords(query);
+    if let Some(tag) = matches.get_one::<String>("tag") {
+        passwords.retain(|p| p.has_tag(tag));
+    }
+
+    let format = output::list_format(matches, io);
+    if format != output::Format::Text {
+        output::print_passwords(&passwords, false, format, io)?;
+    } else if passwords.is_empty() {
+        io.info(
+            format!("No passwords match \"{}\".", query),
+            OutputType::Standard,
+        );
+    } else {
+        list::print_list_of_passwords(&passwords, list::WITHOUT_NUMBERS, io);
+    }
+
+    Ok(())
+}
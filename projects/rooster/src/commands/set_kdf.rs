@@ -0,0 +1,92 @@
+use crate::exit_code;
+use crate::password;
+use crate::password::kdf::{
+    Kdf, ARGON2ID_PARAM_ITERATIONS, ARGON2ID_PARAM_MEMORY_KIB, ARGON2ID_PARAM_PARALLELISM,
+};
+use rclio::{CliInputOutput, OutputType};
+
+pub fn callback_exec(
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let subcommand_name = matches.subcommand_name().unwrap();
+    let subcommand_matches = matches.subcommand_matches(subcommand_name).unwrap();
+
+    if subcommand_name == "argon2id" {
+        set_argon2id(subcommand_matches, store, io)
+    } else {
+        unreachable!("Invalid key derivation function")
+    }
+}
+
+fn set_argon2id(
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let memory_kib = matches
+        .get_one::<u32>("memory")
+        .copied()
+        .unwrap_or(ARGON2ID_PARAM_MEMORY_KIB);
+    let iterations = matches
+        .get_one::<u32>("iterations")
+        .copied()
+        .unwrap_or(ARGON2ID_PARAM_ITERATIONS);
+    let parallelism = matches
+        .get_one::<u32>("parallelism")
+        .copied()
+        .unwrap_or(ARGON2ID_PARAM_PARALLELISM);
+
+    if memory_kib == 0 || iterations == 0 || parallelism == 0 {
+        io.error(
+            format!(
+                "The parameters must be > 0 ({}, {}, {})",
+                memory_kib, iterations, parallelism
+            ),
+            OutputType::Error,
+        );
+        return Err(exit_code::ERROR);
+    }
+
+    if !matches.get_flag("force")
+        && (memory_kib > 1024 * 1024 || iterations > 10 || parallelism > 8)
+    {
+        io.error("These parameters seem very high. You might be unable to open your password file ever again. Aborting.", OutputType::Error);
+        io.error(
+            "Run with --force to force, but make a backup of your password file first.",
+            OutputType::Error,
+        );
+        return Err(exit_code::ERROR);
+    }
+
+    let kdf = Kdf::Argon2id {
+        memory_kib,
+        iterations,
+        parallelism,
+    };
+    if let Err(err) = kdf.validate() {
+        io.error(
+            format!("These parameters are invalid ({}).", err),
+            OutputType::Error,
+        );
+        return Err(exit_code::ERROR);
+    }
+
+    store.change_kdf(kdf).map_err(|err| {
+        io.error(
+            format!(
+                "Woops, I couldn't change the key derivation function (reason: {:?}).",
+                err
+            ),
+            OutputType::Error,
+        );
+        exit_code::ERROR
+    })?;
+
+    io.success(
+        "Your password file is now protected with Argon2id.",
+        OutputType::Standard,
+    );
+    Ok(())
+}
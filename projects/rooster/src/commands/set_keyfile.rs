@@ -0,0 +1,73 @@
+use crate::exit_code;
+use crate::password;
+use rclio::{CliInputOutput, OutputType};
+use std::path::PathBuf;
+
+pub fn callback_exec(
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    if matches.get_flag("remove") {
+        let has_keyfile = store.has_keyfile().map_err(|err| {
+            io.error(
+                format!("Woops, I couldn't check your keyfile (reason: {:?}).", err),
+                OutputType::Error,
+            );
+            exit_code::ERROR
+        })?;
+        if !has_keyfile {
+            io.error(
+                "Woops, your password file doesn't need a keyfile.",
+                OutputType::Error,
+            );
+            return Err(exit_code::ERROR);
+        }
+        store.change_keyfile(None).map_err(|err| {
+            io.error(
+                format!("Woops, I couldn't remove the keyfile (reason: {:?}).", err),
+                OutputType::Error,
+            );
+            exit_code::ERROR
+        })?;
+        io.success(
+            "Your password file now only needs your master password.",
+            OutputType::Standard,
+        );
+        return Ok(());
+    }
+
+    let path = matches.get_one::<PathBuf>("path").unwrap();
+    let keyfile_digest = password::keyfile::digest(path).map_err(|err| {
+        io.error(
+            format!(
+                "Woops, I could not read your keyfile {} (reason: {}).",
+                path.display(),
+                err
+            ),
+            OutputType::Error,
+        );
+        exit_code::ERROR
+    })?;
+    store.change_keyfile(Some(keyfile_digest)).map_err(|err| {
+        io.error(
+            format!("Woops, I couldn't change the keyfile (reason: {:?}).", err),
+            OutputType::Error,
+        );
+        exit_code::ERROR
+    })?;
+
+    io.success(
+        "Your password file now needs this keyfile along with your master password.",
+        OutputType::Standard,
+    );
+    print_keyfile_backup_notice(io);
+    Ok(())
+}
+
+pub fn print_keyfile_backup_notice(io: &mut impl CliInputOutput) {
+    io.info(
+        "Keep a backup of the keyfile: without it, your passwords are lost.",
+        OutputType::Standard,
+    );
+}
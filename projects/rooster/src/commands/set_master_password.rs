@@ -1,11 +1,13 @@
+use crate::exit_code;
 use crate::password;
+use crate::strength;
 use rclio::CliInputOutput;
 use rclio::OutputType;
 use std::ops::Deref;
 
 pub fn callback_exec(
     _matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
     match io.prompt_password("Type your new master password: ") {
@@ -21,7 +23,7 @@ pub fn callback_exec(
                             ),
                             OutputType::Error,
                         );
-                        return Err(1);
+                        return Err(exit_code::ERROR);
                     }
                 };
 
@@ -30,10 +32,28 @@ pub fn callback_exec(
                     "The master password confirmation did not match. Aborting.",
                     OutputType::Error,
                 );
-                return Err(1);
+                return Err(exit_code::ERROR);
             }
 
-            store.change_master_password(master_password.deref());
+            strength::confirm_strength(
+                master_password.deref(),
+                &["rooster"],
+                "master password",
+                io,
+            )?;
+
+            store
+                .change_master_password(master_password.deref())
+                .map_err(|err| {
+                    io.error(
+                        format!(
+                            "Woops, I couldn't change your master password (reason: {:?}).",
+                            err
+                        ),
+                        OutputType::Error,
+                    );
+                    exit_code::ERROR
+                })?;
         }
         Err(err) => {
             io.error(
@@ -43,7 +63,7 @@ pub fn callback_exec(
                 ),
                 OutputType::Error,
             );
-            return Err(1);
+            return Err(exit_code::ERROR);
         }
     }
     io.success(
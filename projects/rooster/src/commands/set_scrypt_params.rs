@@ -1,11 +1,19 @@
+use crate::exit_code;
 use crate::password;
+use crate::password::kdf::{self, Kdf};
 use rclio::{CliInputOutput, OutputType};
+use std::time::Duration;
 
 pub fn callback_exec(
     matches: &clap::ArgMatches,
-    store: &mut password::v2::PasswordStore,
+    store: &mut password::v3::PasswordStore,
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
+    if let Some(target_ms) = matches.get_one::<u32>("target-ms") {
+        let max_memory_mb = *matches.get_one::<u32>("max-memory-mb").unwrap();
+        return calibrate(*target_ms, max_memory_mb, store, io);
+    }
+
     let log2_n = *matches.get_one::<u8>("log2n").unwrap();
     let r = *matches.get_one::<u32>("r").unwrap();
     let p = *matches.get_one::<u32>("p").unwrap();
@@ -15,7 +23,7 @@ pub fn callback_exec(
             format!("The parameters must be > 0 ({}, {}, {})", log2_n, r, p),
             OutputType::Error,
         );
-        return Err(1);
+        return Err(exit_code::ERROR);
     }
 
     if !matches.get_flag("force") && (log2_n > 20 || r > 8 || p > 1) {
@@ -24,10 +32,155 @@ pub fn callback_exec(
             "Run with --force to force, but make a backup of your password file first.",
             OutputType::Error,
         );
-        return Err(1);
+        return Err(exit_code::ERROR);
+    }
+
+    let kdf = Kdf::Scrypt { log2_n, r, p };
+    if let Err(err) = kdf.validate() {
+        io.error(
+            format!("These parameters are invalid ({}).", err),
+            OutputType::Error,
+        );
+        return Err(exit_code::ERROR);
     }
 
-    store.change_scrypt_params(log2_n, r, p);
+    store.change_kdf(kdf).map_err(|err| {
+        io.error(
+            format!(
+                "Woops, I couldn't change the key derivation parameters (reason: {:?}).",
+                err
+            ),
+            OutputType::Error,
+        );
+        exit_code::ERROR
+    })?;
+
+    Ok(())
+}
+
+fn calibrate(
+    target_ms: u32,
+    max_memory_mb: u32,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    if target_ms == 0 || max_memory_mb == 0 {
+        io.error(
+            format!(
+                "The target time and memory must be > 0 ({}, {})",
+                target_ms, max_memory_mb
+            ),
+            OutputType::Error,
+        );
+        return Err(exit_code::ERROR);
+    }
+
+    // Switching to scrypt without saying so would quietly drop the Argon2id parameters
+    let current_kdf = store.kdf().map_err(|err| {
+        io.error(
+            format!(
+                "Woops, I couldn't read the key derivation parameters (reason: {:?}).",
+                err
+            ),
+            OutputType::Error,
+        );
+        exit_code::ERROR
+    })?;
+    if let Kdf::Argon2id { .. } = current_kdf {
+        io.error(
+            "Woops, your password file uses Argon2id, and --target-ms only picks scrypt parameters.",
+            OutputType::Error,
+        );
+        io.error(
+            "Run `rooster set-scrypt-params 12 8 1` first if you want to switch to scrypt.",
+            OutputType::Error,
+        );
+        return Err(exit_code::ERROR);
+    }
+
+    io.info(
+        "Measuring how fast this machine is, this may take a few seconds...",
+        OutputType::Standard,
+    );
+
+    let (kdf, duration) = kdf::calibrate_scrypt(
+        Duration::from_millis(target_ms as u64),
+        max_memory_mb as u64 * 1024 * 1024,
+        kdf::benchmark,
+    );
+    let (log2_n, r, p) = match kdf {
+        Kdf::Scrypt { log2_n, r, p } => (log2_n, r, p),
+        Kdf::Argon2id { .. } => unreachable!(),
+    };
+
+    io.info(
+        format!(
+            "With log2n = {}, r = {}, p = {}, unlocking your password file takes about {} ms and {} MiB of memory on this machine.",
+            log2_n,
+            r,
+            p,
+            duration.as_millis(),
+            kdf::scrypt_memory_bytes(log2_n, r) / 1024 / 1024
+        ),
+        OutputType::Standard,
+    );
+    if duration > Duration::from_millis(target_ms as u64) {
+        io.info(
+            "This is slower than you asked for, but I won't go below the default parameters.",
+            OutputType::Standard,
+        );
+    }
+    if kdf::scrypt_memory_bytes(log2_n, r) > max_memory_mb as u64 * 1024 * 1024 {
+        io.info(
+            "This needs more memory than you allowed, but I won't go below the default parameters.",
+            OutputType::Standard,
+        );
+    }
+    io.info("Use these parameters? [y/n]", OutputType::Standard);
+
+    loop {
+        match io.read_line() {
+            Ok(line) => {
+                if line.starts_with('y') {
+                    break;
+                } else if line.starts_with('n') {
+                    io.error("Okay, I did not change anything.", OutputType::Error);
+                    return Err(exit_code::ERROR);
+                } else {
+                    io.info(
+                        "I did not get that. Use these parameters? [y/n]",
+                        OutputType::Standard,
+                    );
+                }
+            }
+            Err(err) => {
+                io.error(
+                    format!(
+                        "Woops, an error occured while reading your response (reason: {:?}).",
+                        err
+                    ),
+                    OutputType::Error,
+                );
+                return Err(exit_code::ERROR);
+            }
+        }
+    }
+
+    store.change_kdf(kdf).map_err(|err| {
+        io.error(
+            format!(
+                "Woops, I couldn't change the key derivation parameters (reason: {:?}).",
+                err
+            ),
+            OutputType::Error,
+        );
+        exit_code::ERROR
+    })?;
+
+    io.success(
+        "Done! Your password file will use these parameters from now on.",
+        OutputType::Standard,
+    );
 
     Ok(())
 }
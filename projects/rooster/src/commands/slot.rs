@@ -0,0 +1,218 @@
+use crate::exit_code;
+use crate::password;
+use crate::password::kdf::Kdf;
+use crate::password::v3::{DEFAULT_KEY_SLOT, MAX_KEY_SLOT_NAME_LEN};
+use crate::password::PasswordError;
+use crate::strength;
+use rclio::{CliInputOutput, OutputType};
+use std::ops::Deref;
+
+pub fn callback_exec(
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let subcommand_name = matches.subcommand_name().unwrap();
+    let subcommand_matches = matches.subcommand_matches(subcommand_name).unwrap();
+
+    match subcommand_name {
+        "add" => add(subcommand_matches, store, io),
+        "remove" => remove(subcommand_matches, store, io),
+        "list" => list(store, io),
+        _ => unreachable!("Invalid slot command"),
+    }
+}
+
+fn add(
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let name = matches.get_one::<String>("name").unwrap();
+    store.check_new_key_slot_name(name).map_err(|err| {
+        match err {
+            PasswordError::InvalidKeySlotNameError => io.error(
+                format!(
+                    "Woops, key slot names must be 1 to {} bytes long.",
+                    MAX_KEY_SLOT_NAME_LEN
+                ),
+                OutputType::Error,
+            ),
+            PasswordError::KeySlotExistsError => io.error(
+                format!("Woops, there is already a key slot named \"{}\".", name),
+                OutputType::Error,
+            ),
+            err => io.error(
+                format!("Woops, I could not add the key slot (reason: {:?}).", err),
+                OutputType::Error,
+            ),
+        }
+        exit_code::ERROR
+    })?;
+
+    let read_error = |err| {
+        format!(
+            "I could not read the master password of the new key slot (reason: {:?}).",
+            err
+        )
+    };
+    let master_password = io
+        .prompt_password(format!("Type the master password for \"{}\": ", name))
+        .map_err(|err| {
+            io.error(read_error(err), OutputType::Error);
+            exit_code::ERROR
+        })?;
+    let master_password_confirmation = io
+        .prompt_password(format!(
+            "Type the master password for \"{}\" once more: ",
+            name
+        ))
+        .map_err(|err| {
+            io.error(read_error(err), OutputType::Error);
+            exit_code::ERROR
+        })?;
+    if master_password != master_password_confirmation {
+        io.error(
+            "The master password confirmation did not match. Aborting.",
+            OutputType::Error,
+        );
+        return Err(exit_code::ERROR);
+    }
+    if master_password.is_empty() {
+        io.error("The master password cannot be empty.", OutputType::Error);
+        return Err(exit_code::ERROR);
+    }
+    strength::confirm_strength(
+        master_password.deref(),
+        &["rooster", name],
+        "master password",
+        io,
+    )?;
+
+    let first_slot = store.key_slots().is_empty();
+    store
+        .add_key_slot(name, master_password.deref())
+        .map_err(|err| {
+            io.error(
+                format!("Woops, I could not add the key slot (reason: {:?}).", err),
+                OutputType::Error,
+            );
+            exit_code::ERROR
+        })?;
+
+    if first_slot {
+        io.info(
+            format!(
+                "Your master password is now in the key slot named \"{}\".",
+                DEFAULT_KEY_SLOT
+            ),
+            OutputType::Standard,
+        );
+    }
+    io.success(
+        format!(
+            "Done! The master password of \"{}\" opens your password file too.",
+            name
+        ),
+        OutputType::Standard,
+    );
+    if let Ok(true) = store.has_keyfile() {
+        io.info(
+            "It needs your keyfile too, give them a copy.",
+            OutputType::Standard,
+        );
+    }
+    Ok(())
+}
+
+fn remove(
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let name = matches.get_one::<String>("name").unwrap();
+    match store.remove_key_slot(name) {
+        Ok(()) => {}
+        Err(PasswordError::NoSuchKeySlotError) => {
+            io.error(
+                format!("Woops, there is no key slot named \"{}\".", name),
+                OutputType::Error,
+            );
+            return Err(exit_code::ERROR);
+        }
+        Err(PasswordError::LastKeySlotError) => {
+            io.error(
+                "Woops, this is the last key slot. Without it, nobody could open your password file.",
+                OutputType::Error,
+            );
+            return Err(exit_code::ERROR);
+        }
+        Err(err) => {
+            io.error(
+                format!(
+                    "Woops, I could not remove the key slot (reason: {:?}).",
+                    err
+                ),
+                OutputType::Error,
+            );
+            return Err(exit_code::ERROR);
+        }
+    }
+
+    io.success(
+        format!(
+            "Done! The master password of \"{}\" can't open your password file anymore.",
+            name
+        ),
+        OutputType::Standard,
+    );
+    io.info(
+        "Copies of your password file made before still open with it.",
+        OutputType::Standard,
+    );
+    Ok(())
+}
+
+fn describe_kdf(kdf: &Kdf) -> String {
+    match *kdf {
+        Kdf::Scrypt { log2_n, r, p } => format!("scrypt (log2(N) {}, r {}, p {})", log2_n, r, p),
+        Kdf::Argon2id {
+            memory_kib,
+            iterations,
+            parallelism,
+        } => format!(
+            "argon2id ({} KiB, {} iterations, parallelism {})",
+            memory_kib, iterations, parallelism
+        ),
+    }
+}
+
+fn list(store: &password::v3::PasswordStore, io: &mut impl CliInputOutput) -> Result<(), i32> {
+    let slots = store.key_slots();
+    if slots.is_empty() {
+        io.info(
+            "No key slots yet, only your master password opens your password file. \
+             Add one with `rooster slot add <name>`.",
+            OutputType::Standard,
+        );
+        return Ok(());
+    }
+
+    let longest_name = slots.iter().map(|slot| slot.name().len()).max().unwrap();
+    for slot in slots.iter() {
+        let mut line = format!(
+            "{:width$}  {}",
+            slot.name(),
+            describe_kdf(slot.kdf()),
+            width = longest_name
+        );
+        if slot.has_keyfile() {
+            line.push_str(", with a keyfile");
+        }
+        if store.current_key_slot() == Some(slot.name()) {
+            line.push_str(" (yours)");
+        }
+        io.writeln(line, OutputType::Standard);
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use crate::exit_code;
+use crate::list;
+use crate::password;
+use rclio::{CliInputOutput, OutputType};
+
+pub fn callback_exec(
+    matches: &clap::ArgMatches,
+    store: &mut password::v3::PasswordStore,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let subcommand_name = matches.subcommand_name().unwrap();
+    let subcommand_matches = matches.subcommand_matches(subcommand_name).unwrap();
+
+    let query = subcommand_matches.get_one::<String>("app").unwrap();
+    let tag = subcommand_matches.get_one::<String>("tag").unwrap().trim();
+
+    if tag.is_empty() {
+        io.error("Woops, tags can't be empty.", OutputType::Error);
+        return Err(exit_code::ERROR);
+    }
+
+    let password = list::search_and_choose_password(
+        store,
+        query,
+        list::WITH_NUMBERS,
+        "Which password would you like to tag?",
+        io,
+    )?
+    .clone();
+
+    if subcommand_name == "add" {
+        if password.has_tag(tag) {
+            io.info(
+                format!("{} is already tagged \"{}\".", password.name, tag),
+                OutputType::Standard,
+            );
+            return Ok(());
+        }
+    } else if subcommand_name == "remove" {
+        if !password.has_tag(tag) {
+            io.error(
+                format!("Woops, {} isn't tagged \"{}\".", password.name, tag),
+                OutputType::Error,
+            );
+            return Err(exit_code::ERROR);
+        }
+    } else {
+        unreachable!("Invalid tag action")
+    }
+
+    let change_result = store.change_password(
+        &password.name,
+        &password.username,
+        &|mut old_password: password::v3::Password| {
+            if subcommand_name == "add" {
+                old_password.tags.push(tag.to_owned());
+            } else {
+                old_password
+                    .tags
+                    .retain(|t| t.to_lowercase() != tag.to_lowercase());
+            }
+            old_password
+        },
+    );
+
+    match change_result {
+        Ok(password) => {
+            io.success(
+                if password.tags.is_empty() {
+                    format!("Done! {} has no tags anymore.", password.name)
+                } else {
+                    format!(
+                        "Done! {} is now tagged: {}",
+                        password.name,
+                        password.tags.join(", ")
+                    )
+                },
+                OutputType::Standard,
+            );
+            Ok(())
+        }
+        Err(err) => {
+            io.error(
+                format!("Woops, I couldn't save the tags (reason: {:?}).", err),
+                OutputType::Error,
+            );
+            Err(exit_code::ERROR)
+        }
+    }
+}
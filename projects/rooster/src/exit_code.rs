@@ -0,0 +1,36 @@
+//! The exit codes of Rooster, so that scripts can tell failures apart. They are listed in the
+//! README too, keep both in sync.
+
+/// Anything that doesn't have a code of its own
+pub const ERROR: i32 = 1;
+
+/// `rooster audit` found reused, weak, old or breached passwords
+pub const AUDIT_FINDINGS: i32 = 2;
+
+/// The command line arguments are invalid
+pub const USAGE: i32 = 3;
+
+/// The master password is wrong, or the password file was damaged in a way that looks the same
+pub const WRONG_MASTER_PASSWORD: i32 = 4;
+
+/// There is no password file yet
+pub const NO_PASSWORD_FILE: i32 = 5;
+
+/// The password file can't be opened: it can't be read, is corrupted, is too recent for this
+/// version of Rooster, or needs an upgrade that was declined
+pub const UNREADABLE_PASSWORD_FILE: i32 = 6;
+
+/// Another Rooster process is using the password file
+pub const PASSWORD_FILE_IN_USE: i32 = 7;
+
+/// Another program changed the password file while Rooster was running, so nothing was saved
+pub const PASSWORD_FILE_CHANGED: i32 = 8;
+
+/// No password matches the app that was asked for
+pub const NO_MATCHING_PASSWORD: i32 = 9;
+
+/// Rooster needed to ask something, but runs with `--non-interactive`
+pub const INPUT_REQUIRED: i32 = 10;
+
+/// The password file needs a keyfile, but none was given
+pub const KEYFILE_REQUIRED: i32 = 11;
@@ -20,9 +20,44 @@ pub fn time() -> time_t {
     retrieved_time
 }
 
+/// Formats a timestamp as a UTC date and time, like "2020-11-16 19:16 UTC".
+pub fn format_time(t: time_t) -> String {
+    let days = (t / 86400) as i64;
+    let seconds_in_day = t % 86400;
+
+    // Converts days since 1970-01-01 to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds_in_day / 3600,
+        seconds_in_day % 3600 / 60
+    )
+}
+
 #[cfg(test)]
 mod test {
-    use crate::ffi::time;
+    use crate::ffi::{format_time, time};
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_time(951782400), "2000-02-29 00:00 UTC");
+        assert_eq!(format_time(1605554169), "2020-11-16 19:16 UTC");
+        assert_eq!(format_time(4102444799), "2099-12-31 23:59 UTC");
+    }
 
     #[test]
     fn test_time() {
@@ -0,0 +1,260 @@
+//! Command line handling of the extra information stored with a password: URL, notes, custom
+//! fields and one-time code secret.
+
+use crate::exit_code;
+use crate::password::v3::{CustomField, Password};
+use crate::totp::Totp;
+use crate::validate_arg_u32;
+use clap::{Arg, ArgAction};
+use rclio::{CliInputOutput, OutputType};
+use rtoolbox::safe_string::SafeString;
+use std::ops::Deref;
+
+fn validate_arg_field(v: &str) -> Result<(String, String), String> {
+    match v.find('=') {
+        Some(index) if index > 0 => Ok((v[..index].to_string(), v[index + 1..].to_string())),
+        _ => Err(String::from("The value must look like NAME=VALUE")),
+    }
+}
+
+/// Arguments shared by commands that create or edit a password.
+pub fn args() -> Vec<Arg> {
+    vec![
+        Arg::new("url")
+            .long("url")
+            .help("The address of the website (an empty value removes it)"),
+        Arg::new("notes")
+            .long("notes")
+            .help("Free-form notes, like recovery codes (an empty value removes them)"),
+        Arg::new("field")
+            .long("field")
+            .action(ArgAction::Append)
+            .value_name("NAME=VALUE")
+            .help("Set a custom field, shown along with the username")
+            .value_parser(validate_arg_field),
+        Arg::new("concealed-field")
+            .long("concealed-field")
+            .action(ArgAction::Append)
+            .value_name("NAME")
+            .help(
+                "Set a custom field that is only shown with --show, you'll be asked for its value",
+            ),
+        Arg::new("otp")
+            .long("otp")
+            .action(ArgAction::SetTrue)
+            .help("Set the secret for one-time codes, you'll be asked for an otpauth:// URI or a base32 secret (an empty value removes it)"),
+        Arg::new("otp-digits")
+            .long("otp-digits")
+            .requires("otp")
+            .default_value("6")
+            .help("The number of digits of one-time codes, when the secret isn't an otpauth:// URI")
+            .value_parser(validate_arg_u32),
+        Arg::new("otp-period")
+            .long("otp-period")
+            .requires("otp")
+            .default_value("30")
+            .help("How many seconds one-time codes are valid, when the secret isn't an otpauth:// URI")
+            .value_parser(validate_arg_u32),
+        Arg::new("otp-algorithm")
+            .long("otp-algorithm")
+            .requires("otp")
+            .default_value("sha1")
+            .value_parser(["sha1", "sha256", "sha512"])
+            .help("The hash function of one-time codes, when the secret isn't an otpauth:// URI"),
+    ]
+}
+
+/// Whether any of the arguments from `args` were given.
+pub fn given(matches: &clap::ArgMatches) -> bool {
+    ["url", "notes", "field", "concealed-field"]
+        .iter()
+        .any(|id| matches.contains_id(id))
+        || matches.get_flag("otp")
+}
+
+fn set_custom_field(password: &mut Password, name: &str, value: SafeString, concealed: bool) {
+    // Fields keep their position when they are updated
+    match password
+        .custom_fields
+        .iter_mut()
+        .find(|field| field.name == name)
+    {
+        Some(field) => {
+            field.value = value;
+            field.concealed = concealed;
+        }
+        None => password.custom_fields.push(CustomField {
+            name: name.to_string(),
+            value,
+            concealed,
+        }),
+    }
+}
+
+/// Applies the arguments from `args` to the password, asking for the values of concealed fields.
+pub fn apply(
+    matches: &clap::ArgMatches,
+    password: &mut Password,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    if let Some(url) = matches.get_one::<String>("url") {
+        password.url = if url.is_empty() {
+            None
+        } else {
+            Some(url.clone())
+        };
+    }
+
+    if let Some(notes) = matches.get_one::<String>("notes") {
+        password.notes = if notes.is_empty() {
+            None
+        } else {
+            Some(SafeString::from_string(notes.clone()))
+        };
+    }
+
+    if let Some(fields) = matches.get_many::<(String, String)>("field") {
+        for (name, value) in fields {
+            set_custom_field(
+                password,
+                name,
+                SafeString::from_string(value.clone()),
+                false,
+            );
+        }
+    }
+
+    if let Some(names) = matches.get_many::<String>("concealed-field") {
+        for name in names {
+            let value = io
+                .prompt_password(format!("What value do you want for \"{}\"? ", name))
+                .map_err(|err| {
+                    io.error(
+                        format!("\nI couldn't read the field's value (reason: {:?}).", err),
+                        OutputType::Error,
+                    );
+                    exit_code::ERROR
+                })?;
+            set_custom_field(password, name, value, true);
+        }
+    }
+
+    if matches.get_flag("otp") {
+        let input = io
+            .prompt_password("What is the otpauth:// URI or secret for one-time codes? ")
+            .map_err(|err| {
+                io.error(
+                    format!("\nI couldn't read the secret (reason: {:?}).", err),
+                    OutputType::Error,
+                );
+                exit_code::ERROR
+            })?;
+        password.otp = if input.trim().is_empty() {
+            None
+        } else {
+            let otp = Totp::parse(
+                input.trim(),
+                *matches.get_one::<u32>("otp-digits").unwrap(),
+                *matches.get_one::<u32>("otp-period").unwrap(),
+                matches.get_one::<String>("otp-algorithm").unwrap(),
+            )
+            .map_err(|err| {
+                io.error(
+                    format!("Woops, I can't compute one-time codes with this ({}).", err),
+                    OutputType::Error,
+                );
+                exit_code::ERROR
+            })?;
+            Some(otp)
+        };
+    }
+
+    Ok(())
+}
+
+/// Shows the URL, notes and custom fields of a password, concealed values only if `show` is set.
+pub fn print(show: bool, password: &Password, io: &mut impl CliInputOutput) {
+    if let Some(ref url) = password.url {
+        io.success(format!("URL: {}", url), OutputType::Standard);
+    }
+
+    if let Some(ref notes) = password.notes {
+        if show {
+            io.success(format!("Notes: {}", notes.deref()), OutputType::Standard);
+        } else {
+            io.success(
+                "Notes: ******** (see them with --show)",
+                OutputType::Standard,
+            );
+        }
+    }
+
+    for field in password.custom_fields.iter() {
+        if field.concealed && !show {
+            io.success(
+                format!("{}: ******** (see it with --show)", field.name),
+                OutputType::Standard,
+            );
+        } else {
+            io.success(
+                format!("{}: {}", field.name, field.value.deref()),
+                OutputType::Standard,
+            );
+        }
+    }
+
+    if !password.tags.is_empty() {
+        io.success(
+            format!("Tags: {}", password.tags.join(", ")),
+            OutputType::Standard,
+        );
+    }
+
+    if password.otp.is_some() {
+        io.success(
+            format!(
+                "One-time codes: see them with `rooster otp '{}'`",
+                password.name
+            ),
+            OutputType::Standard,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{set_custom_field, validate_arg_field};
+    use crate::password::v3::Password;
+
+    #[test]
+    fn test_validate_arg_field() {
+        assert_eq!(
+            validate_arg_field("PIN=1234"),
+            Ok(("PIN".to_string(), "1234".to_string()))
+        );
+        assert_eq!(
+            validate_arg_field("Question=a=b"),
+            Ok(("Question".to_string(), "a=b".to_string()))
+        );
+        assert_eq!(
+            validate_arg_field("Empty="),
+            Ok(("Empty".to_string(), "".to_string()))
+        );
+        assert!(validate_arg_field("=value").is_err());
+        assert!(validate_arg_field("no equal sign").is_err());
+    }
+
+    #[test]
+    fn test_set_custom_field_keeps_order() {
+        let mut password = Password::new("name", "username", "password");
+        set_custom_field(&mut password, "first", "1".into(), false);
+        set_custom_field(&mut password, "second", "2".into(), false);
+        set_custom_field(&mut password, "first", "one".into(), true);
+
+        assert_eq!(password.custom_fields.len(), 2);
+        assert_eq!(password.custom_fields[0].name, "first");
+        assert_eq!(password.custom_fields[0].value, "one".into());
+        assert!(password.custom_fields[0].concealed);
+        assert_eq!(password.custom_fields[1].name, "second");
+    }
+}
@@ -1,44 +1,55 @@
+use crate::exit_code;
+use crate::validate_arg_usize;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction};
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::Rng;
 use rclio::CliInputOutput;
 use rclio::OutputType;
 use rtoolbox::safe_string::SafeString;
+use serde::{Deserialize, Serialize};
 use std::io::Result as IoResult;
-use rand::Rng;
 
-fn generate_password(alnum: bool, len: usize) -> IoResult<SafeString> {
-    let mut password_as_string = String::new();
-    let mut rng = rand::rng();
-    for _ in 0..len {
-        if alnum {
-            match rng.random_range(0..3) {
-                // Numbers 0-9
-                0 => password_as_string.push(rng.random_range(48..58) as u8 as char),
-                // Uppercase A-Z
-                1 => password_as_string.push(rng.random_range(65..91) as u8 as char),
-                // Lowercase a-z
-                2 => password_as_string.push(rng.random_range(97..123) as u8 as char),
-                _ => unreachable!(),
-            }
-        } else {
-            password_as_string.push(rng.random_range(33..127) as u8 as char);
-        }
-    }
-    Ok(SafeString::from_string(password_as_string))
-}
+/// The EFF long wordlist, one "dice rolls, tab, word" entry per line.
+const EFF_LARGE_WORDLIST: &str = include_str!("wordlists/eff_large_wordlist.txt");
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
 
-/// Returns true if the password contains at least one digit, one uppercase letter and one
-/// lowercase letter.
-fn password_is_hard(password: &str, alnum: bool) -> bool {
-    let is_punctuation = |c| -> bool { "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~".find(c).is_some() };
+/// Characters that are easy to mistake for one another in many fonts.
+const AMBIGUOUS: &str = "0O1lI|";
 
-    password.find(char::is_numeric).is_some()
-        && password.find(char::is_lowercase).is_some()
-        && password.find(char::is_uppercase).is_some()
-        && (alnum || password.find(is_punctuation).is_some())
+const CONSONANTS: &str = "bcdfghjklmnpqrstvwxyz";
+const VOWELS: &str = "aeiou";
+
+/// Patterns longer than this are most likely a typo in a repetition count.
+const PATTERN_MAX_LEN: usize = 1024;
+
+fn validate_arg_symbols(v: &str) -> Result<String, String> {
+    if v.is_empty() {
+        return Err(String::from(
+            "There must be at least one symbol, use --alnum for passwords without symbols",
+        ));
+    }
+    if !v.chars().all(|c| c.is_ascii_punctuation()) {
+        return Err(String::from(
+            "The symbols must be ASCII punctuation characters, like !@#",
+        ));
+    }
+    Ok(v.to_string())
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PasswordSpec {
     pub alnum: bool,
     pub len: usize,
+    /// The symbols to use instead of all ASCII punctuation characters
+    pub symbols: Option<String>,
+    pub no_ambiguous: bool,
+    pub min_digits: usize,
+    pub min_symbols: usize,
 }
 
 impl PasswordSpec {
@@ -46,36 +57,561 @@ impl PasswordSpec {
         PasswordSpec {
             alnum,
             len: password_len.unwrap_or(32),
+            symbols: None,
+            no_ambiguous: false,
+            min_digits: 1,
+            min_symbols: 1,
+        }
+    }
+
+    /// Applies the arguments from `args` that were given on the command line.
+    pub fn update_from_matches(&mut self, matches: &clap::ArgMatches) {
+        if matches.get_flag("alnum") {
+            self.alnum = true;
+            self.symbols = None;
+        }
+        if given_on_command_line(matches, "length") {
+            self.len = *matches.get_one::<usize>("length").unwrap();
+        }
+        if let Some(symbols) = matches.get_one::<String>("symbols") {
+            self.alnum = false;
+            self.symbols = Some(symbols.clone());
+        }
+        if matches.get_flag("no-ambiguous") {
+            self.no_ambiguous = true;
         }
+        if given_on_command_line(matches, "min-digits") {
+            self.min_digits = *matches.get_one::<usize>("min-digits").unwrap();
+        }
+        if given_on_command_line(matches, "min-symbols") {
+            self.alnum = false;
+            self.min_symbols = *matches.get_one::<usize>("min-symbols").unwrap();
+        }
+    }
+
+    /// Each kind of character the password is made of, with how many of them it needs at least.
+    fn character_classes(&self) -> Vec<(Vec<char>, usize)> {
+        let alphabet = |chars: &str| -> Vec<char> {
+            let mut alphabet: Vec<char> = chars
+                .chars()
+                .filter(|c| !self.no_ambiguous || !AMBIGUOUS.contains(*c))
+                .collect();
+            alphabet.sort_unstable();
+            alphabet.dedup();
+            alphabet
+        };
+
+        let mut classes = vec![
+            (alphabet(LOWERCASE), 1),
+            (alphabet(UPPERCASE), 1),
+            (alphabet(DIGITS), self.min_digits),
+        ];
+        if !self.alnum {
+            let symbols = alphabet(self.symbols.as_deref().unwrap_or(SYMBOLS));
+            if !symbols.is_empty() {
+                classes.push((symbols, self.min_symbols));
+            }
+        }
+        classes
+    }
+
+    /// The shortest password that has all the characters this spec requires.
+    pub fn min_len(&self) -> usize {
+        self.character_classes().iter().map(|(_, min)| min).sum()
     }
 
     pub fn generate_hard_password(&self) -> IoResult<SafeString> {
-        loop {
-            let password = generate_password(self.alnum, self.len)?;
-            if password_is_hard(password.as_ref(), self.alnum) {
-                return Ok(password);
+        let mut rng = rand::rng();
+        let classes = self.character_classes();
+
+        // Pick the required characters first, then fill up with any kind of character and mix
+        // everything up, so every password we generate is a good one on the first try.
+        let mut password: Vec<char> = Vec::with_capacity(self.len);
+        for (alphabet, min) in classes.iter() {
+            for _ in 0..*min {
+                password.push(*alphabet.choose(&mut rng).unwrap());
+            }
+        }
+        let all: Vec<char> = classes
+            .iter()
+            .flat_map(|(alphabet, _)| alphabet.iter().cloned())
+            .collect();
+        while password.len() < self.len {
+            password.push(*all.choose(&mut rng).unwrap());
+        }
+        password.shuffle(&mut rng);
+
+        Ok(SafeString::from_string(password.into_iter().collect()))
+    }
+}
+
+pub fn check_password_spec(spec: &PasswordSpec, io: &mut impl CliInputOutput) -> Result<(), i32> {
+    if !spec.alnum && spec.character_classes().len() < 4 {
+        io.error(
+            "Woops! All the symbols you picked are ambiguous, so there are none left to pick from.",
+            OutputType::Error,
+        );
+        return Err(exit_code::ERROR);
+    }
+
+    // We want passwords to contain at least one uppercase letter and one lowercase letter, and
+    // by default one digit and one symbol, so we need at least 4 characters for each password.
+    let min_len = spec.min_len().max(4);
+    if spec.len < min_len {
+        io.error(
+            format!(
+                "Woops! The length of the password must be at least {}. This allows us to make sure your password is secure.",
+                min_len
+            ),
+            OutputType::Error,
+        );
+        return Err(exit_code::ERROR);
+    }
+
+    Ok(())
+}
+
+/// What a character of a password generated from a pattern is made of.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PatternElement {
+    Class(Vec<char>),
+    Literal(char),
+}
+
+fn pattern_placeholder(c: char) -> Option<Vec<char>> {
+    let alphabet = match c {
+        'c' => CONSONANTS.to_string(),
+        'C' => CONSONANTS.to_uppercase(),
+        'v' => VOWELS.to_string(),
+        'V' => VOWELS.to_uppercase(),
+        'l' => LOWERCASE.to_string(),
+        'L' => UPPERCASE.to_string(),
+        '9' => DIGITS.to_string(),
+        '!' => SYMBOLS.to_string(),
+        '*' => [LOWERCASE, UPPERCASE, DIGITS, SYMBOLS].concat(),
+        _ => return None,
+    };
+    Some(alphabet.chars().collect())
+}
+
+/// A template for passwords of a fixed shape, like `Cvccvc99!`.
+///
+/// `c`/`C` are lowercase/uppercase consonants, `v`/`V` vowels, `l` a lowercase letter, `L` an
+/// uppercase letter, `9` digits, `!` symbols and `*` any of these. `{n}` repeats the previous
+/// character `n` times and `\` makes the next character literal. Any other character is kept as
+/// is.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternSpec {
+    pub pattern: String,
+}
+
+impl PatternSpec {
+    fn elements(&self) -> Result<Vec<PatternElement>, String> {
+        let mut elements: Vec<PatternElement> = Vec::new();
+        let mut chars = self.pattern.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(escaped) => elements.push(PatternElement::Literal(escaped)),
+                    None => {
+                        return Err(String::from(
+                            "The pattern can't end with \\, use \\\\ for a backslash",
+                        ))
+                    }
+                },
+                '{' => {
+                    let mut count = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(digit) if digit.is_ascii_digit() => count.push(digit),
+                            _ => return Err(String::from("Repetitions must look like {3}")),
+                        }
+                    }
+                    let count = match count.parse::<usize>() {
+                        Ok(count) if count > 0 => count,
+                        _ => return Err(String::from("Repetitions must be at least {1}")),
+                    };
+                    let previous = match elements.last() {
+                        Some(previous) => previous.clone(),
+                        None => return Err(String::from("There is nothing to repeat before {")),
+                    };
+                    if elements.len().saturating_add(count) > PATTERN_MAX_LEN + 1 {
+                        return Err(format!(
+                            "The pattern can't make passwords longer than {} characters",
+                            PATTERN_MAX_LEN
+                        ));
+                    }
+                    for _ in 1..count {
+                        elements.push(previous.clone());
+                    }
+                }
+                c => elements.push(match pattern_placeholder(c) {
+                    Some(alphabet) => PatternElement::Class(alphabet),
+                    None => PatternElement::Literal(c),
+                }),
+            }
+
+            if elements.len() > PATTERN_MAX_LEN {
+                return Err(format!(
+                    "The pattern can't make passwords longer than {} characters",
+                    PATTERN_MAX_LEN
+                ));
+            }
+        }
+
+        Ok(elements)
+    }
+
+    pub fn generate_password(&self) -> Result<SafeString, String> {
+        let mut rng = rand::rng();
+        let password = self
+            .elements()?
+            .iter()
+            .map(|element| match element {
+                PatternElement::Class(alphabet) => *alphabet.choose(&mut rng).unwrap(),
+                PatternElement::Literal(c) => *c,
+            })
+            .collect();
+        Ok(SafeString::from_string(password))
+    }
+
+    /// How many bits an attacker who knows the pattern has to guess.
+    pub fn entropy_bits(&self) -> f64 {
+        self.elements()
+            .unwrap_or_default()
+            .iter()
+            .map(|element| match element {
+                PatternElement::Class(alphabet) => (alphabet.len() as f64).log2(),
+                PatternElement::Literal(_) => 0.0,
+            })
+            .sum()
+    }
+}
+
+fn validate_arg_pattern(v: &str) -> Result<PatternSpec, String> {
+    let spec = PatternSpec {
+        pattern: v.to_string(),
+    };
+    let elements = spec.elements()?;
+    if !elements
+        .iter()
+        .any(|element| matches!(element, PatternElement::Class(_)))
+    {
+        return Err(String::from(
+            "The pattern must have at least one random character, like c, v, 9 or !",
+        ));
+    }
+    Ok(spec)
+}
+
+pub fn wordlist() -> Vec<&'static str> {
+    EFF_LARGE_WORDLIST
+        .lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .collect()
+}
+
+/// Arguments shared by commands that generate passwords or passphrases.
+pub fn args() -> Vec<Arg> {
+    let mut args = vec![
+        Arg::new("alnum")
+            .action(ArgAction::SetTrue)
+            .short('a')
+            .long("alnum")
+            .conflicts_with_all(["words", "symbols", "min-symbols"])
+            .help("Only use alpha numeric (a-z, A-Z, 0-9) in generated passwords"),
+        Arg::new("length")
+            .short('l')
+            .long("length")
+            .default_value("32")
+            .conflicts_with("words")
+            .help("Set a custom length for the generated password")
+            .value_parser(validate_arg_usize),
+        Arg::new("symbols")
+            .long("symbols")
+            .conflicts_with("words")
+            .help("Only use these symbols in generated passwords, for instance '!@#'")
+            .value_parser(validate_arg_symbols),
+        Arg::new("no-ambiguous")
+            .action(ArgAction::SetTrue)
+            .long("no-ambiguous")
+            .conflicts_with("words")
+            .help("Leave out characters that look alike (0, O, 1, l, I and |)"),
+        Arg::new("min-digits")
+            .long("min-digits")
+            .conflicts_with("words")
+            .help("The least number of digits in generated passwords")
+            .default_value("1")
+            .value_parser(validate_arg_usize),
+        Arg::new("min-symbols")
+            .long("min-symbols")
+            .conflicts_with("words")
+            .help("The least number of symbols in generated passwords")
+            .default_value("1")
+            .value_parser(validate_arg_usize),
+        Arg::new("pattern")
+            .long("pattern")
+            .conflicts_with_all([
+                "words",
+                "alnum",
+                "length",
+                "symbols",
+                "no-ambiguous",
+                "min-digits",
+                "min-symbols",
+            ])
+            .help(
+                "Generate a password of a fixed shape, like 'Cvccvc99!': c/C consonant, v/V vowel, \
+                 l lowercase letter, L uppercase letter, 9 digit, ! symbol, * any of them, {3} repeats the previous one 3 times, \\ \
+                 makes the next one literal",
+            )
+            .value_parser(validate_arg_pattern),
+    ];
+    args.extend(passphrase_args());
+    args
+}
+
+/// Arguments shared by commands that generate passphrases.
+pub fn passphrase_args() -> Vec<Arg> {
+    vec![
+        Arg::new("words")
+            .short('w')
+            .long("words")
+            .help("Generate a passphrase of this many words instead, easier to type by hand")
+            .value_parser(validate_arg_usize),
+        Arg::new("separator")
+            .long("separator")
+            .requires("words")
+            .default_value("-")
+            .help("The characters between the words of the passphrase"),
+        Arg::new("capitalize")
+            .action(ArgAction::SetTrue)
+            .long("capitalize")
+            .requires("words")
+            .help("Start each word of the passphrase with an uppercase letter"),
+        Arg::new("digit")
+            .action(ArgAction::SetTrue)
+            .long("digit")
+            .requires("words")
+            .help("Add a digit after one of the words of the passphrase"),
+    ]
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PassphraseSpec {
+    pub words: usize,
+    pub separator: String,
+    pub capitalize: bool,
+    pub digit: bool,
+}
+
+impl PassphraseSpec {
+    /// Reads the arguments from `passphrase_args`, if a number of words was given.
+    pub fn from_matches(matches: &clap::ArgMatches) -> Option<PassphraseSpec> {
+        let mut spec = PassphraseSpec {
+            words: *matches.get_one::<usize>("words")?,
+            separator: "-".to_string(),
+            capitalize: false,
+            digit: false,
+        };
+        spec.update_from_matches(matches);
+        Some(spec)
+    }
+
+    /// Applies the arguments from `passphrase_args` that were given on the command line.
+    pub fn update_from_matches(&mut self, matches: &clap::ArgMatches) {
+        if let Some(words) = matches.get_one::<usize>("words") {
+            self.words = *words;
+        }
+        if given_on_command_line(matches, "separator") {
+            self.separator = matches.get_one::<String>("separator").unwrap().clone();
+        }
+        if matches.get_flag("capitalize") {
+            self.capitalize = true;
+        }
+        if matches.get_flag("digit") {
+            self.digit = true;
+        }
+    }
+
+    pub fn generate_passphrase(&self) -> SafeString {
+        let wordlist = wordlist();
+        let mut rng = rand::rng();
+        let digit_position = rng.random_range(0..self.words);
+
+        let mut passphrase = String::new();
+        for i in 0..self.words {
+            if i > 0 {
+                passphrase.push_str(&self.separator);
+            }
+
+            let word = wordlist[rng.random_range(0..wordlist.len())];
+            if self.capitalize {
+                let mut chars = word.chars();
+                if let Some(first) = chars.next() {
+                    passphrase.extend(first.to_uppercase());
+                    passphrase.push_str(chars.as_str());
+                }
+            } else {
+                passphrase.push_str(word);
+            }
+
+            if self.digit && i == digit_position {
+                passphrase.push(rng.random_range(b'0'..=b'9') as char);
+            }
+        }
+        SafeString::from_string(passphrase)
+    }
+
+    /// How many bits an attacker who knows the wordlist and these options has to guess.
+    pub fn entropy_bits(&self) -> f64 {
+        let mut bits = self.words as f64 * (wordlist().len() as f64).log2();
+        if self.digit {
+            // Which digit, and after which word
+            bits += 10f64.log2() + (self.words as f64).log2();
+        }
+        bits
+    }
+}
+
+pub fn check_passphrase_words(words: usize, io: &mut impl CliInputOutput) -> Result<(), i32> {
+    // Each word is worth a bit less than 13 bits, so 4 words is the least that makes sense.
+    if words < 4 {
+        io.error(
+            "Woops! A passphrase needs at least 4 words to be secure.",
+            OutputType::Error,
+        );
+        return Err(exit_code::ERROR);
+    }
+    Ok(())
+}
+
+fn given_on_command_line(matches: &clap::ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+/// How to generate the password of an entry, remembered so that `regenerate` makes the same
+/// kind of password.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum GenerationPolicy {
+    Password(PasswordSpec),
+    Passphrase(PassphraseSpec),
+    Pattern(PatternSpec),
+}
+
+impl GenerationPolicy {
+    /// The policy asked for with the arguments from `args`. Anything that wasn't given on the
+    /// command line comes from the previous policy, if it is of the same kind.
+    pub fn from_matches(
+        matches: &clap::ArgMatches,
+        previous: Option<&GenerationPolicy>,
+    ) -> GenerationPolicy {
+        let password_args_given = [
+            "alnum",
+            "length",
+            "symbols",
+            "no-ambiguous",
+            "min-digits",
+            "min-symbols",
+        ]
+        .iter()
+        .any(|id| given_on_command_line(matches, id));
+        let words_given = matches.get_one::<usize>("words").is_some();
+
+        if let Some(spec) = matches.get_one::<PatternSpec>("pattern") {
+            return GenerationPolicy::Pattern(spec.clone());
+        }
+
+        match previous {
+            Some(GenerationPolicy::Pattern(spec)) if !password_args_given && !words_given => {
+                GenerationPolicy::Pattern(spec.clone())
+            }
+            Some(GenerationPolicy::Passphrase(spec)) if !password_args_given => {
+                let mut spec = spec.clone();
+                spec.update_from_matches(matches);
+                GenerationPolicy::Passphrase(spec)
+            }
+            _ if words_given => {
+                GenerationPolicy::Passphrase(PassphraseSpec::from_matches(matches).unwrap())
+            }
+            Some(GenerationPolicy::Password(spec)) => {
+                let mut spec = spec.clone();
+                spec.update_from_matches(matches);
+                GenerationPolicy::Password(spec)
+            }
+            _ => {
+                let mut spec = PasswordSpec::new(false, None);
+                spec.update_from_matches(matches);
+                GenerationPolicy::Password(spec)
+            }
+        }
+    }
+
+    /// Generates a password, along with its entropy if it is a passphrase or follows a pattern.
+    pub fn generate(&self, io: &mut impl CliInputOutput) -> Result<(SafeString, Option<f64>), i32> {
+        let pwspec = match *self {
+            GenerationPolicy::Passphrase(ref spec) => {
+                check_passphrase_words(spec.words, io)?;
+                return Ok((spec.generate_passphrase(), Some(spec.entropy_bits())));
+            }
+            GenerationPolicy::Pattern(ref spec) => {
+                return match spec.generate_password() {
+                    Ok(password) => Ok((password, Some(spec.entropy_bits()))),
+                    Err(err) => {
+                        io.error(
+                            format!(
+                                "Woops, I can't generate a password from this pattern ({}).",
+                                err
+                            ),
+                            OutputType::Error,
+                        );
+                        Err(exit_code::ERROR)
+                    }
+                };
+            }
+            GenerationPolicy::Password(ref spec) => spec,
+        };
+
+        check_password_spec(pwspec, io)?;
+        match pwspec.generate_hard_password() {
+            Ok(password) => Ok((password, None)),
+            Err(io_err) => {
+                io.error(
+                    format!(
+                        "Woops, I could not generate the password (reason: {:?}).",
+                        io_err
+                    ),
+                    OutputType::Error,
+                );
+                Err(exit_code::ERROR)
             }
         }
     }
 }
 
-pub fn check_password_len(len: usize, io: &mut impl CliInputOutput) -> Option<usize> {
-    // We want passwords to contain at least one uppercase letter, one lowercase
-    // letter and one digit. So we need at least 4 characters for each password.
-    // This checks makes sure we don't run into an infinite loop trying to generate
-    // a password of length < 4 with 4 different kinds of characters (uppercase,
-    // lowercase, numeric, punctuation).
-    if len < 4 {
-        io.error("Woops! The length of the password must be at least 4. This allows us to make sure your password is secure.", OutputType::Error);
-        None
-    } else {
-        Some(len)
+pub fn print_entropy(entropy_bits: Option<f64>, io: &mut impl CliInputOutput) {
+    if let Some(bits) = entropy_bits {
+        io.info(
+            format!(
+                "This password has about {:.0} bits of entropy.",
+                bits.floor()
+            ),
+            OutputType::Standard,
+        );
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::generate::PasswordSpec;
+    use crate::generate::{
+        args, validate_arg_pattern, validate_arg_symbols, wordlist, GenerationPolicy,
+        PassphraseSpec, PasswordSpec, PatternElement, PatternSpec, AMBIGUOUS,
+    };
+    use rand::seq::IndexedRandom;
+    use std::collections::HashSet;
     use std::ops::Deref;
 
     #[test]
@@ -120,4 +656,319 @@ mod test {
         }
         assert!(ok);
     }
+
+    #[test]
+    fn test_generate_password_symbols() {
+        let mut ps = PasswordSpec::new(false, Some(8));
+        ps.symbols = Some("!@#".to_string());
+        for _ in 0..100 {
+            let pw = ps.generate_hard_password().unwrap();
+            assert_eq!(pw.len(), 8);
+            assert!(pw.deref().chars().any(|c| "!@#".contains(c)));
+            assert!(pw
+                .deref()
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!@#".contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_generate_password_no_ambiguous() {
+        let mut ps = PasswordSpec::new(false, Some(64));
+        ps.no_ambiguous = true;
+        for _ in 0..100 {
+            let pw = ps.generate_hard_password().unwrap();
+            assert!(!pw.deref().chars().any(|c| AMBIGUOUS.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_generate_password_min_digits_and_symbols() {
+        let mut ps = PasswordSpec::new(false, Some(10));
+        ps.min_digits = 5;
+        ps.min_symbols = 3;
+        assert_eq!(ps.min_len(), 10);
+        for _ in 0..100 {
+            let pw = ps.generate_hard_password().unwrap();
+            assert_eq!(pw.len(), 10);
+            assert_eq!(pw.deref().chars().filter(char::is_ascii_digit).count(), 5);
+            assert_eq!(
+                pw.deref()
+                    .chars()
+                    .filter(char::is_ascii_punctuation)
+                    .count(),
+                3
+            );
+            assert_eq!(
+                pw.deref().chars().filter(char::is_ascii_lowercase).count(),
+                1
+            );
+            assert_eq!(
+                pw.deref().chars().filter(char::is_ascii_uppercase).count(),
+                1
+            );
+        }
+
+        assert_eq!(PasswordSpec::new(false, None).min_len(), 4);
+        assert_eq!(PasswordSpec::new(true, None).min_len(), 3);
+    }
+
+    #[test]
+    fn test_generate_password_without_digits_or_symbols_required() {
+        let mut ps = PasswordSpec::new(false, Some(4));
+        ps.min_digits = 0;
+        ps.min_symbols = 0;
+        assert_eq!(ps.min_len(), 2);
+        for _ in 0..100 {
+            let pw = ps.generate_hard_password().unwrap();
+            assert_eq!(pw.len(), 4);
+            assert!(pw.deref().chars().any(|c| c.is_ascii_lowercase()));
+            assert!(pw.deref().chars().any(|c| c.is_ascii_uppercase()));
+        }
+    }
+
+    #[test]
+    fn test_validate_arg_symbols() {
+        assert_eq!(validate_arg_symbols("!@#"), Ok("!@#".to_string()));
+        assert!(validate_arg_symbols("").is_err());
+        assert!(validate_arg_symbols("!a").is_err());
+        assert!(validate_arg_symbols("! ").is_err());
+    }
+
+    #[test]
+    fn test_wordlist_is_the_eff_long_wordlist() {
+        let words = wordlist();
+        assert_eq!(words.len(), 6 * 6 * 6 * 6 * 6);
+        assert_eq!(words.iter().collect::<HashSet<_>>().len(), words.len());
+        assert_eq!(words[0], "abacus");
+        assert_eq!(words[words.len() - 1], "zoom");
+        for word in words {
+            assert!(word.chars().all(|c| c.is_ascii_lowercase() || c == '-'));
+        }
+    }
+
+    #[test]
+    fn test_generate_passphrase() {
+        let words = wordlist();
+        let spec = PassphraseSpec {
+            words: 6,
+            separator: " ".to_string(),
+            capitalize: false,
+            digit: false,
+        };
+        let passphrase = spec.generate_passphrase();
+        let parts: Vec<&str> = passphrase.deref().split(' ').collect();
+        assert_eq!(parts.len(), 6);
+        for part in parts {
+            assert!(words.contains(&part));
+        }
+        assert!((spec.entropy_bits() - 77.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_generate_passphrase_capitalized_with_digit() {
+        let words = wordlist();
+        let spec = PassphraseSpec {
+            words: 5,
+            separator: ".".to_string(),
+            capitalize: true,
+            digit: true,
+        };
+        let passphrase = spec.generate_passphrase();
+        let parts: Vec<&str> = passphrase.deref().split('.').collect();
+        assert_eq!(parts.len(), 5);
+        assert_eq!(
+            parts
+                .iter()
+                .filter(|part| part.ends_with(|c: char| c.is_ascii_digit()))
+                .count(),
+            1
+        );
+        for part in parts {
+            assert!(part.starts_with(|c: char| c.is_ascii_uppercase()));
+            let word = part
+                .trim_end_matches(|c: char| c.is_ascii_digit())
+                .to_lowercase();
+            assert!(words.contains(&word.as_str()));
+        }
+        assert!(spec.entropy_bits() > 5.0 * 12.9 + 3.3);
+    }
+
+    fn policy_from_args(args_: &[&str], previous: Option<&GenerationPolicy>) -> GenerationPolicy {
+        let matches = clap::Command::new("regenerate")
+            .args(args())
+            .try_get_matches_from([&["regenerate"], args_].concat())
+            .unwrap();
+        GenerationPolicy::from_matches(&matches, previous)
+    }
+
+    #[test]
+    fn test_generation_policy_from_matches() {
+        assert_eq!(
+            policy_from_args(&[], None),
+            GenerationPolicy::Password(PasswordSpec::new(false, None))
+        );
+
+        // What isn't given comes from the previous policy
+        let alnum_16 = GenerationPolicy::Password(PasswordSpec::new(true, Some(16)));
+        assert_eq!(policy_from_args(&[], Some(&alnum_16)), alnum_16);
+        assert_eq!(
+            policy_from_args(&["--length", "20"], Some(&alnum_16)),
+            GenerationPolicy::Password(PasswordSpec::new(true, Some(20)))
+        );
+        let mut with_symbols = PasswordSpec::new(false, Some(16));
+        with_symbols.symbols = Some("!@#".to_string());
+        assert_eq!(
+            policy_from_args(&["--symbols", "!@#"], Some(&alnum_16)),
+            GenerationPolicy::Password(with_symbols)
+        );
+
+        let passphrase = GenerationPolicy::Passphrase(PassphraseSpec {
+            words: 5,
+            separator: "_".to_string(),
+            capitalize: true,
+            digit: false,
+        });
+        assert_eq!(policy_from_args(&[], Some(&passphrase)), passphrase);
+        assert_eq!(
+            policy_from_args(&["--words", "7", "--digit"], Some(&passphrase)),
+            GenerationPolicy::Passphrase(PassphraseSpec {
+                words: 7,
+                separator: "_".to_string(),
+                capitalize: true,
+                digit: true,
+            })
+        );
+
+        // Switching between passwords and passphrases starts over
+        assert_eq!(
+            policy_from_args(&["--length", "20"], Some(&passphrase)),
+            GenerationPolicy::Password(PasswordSpec::new(false, Some(20)))
+        );
+        assert_eq!(
+            policy_from_args(&["--words", "4"], Some(&alnum_16)),
+            GenerationPolicy::Passphrase(PassphraseSpec {
+                words: 4,
+                separator: "-".to_string(),
+                capitalize: false,
+                digit: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_generation_policy_serialization() {
+        let policy = GenerationPolicy::Password(PasswordSpec::new(true, Some(16)));
+        let json = serde_json::to_string(&policy).unwrap();
+        assert!(json.contains("\"kind\":\"password\""));
+        assert_eq!(
+            serde_json::from_str::<GenerationPolicy>(&json).unwrap(),
+            policy
+        );
+    }
+
+    fn pattern(pattern: &str) -> PatternSpec {
+        PatternSpec {
+            pattern: pattern.to_string(),
+        }
+    }
+
+    /// Whether the password could have been generated from the pattern.
+    fn matches_pattern(spec: &PatternSpec, password: &str) -> bool {
+        let elements = spec.elements().unwrap();
+        password.chars().count() == elements.len()
+            && password
+                .chars()
+                .zip(elements.iter())
+                .all(|(c, element)| match element {
+                    PatternElement::Class(alphabet) => alphabet.contains(&c),
+                    PatternElement::Literal(literal) => c == *literal,
+                })
+    }
+
+    #[test]
+    fn test_pattern_elements() {
+        let elements = pattern("C9{3}-\\9").elements().unwrap();
+        assert_eq!(elements.len(), 6);
+        assert_eq!(
+            elements[0],
+            PatternElement::Class("BCDFGHJKLMNPQRSTVWXYZ".chars().collect())
+        );
+        assert_eq!(elements[1], elements[3]);
+        assert_eq!(elements[4], PatternElement::Literal('-'));
+        assert_eq!(elements[5], PatternElement::Literal('9'));
+
+        assert!(pattern("9\\").elements().is_err());
+        assert!(pattern("{2}9").elements().is_err());
+        assert!(pattern("9{0}").elements().is_err());
+        assert!(pattern("9{}").elements().is_err());
+        assert!(pattern("9{2").elements().is_err());
+        assert!(pattern("9{x}").elements().is_err());
+        assert!(pattern("9{99999999999999999999}").elements().is_err());
+        assert!(pattern("9{1025}").elements().is_err());
+        assert!(pattern("9{1024}").elements().is_ok());
+
+        assert!(validate_arg_pattern("Cvccvc99!").is_ok());
+        assert!(validate_arg_pattern("\\C\\9-").is_err());
+    }
+
+    #[test]
+    fn test_pattern_entropy() {
+        let expected = 4.0 * 21f64.log2() + 2.0 * 5f64.log2() + 2.0 * 10f64.log2() + 5.0;
+        assert!((pattern("Cvccvc99!").entropy_bits() - expected).abs() < 1e-9);
+        assert!((pattern("prefix-9{6}").entropy_bits() - 6.0 * 10f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pattern_passwords_match_the_pattern() {
+        let tokens = [
+            "c", "C", "v", "V", "l", "L", "9", "!", "*", "-", "x", "\\c", "\\{", "\\\\", "{2}",
+            "{5}",
+        ];
+        let mut rng = rand::rng();
+
+        for _ in 0..200 {
+            // Any sequence of tokens is a valid pattern, once it starts with a placeholder
+            let mut template = String::from("*");
+            for _ in 0..10 {
+                template.push_str(tokens.choose(&mut rng).unwrap());
+            }
+            let spec = pattern(&template);
+
+            for _ in 0..20 {
+                let password = spec.generate_password().unwrap();
+                assert!(
+                    matches_pattern(&spec, &password),
+                    "{:?} doesn't match {:?}",
+                    password.as_str(),
+                    template
+                );
+            }
+        }
+
+        let spec = pattern("Cvccvc99!");
+        for _ in 0..100 {
+            let password = spec.generate_password().unwrap();
+            let chars: Vec<char> = password.chars().collect();
+            assert_eq!(chars.len(), 9);
+            assert!(chars[0].is_ascii_uppercase() && !"AEIOU".contains(chars[0]));
+            assert!("aeiou".contains(chars[1]));
+            assert!(chars[6].is_ascii_digit() && chars[7].is_ascii_digit());
+            assert!(chars[8].is_ascii_punctuation());
+        }
+    }
+
+    #[test]
+    fn test_generation_policy_pattern() {
+        let from_pattern = GenerationPolicy::Pattern(pattern("Cvccvc99!"));
+        assert_eq!(
+            policy_from_args(&["--pattern", "Cvccvc99!"], None),
+            from_pattern
+        );
+        assert_eq!(policy_from_args(&[], Some(&from_pattern)), from_pattern);
+        assert_eq!(
+            policy_from_args(&["--length", "20"], Some(&from_pattern)),
+            GenerationPolicy::Password(PasswordSpec::new(false, Some(20)))
+        );
+    }
 }
@@ -1,11 +1,12 @@
 // #![allow(useless_format, too_many_arguments)]
 
-use crate::password::v2::PasswordStore;
+use crate::password::v3::PasswordStore;
 use clap::{Arg, ArgAction, Command};
 use rclio::CliInputOutput;
 use rclio::OutputType;
 use rtoolbox::safe_string::SafeString;
 use rtoolbox::safe_vec::SafeVec;
+use sha2::{Digest, Sha256};
 use std::env;
 use std::fs::File;
 use std::io::Read;
@@ -14,16 +15,27 @@ use std::ops::Deref;
 use std::path::{Path, PathBuf};
 
 mod aes;
+#[cfg(unix)]
+mod agent;
+mod atomic_file;
+mod breach;
 mod clip;
 mod commands;
+mod exit_code;
 mod ffi;
+mod fields;
 mod generate;
 mod list;
+mod lock;
+mod output;
 mod password;
 #[cfg(unix)]
 mod quale;
-#[cfg(unix)]
-mod shell_escape;
+mod script;
+mod strength;
+mod totp;
+
+const KEYFILE_ENV_VAR: &str = "ROOSTER_KEYFILE";
 
 #[cfg(windows)]
 fn example_environment_variable_configuration() -> &'static str {
@@ -66,44 +78,170 @@ fn validate_arg_u32(v: &str) -> Result<u32, String> {
 fn open_password_file(filename: &str) -> IoResult<File> {
     let mut options = std::fs::OpenOptions::new();
     options.read(true);
-    options.write(true);
+    options.write(false);
     options.create(false);
     options.open(&Path::new(filename))
 }
 
-fn create_password_file(filename: &str) -> IoResult<File> {
-    let mut options = std::fs::OpenOptions::new();
-    options.read(true);
-    options.write(true);
-    options.create(true);
-    options.open(&Path::new(filename))
+/// Whether the command only reads the password file. Such commands can run alongside each other.
+fn is_read_only_command(subcommand: &str, matches: &clap::ArgMatches) -> bool {
+    match subcommand {
+        "get" | "list" | "search" | "export" | "otp" | "audit" => true,
+        "history" => !matches.contains_id("restore"),
+        "slot" => matches.subcommand_name() == Some("list"),
+        _ => false,
+    }
+}
+
+fn lock_password_file(
+    path: &Path,
+    exclusive: bool,
+    io: &mut impl CliInputOutput,
+) -> Result<lock::PasswordFileLock, i32> {
+    match lock::lock(path, exclusive) {
+        Ok(lock) => Ok(lock),
+        Err(lock::LockError::InUse(pid)) => {
+            io.error(
+                match pid {
+                    Some(pid) => format!(
+                        "Woops, your password file is in use by PID {}. Try again once it is done.",
+                        pid
+                    ),
+                    None => "Woops, your password file is in use by another Rooster process. \
+                             Try again once it is done."
+                        .to_string(),
+                },
+                OutputType::Error,
+            );
+            Err(exit_code::PASSWORD_FILE_IN_USE)
+        }
+        Err(lock::LockError::Io(err)) => {
+            io.error(
+                format!("Woops, I couldn't lock your password file (reason: {}).", err),
+                OutputType::Error,
+            );
+            Err(exit_code::ERROR)
+        }
+    }
+}
+
+fn digest_password_file(input: &[u8]) -> Vec<u8> {
+    Sha256::digest(input).to_vec()
+}
+
+/// Checks whether something other than Rooster changed the password file since we read it.
+fn password_file_changed(path: &Path, digest: &[u8]) -> IoResult<bool> {
+    let current = std::fs::read(path)?;
+    Ok(digest_password_file(current.as_slice()).as_slice() != digest)
 }
 
 fn sync_password_store(
     store: &mut PasswordStore,
-    file: &mut File,
+    path: &Path,
+    digest: &[u8],
     io: &mut impl CliInputOutput,
 ) -> Result<(), i32> {
-    if let Err(err) = store.sync(file) {
+    match password_file_changed(path, digest) {
+        Ok(false) => {}
+        Ok(true) => {
+            io.error(
+                "Woops, your password file was changed by another program since I read it. \
+                 I did not save your changes, so that I don't overwrite theirs.",
+                OutputType::Error,
+            );
+            return Err(exit_code::PASSWORD_FILE_CHANGED);
+        }
+        Err(err) => {
+            io.error(
+                format!("I could not save the password file (reason: {:?}).", err),
+                OutputType::Error,
+            );
+            return Err(exit_code::ERROR);
+        }
+    }
+
+    if let Err(err) = store.sync(path) {
         io.error(
             format!("I could not save the password file (reason: {:?}).", err),
             OutputType::Error,
         );
-        return Err(1);
+        return Err(exit_code::ERROR);
     }
 
     return Ok(());
 }
 
-fn get_password_store(
-    file: &mut File,
-    io: &mut impl CliInputOutput,
-) -> Result<password::v2::PasswordStore, i32> {
+fn read_password_file(file: &mut File, io: &mut impl CliInputOutput) -> Result<SafeVec, i32> {
     // Read the Rooster file contents.
     let mut input: SafeVec = SafeVec::new(Vec::new());
-    file.read_to_end(input.inner_mut()).map_err(|_| 1)?;
+    file.read_to_end(input.inner_mut()).map_err(|err| {
+        io.error(
+            format!("Woops, I couldn't read your password file (reason: {}).", err),
+            OutputType::Error,
+        );
+        exit_code::UNREADABLE_PASSWORD_FILE
+    })?;
+    Ok(input)
+}
+
+/// Whether the command changes how the password file is protected. Such commands always ask for
+/// the master password, even when the agent remembers it.
+fn needs_master_password(subcommand: &str) -> bool {
+    matches!(
+        subcommand,
+        "set-master-password" | "set-scrypt-params" | "set-kdf" | "set-keyfile" | "slot"
+    )
+}
+
+fn get_password_store(
+    input: &SafeVec,
+    use_agent: bool,
+    master_password: Option<&SafeString>,
+    keyfile_digest: Option<&SafeVec>,
+    io: &mut impl CliInputOutput,
+) -> Result<password::v3::PasswordStore, i32> {
+    #[cfg(unix)]
+    if use_agent && master_password.is_none() {
+        if let Some(store) = agent::unlock(input) {
+            return Ok(store);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = use_agent;
+
+    // No need to ask for the master password if it can't be enough.
+    if keyfile_digest.is_none()
+        && password::v3::PasswordStore::keyfile_required_from_input(input).unwrap_or(false)
+    {
+        io.error(
+            "Woops, your password file needs a keyfile along with your master password. \
+             Give its path with --keyfile or the ROOSTER_KEYFILE environment variable.",
+            OutputType::Error,
+        );
+        return Err(exit_code::KEYFILE_REQUIRED);
+    }
 
-    return get_password_store_from_input_interactive(&input, 3, false, false, io).map_err(|_| 1);
+    let store = get_password_store_from_input_interactive(
+        input,
+        3,
+        false,
+        false,
+        master_password,
+        keyfile_digest,
+        io,
+    )
+    .map_err(|err| match err {
+        password::PasswordError::KeyfileRequiredError => exit_code::KEYFILE_REQUIRED,
+        password::PasswordError::DecryptionError
+        | password::PasswordError::CorruptionLikelyError => exit_code::WRONG_MASTER_PASSWORD,
+        password::PasswordError::CorruptionError
+        | password::PasswordError::OutdatedRoosterBinaryError
+        | password::PasswordError::NoUpgradeError => exit_code::UNREADABLE_PASSWORD_FILE,
+        _ => exit_code::ERROR,
+    })?;
+    #[cfg(unix)]
+    agent::remember(&store);
+    Ok(store)
 }
 
 fn get_password_store_from_input_interactive(
@@ -111,8 +249,10 @@ fn get_password_store_from_input_interactive(
     retries: i32,
     force_upgrade: bool,
     retry: bool,
+    given_master_password: Option<&SafeString>,
+    keyfile_digest: Option<&SafeVec>,
     io: &mut impl CliInputOutput,
-) -> Result<password::v2::PasswordStore, password::PasswordError> {
+) -> Result<password::v3::PasswordStore, password::PasswordError> {
     if retries == 0 {
         io.error(
             "Decryption of your Rooster file keeps failing. \
@@ -123,13 +263,35 @@ fn get_password_store_from_input_interactive(
     }
 
     if retry {
+        // Trying the same password again won't help.
+        if given_master_password.is_some() {
+            io.error(
+                match keyfile_digest {
+                    Some(_) => "Woops, that's not the right master password or keyfile.",
+                    None => "Woops, that's not the right master password.",
+                },
+                OutputType::Error,
+            );
+            return Err(password::PasswordError::DecryptionError);
+        }
+
         io.error(
-            "Woops, that's not the right password. Let's try again.",
+            match keyfile_digest {
+                Some(_) => {
+                    "Woops, that's not the right password, or not the right keyfile. \
+                     Let's try again."
+                }
+                None => "Woops, that's not the right password. Let's try again.",
+            },
             OutputType::Error,
         );
     }
 
-    let master_password = match ask_master_password(io) {
+    let master_password = match given_master_password {
+        Some(p) => Ok(p.clone()),
+        None => ask_master_password(io),
+    };
+    let master_password = match master_password {
         Ok(p) => p,
         Err(err) => {
             io.error(
@@ -143,7 +305,8 @@ fn get_password_store_from_input_interactive(
         }
     };
 
-    match get_password_store_from_input(&input, &master_password, force_upgrade) {
+    match get_password_store_from_input(&input, &master_password, keyfile_digest, force_upgrade)
+    {
         Ok(store) => {
             return Ok(store);
         }
@@ -151,6 +314,13 @@ fn get_password_store_from_input_interactive(
             io.error("Your Rooster file is corrupted.", OutputType::Error);
             return Err(password::PasswordError::CorruptionError);
         }
+        Err(password::PasswordError::KeyfileRequiredError) => {
+            io.error(
+                "Woops, your password file needs a keyfile along with your master password.",
+                OutputType::Error,
+            );
+            return Err(password::PasswordError::KeyfileRequiredError);
+        }
         Err(password::PasswordError::OutdatedRoosterBinaryError) => {
             io.error(
                 "I could not open the Rooster file because your version of Rooster is outdated.",
@@ -170,8 +340,8 @@ fn get_password_store_from_input_interactive(
             return Err(password::PasswordError::Io(err));
         }
         Err(password::PasswordError::NeedUpgradeErrorFromV1) => {
-            io.error("Your Rooster file has version 1. You need to upgrade to version 2.\n\nWARNING: If in doubt, it could mean you've been hacked. Only \
-                 proceed if you recently upgraded your Rooster installation.\nUpgrade to version 2? [y/n]", OutputType::Error
+            io.error("Your Rooster file has version 1. You need to upgrade to version 3.\n\nWARNING: If in doubt, it could mean you've been hacked. Only \
+                 proceed if you recently upgraded your Rooster installation.\nUpgrade to version 3? [y/n]", OutputType::Error
             );
             loop {
                 match io.read_line() {
@@ -179,14 +349,20 @@ fn get_password_store_from_input_interactive(
                         if line.starts_with('y') {
                             // This time we'll try to upgrade
                             return get_password_store_from_input_interactive(
-                                &input, retries, true, false, io,
+                                &input,
+                                retries,
+                                true,
+                                false,
+                                given_master_password,
+                                keyfile_digest,
+                                io,
                             );
                         } else if line.starts_with('n') {
                             // The user doesn't want to upgrade, that's fine
                             return Err(password::PasswordError::NoUpgradeError);
                         } else {
                             io.error(
-                                "I did not get that. Upgrade from v1 to v2? [y/n]",
+                                "I did not get that. Upgrade from v1 to v3? [y/n]",
                                 OutputType::Error,
                             );
                         }
@@ -203,7 +379,15 @@ fn get_password_store_from_input_interactive(
             }
         }
         _ => {
-            return get_password_store_from_input_interactive(&input, retries - 1, false, true, io);
+            return get_password_store_from_input_interactive(
+                &input,
+                retries - 1,
+                false,
+                true,
+                given_master_password,
+                keyfile_digest,
+                io,
+            );
         }
     }
 }
@@ -211,10 +395,15 @@ fn get_password_store_from_input_interactive(
 fn get_password_store_from_input(
     input: &SafeVec,
     master_password: &SafeString,
+    keyfile_digest: Option<&SafeVec>,
     upgrade: bool,
-) -> Result<password::v2::PasswordStore, password::PasswordError> {
+) -> Result<password::v3::PasswordStore, password::PasswordError> {
     // Try to open the file as is.
-    match password::v2::PasswordStore::from_input(master_password.clone(), input.clone()) {
+    match password::v3::PasswordStore::from_input(
+        master_password.clone(),
+        keyfile_digest.cloned(),
+        input.clone(),
+    ) {
         Ok(store) => {
             return Ok(store);
         }
@@ -224,6 +413,11 @@ fn get_password_store_from_input(
         Err(password::PasswordError::OutdatedRoosterBinaryError) => {
             return Err(password::PasswordError::OutdatedRoosterBinaryError);
         }
+        Err(password::PasswordError::NeedUpgradeErrorFromV2) => {
+            // Version 2 files are upgraded without asking, they'll be saved as version 3 on the
+            // next change.
+            return password::upgrade_from_v2(master_password.clone(), input.clone());
+        }
         Err(password::PasswordError::NeedUpgradeErrorFromV1) => {
             if !upgrade {
                 return Err(password::PasswordError::NeedUpgradeErrorFromV1);
@@ -254,13 +448,45 @@ pub fn main_with_args(
     io: &mut impl CliInputOutput,
     rooster_file_path: &PathBuf,
 ) -> i32 {
-    let matches = Command::new("rooster")
+    let command = Command::new("rooster")
         .help_expected(true)
         .disable_help_subcommand(true)
         .subcommand_required(true)
         .arg_required_else_help(true)
         .about("Welcome to Rooster, a simple password manager")
         .version(env!("CARGO_PKG_VERSION"))
+        .arg(
+            Arg::new("osc52")
+                .long("osc52")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Copy to the clipboard of your terminal with an OSC 52 escape sequence, which works over SSH (the default when SSH_TTY is set or there is no clipboard tool)"),
+        )
+        .arg(output::arg())
+        .arg(
+            Arg::new("non-interactive")
+                .long("non-interactive")
+                .visible_alias("yes")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Fail instead of asking anything, for scripts, cron and CI (exit code 10)"),
+        )
+        .arg(
+            Arg::new("keyfile")
+                .long("keyfile")
+                .value_name("PATH")
+                .global(true)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("The keyfile that unlocks your password file along with your master password, if it needs one (defaults to the ROOSTER_KEYFILE environment variable)"),
+        )
+        .arg(
+            Arg::new("master-password-file")
+                .long("master-password-file")
+                .value_name("PATH")
+                .global(true)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Read the master password from this file instead of asking for it"),
+        )
         .subcommand(
             Command::new("init")
                 .about("Create a new password file")
@@ -291,11 +517,12 @@ pub fn main_with_args(
                         .short('s')
                         .long("show")
                         .help("Show the password instead of copying it to the clipboard"),
-                ),
+                )
+                .args(fields::args()),
         )
         .subcommand(
             Command::new("change")
-                .about("Change a password manually")
+                .about("Change a password manually, or only its URL, notes and custom fields")
                 .arg(
                     Arg::new("app")
                         .required(true)
@@ -307,6 +534,14 @@ pub fn main_with_args(
                         .short('s')
                         .long("show")
                         .help("Show the password instead of copying it to the clipboard"),
+                )
+                .args(fields::args())
+                .arg(
+                    Arg::new("remove-field")
+                        .long("remove-field")
+                        .action(ArgAction::Append)
+                        .value_name("NAME")
+                        .help("Remove a custom field"),
                 ),
         )
         .subcommand(
@@ -336,25 +571,52 @@ pub fn main_with_args(
                         .long("show")
                         .help("Show the password instead of copying it to the clipboard"),
                 )
+                .args(generate::args()),
+        )
+        .subcommand(
+            Command::new("regenerate")
+                .about("Regenerate a previously existing password")
+                .arg(
+                    Arg::new("app")
+                        .required(true)
+                        .help("The name of the app (fuzzy-matched)"),
+                )
                 .arg(
-                    Arg::new("alnum")
+                    Arg::new("show")
                         .action(ArgAction::SetTrue)
-                        .short('a')
-                        .long("alnum")
-                        .help("Only use alpha numeric (a-z, A-Z, 0-9) in generated passwords"),
+                        .short('s')
+                        .long("show")
+                        .help("Show the password instead of copying it to the clipboard"),
                 )
+                .args(generate::args()),
+        )
+        .subcommand(
+            Command::new("passphrase")
+                .about("Generate a passphrase without saving it, for instance for disk encryption")
                 .arg(
-                    Arg::new("length")
-                        .short('l')
-                        .long("length")
-                        .default_value("32")
-                        .help("Set a custom length for the generated password")
-                        .value_parser(validate_arg_usize),
+                    Arg::new("show")
+                        .action(ArgAction::SetTrue)
+                        .short('s')
+                        .long("show")
+                        .help("Show the passphrase instead of copying it to the clipboard"),
+                )
+                .args(generate::passphrase_args())
+                .mut_arg("words", |arg| arg.default_value("6")),
+        )
+        .subcommand(
+            Command::new("clear-clipboard")
+                .about("Wipe the clipboard after a while, if it still holds the value whose digest is on stdin")
+                .hide(true)
+                .arg(
+                    Arg::new("seconds")
+                        .required(true)
+                        .help("How many seconds to wait")
+                        .value_parser(clap::value_parser!(u64)),
                 ),
         )
         .subcommand(
-            Command::new("regenerate")
-                .about("Regenerate a previously existing password")
+            Command::new("get")
+                .about("Retrieve a password")
                 .arg(
                     Arg::new("app")
                         .required(true)
@@ -368,24 +630,37 @@ pub fn main_with_args(
                         .help("Show the password instead of copying it to the clipboard"),
                 )
                 .arg(
-                    Arg::new("alnum")
+                    Arg::new("tag")
+                        .long("tag")
+                        .help("Only search the passwords with this tag"),
+                ),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("List the previous passwords of an app, or restore one")
+                .arg(
+                    Arg::new("app")
+                        .required(true)
+                        .help("The name of the app (fuzzy-matched)"),
+                )
+                .arg(
+                    Arg::new("show")
                         .action(ArgAction::SetTrue)
-                        .short('a')
-                        .long("alnum")
-                        .help("Only use alpha numeric (a-z, A-Z, 0-9) in generated passwords"),
+                        .short('s')
+                        .long("show")
+                        .help("Show the passwords instead of masking them"),
                 )
                 .arg(
-                    Arg::new("length")
-                        .short('l')
-                        .long("length")
-                        .default_value("32")
-                        .help("Set a custom length for the generated password")
+                    Arg::new("restore")
+                        .long("restore")
+                        .value_name("number")
+                        .help("Make the previous password with this number the current one")
                         .value_parser(validate_arg_usize),
                 ),
         )
         .subcommand(
-            Command::new("get")
-                .about("Retrieve a password")
+            Command::new("otp")
+                .about("Retrieve the current one-time code")
                 .arg(
                     Arg::new("app")
                         .required(true)
@@ -396,7 +671,7 @@ pub fn main_with_args(
                         .action(ArgAction::SetTrue)
                         .short('s')
                         .long("show")
-                        .help("Show the password instead of copying it to the clipboard"),
+                        .help("Show the code instead of copying it to the clipboard"),
                 ),
         )
         .subcommand(
@@ -427,7 +702,95 @@ pub fn main_with_args(
                         .help("Your new username for this account"),
                 ),
         )
-        .subcommand(Command::new("list").about("List all apps and usernames"))
+        .subcommand(
+            Command::new("list")
+                .about("List all apps and usernames")
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .help("Only list the passwords with this tag"),
+                ),
+        )
+        .subcommand(
+            Command::new("search")
+                .about("List the apps and usernames that match a query")
+                .arg(
+                    Arg::new("app")
+                        .required(true)
+                        .help("The name of the app (fuzzy-matched)"),
+                )
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .help("Only search the passwords with this tag"),
+                ),
+        )
+        .subcommand(
+            Command::new("audit")
+                .about("Find reused, weak, old and breached passwords, exits with 2 if it finds any")
+                .arg(
+                    Arg::new("show")
+                        .action(ArgAction::SetTrue)
+                        .short('s')
+                        .long("show")
+                        .help("Show the passwords instead of masking them"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .action(ArgAction::SetTrue)
+                        .long("json")
+                        .hide(true)
+                        .help("Same as --format json, kept for the scripts that already use it"),
+                )
+                .arg(
+                    Arg::new("min-score")
+                        .long("min-score")
+                        .default_value("3")
+                        .help("Report passwords with a lower strength score, from 0 (too guessable) to 4 (very unguessable)")
+                        .value_parser(clap::value_parser!(u8).range(0..=4)),
+                )
+                .arg(
+                    Arg::new("max-age")
+                        .long("max-age")
+                        .value_name("days")
+                        .default_value("365")
+                        .help("Report passwords that haven't changed in more days than this")
+                        .value_parser(validate_arg_u32),
+                )
+                .arg(
+                    Arg::new("breach-db")
+                        .long("breach-db")
+                        .value_name("path")
+                        .help("Report passwords found in a local copy of the Have I Been Pwned SHA-1 hashes, either the file sorted by hash or a directory of range files")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("tag")
+                .about("Organize your passwords with tags")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("Add a tag to a password")
+                        .arg(
+                            Arg::new("app")
+                                .required(true)
+                                .help("The name of the app (fuzzy-matched)"),
+                        )
+                        .arg(Arg::new("tag").required(true).help("The tag to add")),
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("Remove a tag from a password")
+                        .arg(
+                            Arg::new("app")
+                                .required(true)
+                                .help("The name of the app (fuzzy-matched)"),
+                        )
+                        .arg(Arg::new("tag").required(true).help("The tag to remove")),
+                ),
+        )
         .subcommand(
             Command::new("import")
                 .subcommand_required(true)
@@ -474,27 +837,86 @@ pub fn main_with_args(
                 ),
         )
         .subcommand(Command::new("set-master-password").about("Set your master password"))
+        .subcommand(
+            Command::new("slot")
+                .about("Let several master passwords open your password file, each in a key slot")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("Add a key slot with a master password of its own")
+                        .arg(
+                            Arg::new("name")
+                                .required(true)
+                                .help("The name of the key slot, for instance who it is for"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("Remove a key slot, so that its master password can't open your password file anymore")
+                        .arg(
+                            Arg::new("name")
+                                .required(true)
+                                .help("The name of the key slot"),
+                        ),
+                )
+                .subcommand(Command::new("list").about("List the key slots")),
+        )
+        .subcommand(
+            Command::new("set-keyfile")
+                .about("Require a keyfile along with your master password to unlock your password file")
+                .arg(
+                    Arg::new("path")
+                        .required_unless_present("remove")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("The new keyfile, any file that won't change, like random bytes from /dev/urandom"),
+                )
+                .arg(
+                    Arg::new("remove")
+                        .long("remove")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("path")
+                        .help("Only require the master password from now on"),
+                ),
+        )
         .subcommand(
             Command::new("set-scrypt-params")
                 .about("Set the key derivation parameters")
                 .arg(
                     Arg::new("log2n")
-                        .required(true)
+                        .required_unless_present("target-ms")
+                        .conflicts_with("target-ms")
                         .help("The log2n parameter")
                         .value_parser(validate_arg_u8),
                 )
                 .arg(
                     Arg::new("r")
-                        .required(true)
+                        .required_unless_present("target-ms")
+                        .conflicts_with("target-ms")
                         .help("The r parameter")
                         .value_parser(validate_arg_u32),
                 )
                 .arg(
                     Arg::new("p")
-                        .required(true)
+                        .required_unless_present("target-ms")
+                        .conflicts_with("target-ms")
                         .help("The p parameter")
                         .value_parser(validate_arg_u32),
                 )
+                .arg(
+                    Arg::new("target-ms")
+                        .long("target-ms")
+                        .help("Pick the strongest parameters that unlock within this many milliseconds on this machine")
+                        .value_parser(validate_arg_u32),
+                )
+                .arg(
+                    Arg::new("max-memory-mb")
+                        .long("max-memory-mb")
+                        .requires("target-ms")
+                        .default_value("1024")
+                        .help("The most memory the parameters picked by --target-ms may use, in MiB")
+                        .value_parser(validate_arg_u32),
+                )
                 .arg(
                     Arg::new("force")
                         .action(ArgAction::SetTrue)
@@ -503,8 +925,165 @@ pub fn main_with_args(
                         .help("Disable parameter checks"),
                 ),
         )
-        .get_matches_from(args);
+        .subcommand(
+            Command::new("set-kdf")
+                .about("Set the key derivation function and its parameters")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("argon2id")
+                        .about("Use Argon2id for key derivation")
+                        .arg(
+                            Arg::new("memory")
+                                .help("The memory parameter, in KiB (defaults to 19456)")
+                                .value_parser(validate_arg_u32),
+                        )
+                        .arg(
+                            Arg::new("iterations")
+                                .help("The number of iterations (defaults to 2)")
+                                .value_parser(validate_arg_u32),
+                        )
+                        .arg(
+                            Arg::new("parallelism")
+                                .help("The degree of parallelism (defaults to 1)")
+                                .value_parser(validate_arg_u32),
+                        )
+                        .arg(
+                            Arg::new("force")
+                                .action(ArgAction::SetTrue)
+                                .short('f')
+                                .long("force")
+                                .help("Disable parameter checks"),
+                        ),
+                ),
+        );
+    #[cfg(unix)]
+    let command = command
+        .arg(
+            Arg::new("master-password-fd")
+                .long("master-password-fd")
+                .value_name("FD")
+                .global(true)
+                .conflicts_with("master-password-file")
+                .value_parser(clap::value_parser!(i32).range(0..))
+                .help("Read the master password from this file descriptor instead of asking for it"),
+        )
+        .subcommand(
+            Command::new("agent")
+                .about("Remember your master password for a while, so that other commands don't ask for it")
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .value_name("SECONDS")
+                        .default_value("900")
+                        .value_parser(clap::value_parser!(u64))
+                        .help("Forget the master password after this many seconds without use, 0 to keep it until `rooster lock`"),
+                )
+                .arg(
+                    Arg::new("detach")
+                        .long("detach")
+                        .short('d')
+                        .action(ArgAction::SetTrue)
+                        .help("Run in the background and print where the agent listens, instead of staying in the foreground"),
+                )
+                .arg(
+                    Arg::new("listener-on-stdin")
+                        .long("listener-on-stdin")
+                        .hide(true)
+                        .action(ArgAction::SetTrue)
+                        .help("Serve the listening socket handed over on stdin, used by --detach"),
+                )
+                .after_help(
+                    "Without --detach, the agent stays in the foreground until it is stopped: run it in another \
+                     terminal, or in the background with `rooster agent &`.",
+                ),
+        )
+        .subcommand(Command::new("lock").about("Make the agent forget your master password now"));
+    let matches = match command.try_get_matches_from(args) {
+        Ok(matches) => matches,
+        Err(err) => {
+            let _ = err.print();
+            // Asking for help or the version is not an error.
+            return if err.use_stderr() { exit_code::USAGE } else { 0 };
+        }
+    };
+
+    let interactive = !matches.get_flag("non-interactive");
+    let mut io = script::ScriptInputOutput::new(io, interactive);
+    let code = run_command(&matches, &mut io, rooster_file_path);
+    if code != 0 && io.input_refused() {
+        return exit_code::INPUT_REQUIRED;
+    }
+    code
+}
+
+/// Hashes the keyfile from `--keyfile` or `ROOSTER_KEYFILE`, if any.
+fn get_keyfile_digest_from_args(
+    matches: &clap::ArgMatches,
+    io: &mut impl CliInputOutput,
+) -> Result<Option<SafeVec>, i32> {
+    let path = match matches.get_one::<PathBuf>("keyfile") {
+        Some(path) => path.clone(),
+        None => match env::var_os(KEYFILE_ENV_VAR) {
+            Some(path) => PathBuf::from(path),
+            None => return Ok(None),
+        },
+    };
+    read_keyfile_digest(&path, io).map(Some)
+}
+
+fn read_keyfile_digest(path: &Path, io: &mut impl CliInputOutput) -> Result<SafeVec, i32> {
+    password::keyfile::digest(path).map_err(|err| {
+        io.error(
+            format!(
+                "Woops, I could not read your keyfile {} (reason: {}).",
+                path.display(),
+                err
+            ),
+            OutputType::Error,
+        );
+        exit_code::ERROR
+    })
+}
+
+/// Reads the master password from `--master-password-fd` or `--master-password-file`, if any.
+fn get_master_password_from_args(
+    matches: &clap::ArgMatches,
+    io: &mut impl CliInputOutput,
+) -> Result<Option<SafeString>, i32> {
+    let master_password = if let Some(path) = matches.get_one::<PathBuf>("master-password-file")
+    {
+        script::read_master_password_from_file(path)
+    } else {
+        #[cfg(unix)]
+        match matches.get_one::<i32>("master-password-fd") {
+            Some(fd) => script::read_master_password_from_fd(*fd),
+            None => return Ok(None),
+        }
+        #[cfg(not(unix))]
+        return Ok(None);
+    };
+
+    match master_password {
+        Ok(master_password) => Ok(Some(master_password)),
+        Err(err) => {
+            io.error(
+                format!(
+                    "Woops, I could not read your master password (reason: {}).",
+                    err
+                ),
+                OutputType::Error,
+            );
+            Err(exit_code::ERROR)
+        }
+    }
+}
 
+fn run_command(
+    matches: &clap::ArgMatches,
+    io: &mut impl CliInputOutput,
+    rooster_file_path: &PathBuf,
+) -> i32 {
     let subcommand = matches.subcommand_name().unwrap();
 
     let command_matches = matches.subcommand_matches(subcommand).unwrap();
@@ -516,6 +1095,36 @@ pub fn main_with_args(
         }
     }
 
+    if subcommand == "passphrase" {
+        return match commands::passphrase::callback_exec(command_matches, io) {
+            Err(i) => i,
+            _ => 0,
+        };
+    }
+
+    #[cfg(unix)]
+    if subcommand == "agent" {
+        return match commands::agent::callback_exec(command_matches, io) {
+            Err(i) => i,
+            _ => 0,
+        };
+    }
+
+    #[cfg(unix)]
+    if subcommand == "lock" {
+        return match commands::lock::callback_exec(io) {
+            Err(i) => i,
+            _ => 0,
+        };
+    }
+
+    if subcommand == "clear-clipboard" {
+        return match commands::clear_clipboard::callback_exec(command_matches) {
+            Err(i) => i,
+            _ => 0,
+        };
+    }
+
     let password_file_path_as_string = rooster_file_path.to_string_lossy().into_owned();
 
     if !rooster_file_path.exists() {
@@ -533,9 +1142,24 @@ pub fn main_with_args(
             format!("    {}", example_environment_variable_configuration()),
             OutputType::Standard,
         );
-        return 1;
+        return exit_code::NO_PASSWORD_FILE;
     }
 
+    let master_password = match get_master_password_from_args(matches, io) {
+        Err(code) => return code,
+        Ok(master_password) => master_password,
+    };
+    let keyfile_digest = match get_keyfile_digest_from_args(matches, io) {
+        Err(code) => return code,
+        Ok(keyfile_digest) => keyfile_digest,
+    };
+
+    let read_only = is_read_only_command(subcommand, command_matches);
+    let _lock = match lock_password_file(rooster_file_path, !read_only, io) {
+        Err(code) => return code,
+        Ok(lock) => lock,
+    };
+
     let mut file = match open_password_file(password_file_path_as_string.deref()) {
         Ok(file) => file,
         Err(err) => {
@@ -545,6 +1169,7 @@ pub fn main_with_args(
                         "Woops, I can't find your password file. Run `rooster init` to create one.",
                         OutputType::Error,
                     );
+                    return exit_code::NO_PASSWORD_FILE;
                 }
                 _ => {
                     io.error(
@@ -556,11 +1181,23 @@ pub fn main_with_args(
                     );
                 }
             }
-            return 1;
+            return exit_code::UNREADABLE_PASSWORD_FILE;
         }
     };
 
-    let mut store = match get_password_store(&mut file, io) {
+    let input = match read_password_file(&mut file, io) {
+        Err(code) => return code,
+        Ok(input) => input,
+    };
+    let input_digest = digest_password_file(input.deref());
+
+    let mut store = match get_password_store(
+        &input,
+        !needs_master_password(subcommand),
+        master_password.as_ref(),
+        keyfile_digest.as_ref(),
+        io,
+    ) {
         Err(code) => return code,
         Ok(store) => store,
     };
@@ -572,10 +1209,18 @@ pub fn main_with_args(
         "generate" => commands::generate::callback_exec,
         "regenerate" => commands::regenerate::callback_exec,
         "list" => commands::list::callback_exec,
+        "search" => commands::search::callback_exec,
+        "audit" => commands::audit::callback_exec,
+        "history" => commands::history::callback_exec,
+        "tag" => commands::tag::callback_exec,
+        "otp" => commands::otp::callback_exec,
         "import" => commands::import::callback_exec,
         "export" => commands::export::callback_exec,
         "set-master-password" => commands::set_master_password::callback_exec,
         "set-scrypt-params" => commands::set_scrypt_params::callback_exec,
+        "set-kdf" => commands::set_kdf::callback_exec,
+        "set-keyfile" => commands::set_keyfile::callback_exec,
+        "slot" => commands::slot::callback_exec,
         "rename" => commands::rename::callback_exec,
         "transfer" => commands::transfer::callback_exec,
         "change" => commands::change::callback_exec,
@@ -586,9 +1231,38 @@ pub fn main_with_args(
         return code;
     }
 
-    if let Err(code) = sync_password_store(&mut store, &mut file, io) {
+    if read_only {
+        return 0;
+    }
+
+    if let Err(code) = sync_password_store(&mut store, rooster_file_path, &input_digest, io) {
         return code;
     }
 
+    // The key changes along with the master password or the key derivation function.
+    #[cfg(unix)]
+    if needs_master_password(subcommand) {
+        agent::remember(&store);
+    }
+
     return 0;
 }
+
+#[cfg(test)]
+mod test {
+    use super::{digest_password_file, password_file_changed};
+    use std::fs;
+
+    #[test]
+    fn test_password_file_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("passwords.rooster");
+        fs::write(&path, b"original").unwrap();
+        let digest = digest_password_file(b"original");
+
+        assert!(!password_file_changed(&path, &digest).unwrap());
+
+        fs::write(&path, b"changed by another program").unwrap();
+        assert!(password_file_changed(&path, &digest).unwrap());
+    }
+}
@@ -1,4 +1,5 @@
-use crate::password::v2::{Password, PasswordStore};
+use crate::exit_code;
+use crate::password::v3::{Password, PasswordStore};
 use rclio::{CliInputOutput, OutputType};
 
 /// Used to indicate lists should have a number, ie: 23 Google my.account@gmail.com
@@ -73,7 +74,7 @@ fn request_password_index_from_stdin(
     passwords: &Vec<&Password>,
     prompt: &str,
     io: &mut impl CliInputOutput,
-) -> usize {
+) -> Result<usize, i32> {
     assert!(!passwords.is_empty());
 
     // Read the index from the command line and convert to a number
@@ -106,7 +107,7 @@ fn request_password_index_from_stdin(
                             continue;
                         }
 
-                        return index - 1;
+                        return Ok(index - 1);
                     }
                     Err(err) => {
                         io.write(
@@ -117,14 +118,12 @@ fn request_password_index_from_stdin(
                 };
             }
             Err(err) => {
-                io.write(
-                    format!(
-                        "I couldn't read that (reason: {}). Let's try again (1 to {}): ",
-                        err,
-                        passwords.len()
-                    ),
-                    OutputType::Standard,
+                io.nl(OutputType::Standard);
+                io.error(
+                    format!("Woops, I couldn't read your choice (reason: {}).", err),
+                    OutputType::Error,
                 );
+                return Err(exit_code::ERROR);
             }
         }
     }
@@ -135,7 +134,7 @@ fn choose_password_in_list(
     with_numbers: bool,
     prompt: &str,
     io: &mut impl CliInputOutput,
-) -> usize {
+) -> Result<usize, i32> {
     print_list_of_passwords(passwords, with_numbers, io);
     io.nl(OutputType::Standard);
     request_password_index_from_stdin(passwords, prompt, io)
@@ -147,32 +146,65 @@ pub fn search_and_choose_password<'a>(
     with_numbers: bool,
     prompt: &str,
     io: &mut impl CliInputOutput,
-) -> Option<&'a Password> {
-    let passwords = store.search_passwords(query);
+) -> Result<&'a Password, i32> {
+    search_and_choose_password_with_tag(store, query, None, with_numbers, prompt, io)
+}
+
+/// Same as `search_and_choose_password`, but only among passwords with the tag, if any.
+pub fn search_and_choose_password_with_tag<'a>(
+    store: &'a PasswordStore,
+    query: &str,
+    tag: Option<&str>,
+    with_numbers: bool,
+    prompt: &str,
+    io: &mut impl CliInputOutput,
+) -> Result<&'a Password, i32> {
+    let passwords: Vec<&Password> = store
+        .search_passwords(query)
+        .into_iter()
+        .filter(|p| match tag {
+            Some(tag) => p.has_tag(tag),
+            None => true,
+        })
+        .collect();
     if passwords.len() == 0 {
-        io.error(
-            format!("Woops, I can't find any passwords for \"{}\".", query),
-            OutputType::Error,
-        );
-        return None;
+        match tag {
+            Some(tag) => io.error(
+                format!(
+                    "Woops, I can't find any passwords for \"{}\" tagged \"{}\".",
+                    query, tag
+                ),
+                OutputType::Error,
+            ),
+            None => io.error(
+                format!("Woops, I can't find any passwords for \"{}\".", query),
+                OutputType::Error,
+            ),
+        }
+        return Err(exit_code::NO_MATCHING_PASSWORD);
     }
 
-    if let Some(&password) = passwords
+    // When the query is exactly the app name, we only need to choose between its accounts
+    let exact_matches: Vec<&Password> = passwords
         .iter()
-        .find(|p| p.name.to_lowercase() == query.to_lowercase())
-    {
-        return Some(&password);
-    }
+        .filter(|p| p.name.to_lowercase() == query.to_lowercase())
+        .cloned()
+        .collect();
+    let passwords = match exact_matches.len() {
+        0 => passwords,
+        1 => return Ok(exact_matches[0]),
+        _ => exact_matches,
+    };
 
-    let index = choose_password_in_list(&passwords, with_numbers, prompt, io);
-    Some(passwords[index])
+    let index = choose_password_in_list(&passwords, with_numbers, prompt, io)?;
+    Ok(passwords[index])
 }
 
 #[cfg(test)]
 mod test {
     use super::get_list_of_passwords;
     use crate::list::{WITHOUT_NUMBERS, WITH_NUMBERS};
-    use crate::password::v2::Password;
+    use crate::password::v3::Password;
     use rtoolbox::safe_string::SafeString;
 
     // Creates a list of at least two passwords, and more if specified
@@ -0,0 +1,114 @@
+//! Advisory locking of the password file, so that concurrent Rooster processes cannot
+//! overwrite each other's changes.
+//!
+//! The password file itself is replaced on every save (see `atomic_file`), so the lock is taken
+//! on a separate `.lock` file next to it. The lock file contains the PID of the last process
+//! that took the lock, which we show to the user when the password file is in use.
+
+use crate::atomic_file;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Error as IoError, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum LockError {
+    /// Another process holds a conflicting lock, maybe with its PID.
+    InUse(Option<u32>),
+    Io(IoError),
+}
+
+impl From<IoError> for LockError {
+    fn from(err: IoError) -> LockError {
+        LockError::Io(err)
+    }
+}
+
+/// A lock on the password file, released when dropped.
+pub struct PasswordFileLock {
+    _file: File,
+}
+
+fn lock_file_path(password_file_path: &Path) -> Result<PathBuf, LockError> {
+    // Symlinks to the same password file must share the same lock.
+    let mut path = atomic_file::resolve_symlinks(password_file_path)?.into_os_string();
+    path.push(".lock");
+    Ok(PathBuf::from(path))
+}
+
+fn read_pid(file: &mut File) -> Option<u32> {
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse::<u32>().ok()
+}
+
+fn write_pid(file: &mut File) -> Result<(), LockError> {
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(format!("{}\n", std::process::id()).as_bytes())?;
+    Ok(())
+}
+
+/// Locks the password file without waiting.
+///
+/// Exclusive locks are for commands that change the password file, shared locks for commands
+/// that only read it. Several shared locks can be held at once.
+pub fn lock(password_file_path: &Path, exclusive: bool) -> Result<PasswordFileLock, LockError> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(lock_file_path(password_file_path)?)?;
+
+    let result = if exclusive {
+        file.try_lock()
+    } else {
+        file.try_lock_shared()
+    };
+
+    match result {
+        Ok(()) => {
+            write_pid(&mut file)?;
+            Ok(PasswordFileLock { _file: file })
+        }
+        Err(TryLockError::WouldBlock) => Err(LockError::InUse(read_pid(&mut file))),
+        Err(TryLockError::Error(err)) => Err(LockError::Io(err)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{lock, LockError};
+
+    #[test]
+    fn test_exclusive_lock_excludes_everyone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("passwords.rooster");
+
+        let held = lock(&path, true).unwrap();
+        match lock(&path, true) {
+            Err(LockError::InUse(Some(pid))) => assert_eq!(pid, std::process::id()),
+            _ => panic!(),
+        }
+        match lock(&path, false) {
+            Err(LockError::InUse(_)) => {}
+            _ => panic!(),
+        }
+
+        drop(held);
+        assert!(lock(&path, true).is_ok());
+    }
+
+    #[test]
+    fn test_shared_locks_exclude_writers_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("passwords.rooster");
+
+        let _first = lock(&path, false).unwrap();
+        let _second = lock(&path, false).unwrap();
+        match lock(&path, true) {
+            Err(LockError::InUse(_)) => {}
+            _ => panic!(),
+        }
+    }
+}
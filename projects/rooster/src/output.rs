@@ -0,0 +1,208 @@
+//! Output that scripts can read, with `--format json` or `--format tsv`.
+
+use crate::exit_code;
+use crate::ffi;
+use crate::password::v3::Password;
+use clap::{Arg, ArgMatches};
+use rclio::{CliInputOutput, OutputType};
+use serde::Serialize;
+use std::ops::Deref;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Sentences and aligned columns, for people
+    Text,
+    Json,
+    /// One line per password and tab separated columns, without a header
+    Tsv,
+}
+
+pub fn arg() -> Arg {
+    Arg::new("format")
+        .long("format")
+        .global(true)
+        .value_parser(["text", "json", "tsv"])
+        .help("How to print the results of list, search, get and audit: text by default, or tsv for list and search when not printing to a terminal")
+}
+
+fn get_format(matches: &ArgMatches) -> Option<Format> {
+    match matches.get_one::<String>("format").map(|f| f.as_str()) {
+        Some("json") => Some(Format::Json),
+        Some("tsv") => Some(Format::Tsv),
+        Some("text") => Some(Format::Text),
+        _ => None,
+    }
+}
+
+/// The format asked for with `--format`, or text.
+pub fn format(matches: &ArgMatches) -> Format {
+    get_format(matches).unwrap_or(Format::Text)
+}
+
+/// Same as `format`, but lists piped to other programs are TSV unless asked otherwise, so that
+/// they don't depend on the length of the longest app name.
+pub fn list_format(matches: &ArgMatches, io: &impl CliInputOutput) -> Format {
+    match get_format(matches) {
+        Some(format) => format,
+        None if io.is_stdout_tty() => Format::Text,
+        None => Format::Tsv,
+    }
+}
+
+/// A password as printed for scripts. The password itself is only there when asked for.
+#[derive(Serialize)]
+struct Record {
+    app: String,
+    username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    tags: Vec<String>,
+    created_at: ffi::time_t,
+    updated_at: ffi::time_t,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+}
+
+impl Record {
+    fn new(password: &Password, show: bool) -> Record {
+        Record {
+            app: password.name.clone(),
+            username: password.username.clone(),
+            url: password.url.clone(),
+            tags: password.tags.clone(),
+            created_at: password.created_at,
+            updated_at: password.updated_at,
+            password: if show {
+                Some(password.password.deref().clone())
+            } else {
+                None
+            },
+        }
+    }
+
+    fn to_tsv(&self) -> String {
+        let mut columns = vec![
+            escape_tsv(&self.app),
+            escape_tsv(&self.username),
+            escape_tsv(self.url.as_deref().unwrap_or("")),
+            escape_tsv(&self.tags.join(",")),
+            self.created_at.to_string(),
+            self.updated_at.to_string(),
+        ];
+        if let Some(ref password) = self.password {
+            columns.push(escape_tsv(password));
+        }
+        columns.join("\t")
+    }
+}
+
+/// Escapes what would otherwise break columns or lines, the same way `\t` is written in Rust.
+fn escape_tsv(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn to_json(value: &impl Serialize, io: &mut impl CliInputOutput) -> Result<String, i32> {
+    serde_json::to_string(value).map_err(|json_err| {
+        io.error(
+            format!(
+                "Woops, I could not encode the passwords into JSON (reason: {:?}).",
+                json_err
+            ),
+            OutputType::Error,
+        );
+        exit_code::ERROR
+    })
+}
+
+/// Prints passwords as a JSON array or as TSV lines.
+pub fn print_passwords(
+    passwords: &[&Password],
+    show: bool,
+    format: Format,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let records: Vec<Record> = passwords.iter().map(|p| Record::new(p, show)).collect();
+    match format {
+        Format::Json => {
+            let json = to_json(&records, io)?;
+            io.writeln(json, OutputType::Standard);
+        }
+        Format::Tsv => {
+            for record in records.iter() {
+                io.writeln(record.to_tsv(), OutputType::Standard);
+            }
+        }
+        Format::Text => unreachable!("text is printed by each command"),
+    }
+    Ok(())
+}
+
+/// Prints one password as a JSON object or as a TSV line.
+pub fn print_password(
+    password: &Password,
+    show: bool,
+    format: Format,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let record = Record::new(password, show);
+    match format {
+        Format::Json => {
+            let json = to_json(&record, io)?;
+            io.writeln(json, OutputType::Standard);
+        }
+        Format::Tsv => io.writeln(record.to_tsv(), OutputType::Standard),
+        Format::Text => unreachable!("text is printed by each command"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape_tsv, Record};
+    use crate::password::v3::Password;
+    use rtoolbox::safe_string::SafeString;
+
+    #[test]
+    fn test_escape_tsv() {
+        assert_eq!(escape_tsv("Google"), "Google");
+        assert_eq!(escape_tsv("a\tb\nc\\d\re"), "a\\tb\\nc\\\\d\\re");
+    }
+
+    #[test]
+    fn test_record() {
+        let mut password = Password::new(
+            "Google".to_string(),
+            "me@example.com".to_string(),
+            SafeString::from_string("s3cr3t".to_string()),
+        );
+        password.created_at = 1;
+        password.updated_at = 2;
+        password.tags = vec!["work".to_string(), "mail".to_string()];
+
+        let record = Record::new(&password, false);
+        assert_eq!(record.to_tsv(), "Google\tme@example.com\t\twork,mail\t1\t2");
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"app":"Google","username":"me@example.com","tags":["work","mail"],"created_at":1,"updated_at":2}"#
+        );
+
+        let record = Record::new(&password, true);
+        assert_eq!(
+            record.to_tsv(),
+            "Google\tme@example.com\t\twork,mail\t1\t2\ts3cr3t"
+        );
+        assert!(serde_json::to_string(&record)
+            .unwrap()
+            .ends_with(r#""password":"s3cr3t"}"#));
+    }
+}
@@ -0,0 +1,403 @@
+use argon2::{Algorithm, Argon2, Params as Argon2Params, Version};
+use rtoolbox::safe_vec::SafeVec;
+use scrypt::{scrypt, Params as ScryptParams};
+use std::time::{Duration, Instant};
+
+/// Length of the key derived from the user password, in bytes
+pub const KEY_LEN: usize = 32;
+
+/// Scrypt parameters
+pub const SCRYPT_PARAM_LOG2_N: u8 = 12;
+pub const SCRYPT_PARAM_R: u32 = 8;
+pub const SCRYPT_PARAM_P: u32 = 1;
+
+/// The highest log2n we try when calibrating scrypt
+const SCRYPT_CALIBRATION_MAX_LOG2_N: u8 = 30;
+
+/// The most memory a key derivation function may use, so that a tampered password file can't
+/// make us run out of memory before the header is authenticated
+pub const MAX_MEMORY_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// The most scrypt lanes and argon2id passes we accept, so that a tampered password file can't
+/// make deriving a key take forever either
+pub const MAX_SCRYPT_PARAM_P: u32 = 16;
+pub const MAX_ARGON2ID_PARAM_ITERATIONS: u32 = 64;
+
+/// Argon2id parameters, as recommended by OWASP
+pub const ARGON2ID_PARAM_MEMORY_KIB: u32 = 19 * 1024;
+pub const ARGON2ID_PARAM_ITERATIONS: u32 = 2;
+pub const ARGON2ID_PARAM_PARALLELISM: u32 = 1;
+
+/// The function used to derive the encryption key from the master password, with its parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kdf {
+    Scrypt {
+        log2_n: u8,
+        r: u32,
+        p: u32,
+    },
+    Argon2id {
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+}
+
+impl Kdf {
+    pub fn default_scrypt() -> Kdf {
+        Kdf::Scrypt {
+            log2_n: SCRYPT_PARAM_LOG2_N,
+            r: SCRYPT_PARAM_R,
+            p: SCRYPT_PARAM_P,
+        }
+    }
+
+    /// How much memory deriving a key needs, in bytes.
+    pub fn memory_bytes(&self) -> u128 {
+        match *self {
+            // The N blocks of V, plus the p blocks of B
+            Kdf::Scrypt { log2_n, r, p } => {
+                128 * r as u128 * ((1u128 << log2_n.min(127)) + p as u128)
+            }
+            Kdf::Argon2id { memory_kib, .. } => memory_kib as u128 * 1024,
+        }
+    }
+
+    /// Checks that the parameters are accepted by the key derivation function, that they don't
+    /// need more than `MAX_MEMORY_BYTES` and that they stay under `MAX_SCRYPT_PARAM_P` and
+    /// `MAX_ARGON2ID_PARAM_ITERATIONS`.
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Kdf::Scrypt { log2_n, r, p } => ScryptParams::new(log2_n, r, p, KEY_LEN)
+                .map(|_| ())
+                .map_err(|err| err.to_string()),
+            Kdf::Argon2id {
+                memory_kib,
+                iterations,
+                parallelism,
+            } => Argon2Params::new(memory_kib, iterations, parallelism, Some(KEY_LEN))
+                .map(|_| ())
+                .map_err(|err| err.to_string()),
+        }?;
+        if self.memory_bytes() > MAX_MEMORY_BYTES as u128 {
+            return Err(format!(
+                "it would need more than {} MiB of memory",
+                MAX_MEMORY_BYTES / 1024 / 1024
+            ));
+        }
+        match *self {
+            Kdf::Scrypt { p, .. } if p > MAX_SCRYPT_PARAM_P => {
+                Err(format!("p must be at most {}", MAX_SCRYPT_PARAM_P))
+            }
+            Kdf::Argon2id { iterations, .. } if iterations > MAX_ARGON2ID_PARAM_ITERATIONS => {
+                Err(format!(
+                    "iterations must be at most {}",
+                    MAX_ARGON2ID_PARAM_ITERATIONS
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Derives a 256 bits encryption key from the secret, usually the master password.
+    ///
+    /// The parameters must have been checked with `validate` first.
+    pub fn derive_key(&self, secret: &[u8], salt: &[u8]) -> SafeVec {
+        let mut output = SafeVec::new(vec![0u8; KEY_LEN]);
+
+        match *self {
+            Kdf::Scrypt { log2_n, r, p } => {
+                let result = scrypt(
+                    secret,
+                    salt,
+                    &ScryptParams::new(log2_n, r, p, KEY_LEN).unwrap(),
+                    output.as_mut(),
+                );
+
+                if result.is_err() {
+                    panic!("Deriving scrypt key failed: {:?}", result);
+                }
+            }
+            Kdf::Argon2id {
+                memory_kib,
+                iterations,
+                parallelism,
+            } => {
+                let argon2 = Argon2::new(
+                    Algorithm::Argon2id,
+                    Version::V0x13,
+                    Argon2Params::new(memory_kib, iterations, parallelism, Some(KEY_LEN)).unwrap(),
+                );
+                let result =
+                    argon2.hash_password_into(secret, salt, output.as_mut());
+
+                if result.is_err() {
+                    panic!("Deriving argon2id key failed: {:?}", result);
+                }
+            }
+        }
+
+        assert_eq!(output.len(), KEY_LEN);
+
+        output
+    }
+}
+
+/// How much memory scrypt needs with these parameters, in bytes.
+pub fn scrypt_memory_bytes(log2_n: u8, r: u32) -> u64 {
+    128 * (r as u64) * (1u64 << log2_n)
+}
+
+/// Measures how long deriving a key takes on this machine.
+pub fn benchmark(kdf: &Kdf) -> Duration {
+    let start = Instant::now();
+    kdf.derive_key(b"benchmark password", &[0u8; 32]);
+    start.elapsed()
+}
+
+/// Finds the strongest scrypt parameters that derive a key within the target time and memory.
+///
+/// Only log2n is increased, r and p keep their default values. Parameters are never weaker than
+/// the defaults, even if the defaults are slower than the target. Returns the parameters along
+/// with how long deriving a key took with them.
+pub fn calibrate_scrypt<F>(
+    target: Duration,
+    max_memory_bytes: u64,
+    mut benchmark: F,
+) -> (Kdf, Duration)
+where
+    F: FnMut(&Kdf) -> Duration,
+{
+    let scrypt = |log2_n| Kdf::Scrypt {
+        log2_n,
+        r: SCRYPT_PARAM_R,
+        p: SCRYPT_PARAM_P,
+    };
+
+    let mut best = scrypt(SCRYPT_PARAM_LOG2_N);
+    let mut best_duration = benchmark(&best);
+
+    for log2_n in (SCRYPT_PARAM_LOG2_N + 1)..=SCRYPT_CALIBRATION_MAX_LOG2_N {
+        // Each step doubles the time it takes, so there's no point in measuring steps that are
+        // obviously too slow.
+        let candidate = scrypt(log2_n);
+        if best_duration * 2 > target
+            || scrypt_memory_bytes(log2_n, SCRYPT_PARAM_R) > max_memory_bytes
+            || candidate.validate().is_err()
+        {
+            break;
+        }
+
+        let duration = benchmark(&candidate);
+        if duration > target {
+            break;
+        }
+
+        best = candidate;
+        best_duration = duration;
+    }
+
+    (best, best_duration)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        calibrate_scrypt, scrypt_memory_bytes, Kdf, ARGON2ID_PARAM_ITERATIONS,
+        ARGON2ID_PARAM_MEMORY_KIB, ARGON2ID_PARAM_PARALLELISM,
+    };
+    use std::time::Duration;
+
+    fn default_argon2id() -> Kdf {
+        Kdf::Argon2id {
+            memory_kib: ARGON2ID_PARAM_MEMORY_KIB,
+            iterations: ARGON2ID_PARAM_ITERATIONS,
+            parallelism: ARGON2ID_PARAM_PARALLELISM,
+        }
+    }
+
+    #[test]
+    fn test_derive_key_returns_256_bits_key() {
+        let salt = [0u8; 32];
+        assert_eq!(
+            Kdf::default_scrypt().derive_key(b"hello world", &salt).len(),
+            32
+        );
+        assert_eq!(
+            default_argon2id().derive_key(b"hello world", &salt).len(),
+            32
+        );
+    }
+
+    #[test]
+    fn test_derive_key_depends_on_kdf_and_params() {
+        let salt = [0u8; 32];
+        let argon2id = Kdf::Argon2id {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let key = argon2id.derive_key(b"hello world", &salt);
+
+        assert_eq!(key, argon2id.derive_key(b"hello world", &salt));
+        assert_ne!(key, argon2id.derive_key(b"hello world", &[1u8; 32]));
+        assert_ne!(
+            key,
+            Kdf::Argon2id {
+                memory_kib: 1024,
+                iterations: 2,
+                parallelism: 1,
+            }
+            .derive_key(b"hello world", &salt)
+        );
+        assert_ne!(
+            key,
+            Kdf::Scrypt {
+                log2_n: 4,
+                r: 8,
+                p: 1
+            }
+            .derive_key(b"hello world", &salt)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Kdf::default_scrypt().validate().is_ok());
+        assert!(default_argon2id().validate().is_ok());
+        assert!(Kdf::Scrypt {
+            log2_n: 64,
+            r: 8,
+            p: 1
+        }
+        .validate()
+        .is_err());
+        assert!(Kdf::Argon2id {
+            memory_kib: 1,
+            iterations: 1,
+            parallelism: 1
+        }
+        .validate()
+        .is_err());
+
+        // Accepted by the key derivation functions, but too much memory
+        assert!(Kdf::Scrypt {
+            log2_n: 21,
+            r: 8,
+            p: 1
+        }
+        .validate()
+        .is_ok());
+        assert!(Kdf::Scrypt {
+            log2_n: 22,
+            r: 8,
+            p: 1
+        }
+        .validate()
+        .is_err());
+        assert!(Kdf::Scrypt {
+            log2_n: 12,
+            r: 8,
+            p: 1 << 23
+        }
+        .validate()
+        .is_err());
+        assert!(Kdf::Argon2id {
+            memory_kib: 4 * 1024 * 1024,
+            iterations: 1,
+            parallelism: 1
+        }
+        .validate()
+        .is_ok());
+        assert!(Kdf::Argon2id {
+            memory_kib: 4 * 1024 * 1024 + 1,
+            iterations: 1,
+            parallelism: 1
+        }
+        .validate()
+        .is_err());
+
+        // Accepted by the key derivation functions, but too much work
+        assert!(Kdf::Scrypt {
+            log2_n: 12,
+            r: 8,
+            p: 16
+        }
+        .validate()
+        .is_ok());
+        assert!(Kdf::Scrypt {
+            log2_n: 12,
+            r: 8,
+            p: 17
+        }
+        .validate()
+        .is_err());
+        assert!(Kdf::Argon2id {
+            memory_kib: 1024,
+            iterations: 64,
+            parallelism: 1
+        }
+        .validate()
+        .is_ok());
+        assert!(Kdf::Argon2id {
+            memory_kib: 1024,
+            iterations: 65,
+            parallelism: 1
+        }
+        .validate()
+        .is_err());
+    }
+
+    // Pretends that each increment of log2n doubles the time, starting at 1ms for log2n = 10.
+    fn fake_benchmark(kdf: &Kdf) -> Duration {
+        match *kdf {
+            Kdf::Scrypt { log2_n, .. } => Duration::from_millis(1 << (log2_n - 10)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_scrypt_memory_bytes() {
+        assert_eq!(scrypt_memory_bytes(12, 8), 4 * 1024 * 1024);
+        assert_eq!(scrypt_memory_bytes(20, 8), 1024 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_calibrate_scrypt_respects_target_time() {
+        let (kdf, duration) =
+            calibrate_scrypt(Duration::from_millis(100), u64::MAX, fake_benchmark);
+        assert_eq!(
+            kdf,
+            Kdf::Scrypt {
+                log2_n: 16,
+                r: 8,
+                p: 1
+            }
+        );
+        assert_eq!(duration, Duration::from_millis(64));
+    }
+
+    #[test]
+    fn test_calibrate_scrypt_respects_memory() {
+        let (kdf, _) = calibrate_scrypt(
+            Duration::from_secs(3600),
+            scrypt_memory_bytes(14, 8),
+            fake_benchmark,
+        );
+        assert_eq!(
+            kdf,
+            Kdf::Scrypt {
+                log2_n: 14,
+                r: 8,
+                p: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_calibrate_scrypt_never_goes_below_defaults() {
+        let (kdf, duration) = calibrate_scrypt(Duration::from_millis(1), 0, fake_benchmark);
+        assert_eq!(kdf, Kdf::default_scrypt());
+        assert_eq!(duration, Duration::from_millis(4));
+    }
+}
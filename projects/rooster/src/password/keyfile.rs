@@ -0,0 +1,39 @@
+use rtoolbox::safe_vec::SafeVec;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Error, ErrorKind, Result as IoResult};
+use std::path::Path;
+
+/// Hashes a keyfile, which can be any file as long as it doesn't change: random bytes, a photo...
+pub fn digest(path: &Path) -> IoResult<SafeVec> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    // An empty keyfile is most likely a mistake, and it would add nothing to the master password.
+    if std::io::copy(&mut file, &mut hasher)? == 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "the keyfile is empty"));
+    }
+    Ok(SafeVec::new(hasher.finalize().to_vec()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::digest;
+
+    #[test]
+    fn test_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyfile = dir.path().join("keyfile");
+
+        std::fs::write(&keyfile, b"some random bytes").unwrap();
+        let first = digest(&keyfile).unwrap();
+        assert_eq!(first.len(), 32);
+        assert_eq!(first, digest(&keyfile).unwrap());
+
+        std::fs::write(&keyfile, b"other random bytes").unwrap();
+        assert_ne!(first, digest(&keyfile).unwrap());
+
+        std::fs::write(&keyfile, b"").unwrap();
+        assert!(digest(&keyfile).is_err());
+        assert!(digest(&dir.path().join("missing")).is_err());
+    }
+}
@@ -1,5 +1,8 @@
+pub mod kdf;
+pub mod keyfile;
 pub mod v1;
 pub mod v2;
+pub mod v3;
 
 use rtoolbox::safe_string::SafeString;
 use rtoolbox::safe_vec::SafeVec;
@@ -19,8 +22,21 @@ pub enum PasswordError {
     CorruptionError,
     CorruptionLikelyError,
     NeedUpgradeErrorFromV1,
+    NeedUpgradeErrorFromV2,
     NoUpgradeError,
     EmptyPasswordError,
+    NoSuchPreviousPasswordError,
+    /// The password file needs a keyfile, but none was given
+    KeyfileRequiredError,
+    NoSuchKeySlotError,
+    KeySlotExistsError,
+    /// Key slot names can't be empty or longer than `v3::MAX_KEY_SLOT_NAME_LEN` bytes
+    InvalidKeySlotNameError,
+    /// Removing the key slot would leave no way to open the password file
+    LastKeySlotError,
+    /// The password file was opened with a key from the agent, or the key slot of its master
+    /// password was removed, so the master password is unknown
+    MasterPasswordUnknownError,
 }
 
 impl From<IoError> for PasswordError {
@@ -29,19 +45,51 @@ impl From<IoError> for PasswordError {
     }
 }
 
-fn upgrade_v1_v2(
+fn upgrade_v1_v3(
     v1_passwords: &[v1::Password],
-    v2_store: &mut v2::PasswordStore,
+    v3_store: &mut v3::PasswordStore,
 ) -> Result<(), PasswordError> {
     for p in v1_passwords.iter() {
-        let v2_password = v2::Password {
+        let v3_password = v3::Password {
             name: p.name.clone(),
             username: p.username.clone(),
             password: p.password.clone(),
             created_at: p.created_at,
             updated_at: p.updated_at,
+            url: p.domain.clone(),
+            notes: None,
+            custom_fields: Vec::new(),
+            otp: None,
+            history: Vec::new(),
+            tags: Vec::new(),
+            generation_policy: None,
         };
-        v2_store.add_password(v2_password)?;
+        v3_store.add_password(v3_password)?;
+    }
+
+    Ok(())
+}
+
+fn upgrade_v2_v3(
+    v2_passwords: &[v2::Password],
+    v3_store: &mut v3::PasswordStore,
+) -> Result<(), PasswordError> {
+    for p in v2_passwords.iter() {
+        let v3_password = v3::Password {
+            name: p.name.clone(),
+            username: p.username.clone(),
+            password: p.password.clone(),
+            created_at: p.created_at,
+            updated_at: p.updated_at,
+            url: None,
+            notes: None,
+            custom_fields: Vec::new(),
+            otp: None,
+            history: Vec::new(),
+            tags: Vec::new(),
+            generation_policy: None,
+        };
+        v3_store.add_password(v3_password)?;
     }
 
     Ok(())
@@ -50,14 +98,42 @@ fn upgrade_v1_v2(
 pub fn upgrade(
     master_password: SafeString,
     input: SafeVec,
-) -> Result<v2::PasswordStore, PasswordError> {
+) -> Result<v3::PasswordStore, PasswordError> {
     // If we can't read v1 passwords, we have a hard error, because we previously tried
-    // to read the passwords as v2. Which failed. That means we can't upgrade.
+    // to read the passwords as v3. Which failed. That means we can't upgrade.
     let v1_passwords = v1::get_all_passwords(master_password.deref(), input.deref())?;
 
-    // Upgrade from v1 to v2 if we could read v1 passwords.
-    let mut v2_store = v2::PasswordStore::new(master_password.clone())?;
-    upgrade_v1_v2(v1_passwords.deref(), &mut v2_store)?;
+    // Upgrade from v1 to v3 if we could read v1 passwords.
+    let mut v3_store = v3::PasswordStore::new(master_password.clone())?;
+    upgrade_v1_v3(v1_passwords.deref(), &mut v3_store)?;
+
+    Ok(v3_store)
+}
+
+pub fn upgrade_from_v2(
+    master_password: SafeString,
+    input: SafeVec,
+) -> Result<v3::PasswordStore, PasswordError> {
+    // Keep the key derivation parameters the user chose.
+    let (scrypt_log2_n, scrypt_r, scrypt_p) = v2::get_scrypt_params(input.deref())?;
+    let kdf = kdf::Kdf::Scrypt {
+        log2_n: scrypt_log2_n,
+        r: scrypt_r,
+        p: scrypt_p,
+    };
+    kdf.validate().map_err(|_| PasswordError::CorruptionError)?;
+
+    // The signature is checked with the key derived from the master password, so a wrong master
+    // password fails it just like a corrupted file does.
+    let v2_passwords = v2::get_all_passwords(master_password.deref(), input.deref()).map_err(
+        |err| match err {
+            PasswordError::CorruptionError => PasswordError::DecryptionError,
+            err => err,
+        },
+    )?;
+
+    let mut v3_store = v3::PasswordStore::with_kdf(master_password.clone(), kdf)?;
+    upgrade_v2_v3(v2_passwords.deref(), &mut v3_store)?;
 
-    Ok(v2_store)
+    Ok(v3_store)
 }
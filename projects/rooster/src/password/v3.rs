@@ -0,0 +1,1853 @@
+use crate::aes;
+use crate::atomic_file;
+use crate::ffi;
+use crate::generate::GenerationPolicy;
+use crate::password::kdf::{Kdf, KEY_LEN};
+use crate::password::PasswordError;
+use crate::totp::Totp;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use rand::RngCore;
+use rtoolbox::safe_string::SafeString;
+use rtoolbox::safe_vec::SafeVec;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use serde_json::Error;
+use sha2::{Digest, Sha256};
+use std::convert::TryFrom;
+use std::io::{Cursor, Error as IoError, ErrorKind, Read, Result as IoResult, Write};
+use std::ops::Deref;
+use std::path::Path;
+
+/// The nonce is 96 bits long, as recommended for AES-GCM.
+///
+/// A new random nonce is generated every time the file is saved. With 96 random bits, the
+/// odds of reusing a nonce with the same key are negligible.
+const NONCE_LEN: usize = 12;
+
+/// Length of the salt passed to the key derivation function, in bytes
+const SALT_LEN: usize = 32;
+
+/// Length of the identifier of a derived key, see `PasswordStore::key_id`
+pub const KEY_ID_LEN: usize = 32;
+
+/// The key slot of the master password that was there before the first key slot was added
+pub const DEFAULT_KEY_SLOT: &str = "default";
+
+/// Longest key slot name, in bytes
+pub const MAX_KEY_SLOT_NAME_LEN: usize = 255;
+
+/// The version of this lib
+const VERSION: u32 = 3;
+
+/// Header field types
+///
+/// There is one parameters field per key derivation function, its type identifies the function.
+/// Key slots are made of header fields too.
+const FIELD_END: u8 = 0;
+const FIELD_SCRYPT_PARAMS: u8 = 1;
+const FIELD_SALT: u8 = 2;
+const FIELD_NONCE: u8 = 3;
+const FIELD_ARGON2ID_PARAMS: u8 = 4;
+const FIELD_KEYFILE: u8 = 5;
+const FIELD_KEY_SLOT: u8 = 6;
+const FIELD_KEY_SLOT_NAME: u8 = 7;
+const FIELD_WRAPPED_KEY: u8 = 8;
+
+// Create a random nonce.
+fn generate_random_nonce() -> IoResult<[u8; NONCE_LEN]> {
+    let mut bytes: [u8; NONCE_LEN] = [0; NONCE_LEN];
+    let mut rng = rand::rng();
+    rng.fill_bytes(&mut bytes);
+    Ok(bytes)
+}
+
+// Create a random salt.
+fn generate_random_salt() -> IoResult<[u8; SALT_LEN]> {
+    let mut bytes: [u8; SALT_LEN] = [0; SALT_LEN];
+    let mut rng = rand::rng();
+    rng.fill_bytes(&mut bytes);
+    Ok(bytes)
+}
+
+// Create a random data key, for password files with key slots.
+fn generate_random_data_key() -> IoResult<SafeVec> {
+    let mut bytes = SafeVec::new(vec![0; KEY_LEN]);
+    let mut rng = rand::rng();
+    rng.fill_bytes(bytes.inner_mut());
+    Ok(bytes)
+}
+
+/// Derives a 256 bits encryption key from the password, and from the keyfile if there is one.
+fn generate_encryption_key(
+    master_password: &str,
+    keyfile_digest: Option<&SafeVec>,
+    salt: [u8; SALT_LEN],
+    kdf: &Kdf,
+) -> SafeVec {
+    // The digest always has the same length, so it can't be mistaken for part of the password.
+    let mut secret = SafeVec::new(Vec::new());
+    if let Some(keyfile_digest) = keyfile_digest {
+        secret.inner_mut().extend_from_slice(keyfile_digest.as_ref());
+    }
+    secret
+        .inner_mut()
+        .extend_from_slice(master_password.as_bytes());
+    kdf.derive_key(secret.as_ref(), salt.as_slice())
+}
+
+/// How a key is derived from a master password.
+#[derive(Clone, Copy)]
+struct KeyDerivation {
+    kdf: Kdf,
+    salt: [u8; SALT_LEN],
+    /// Whether a keyfile is needed along with the master password
+    keyfile: bool,
+}
+
+impl KeyDerivation {
+    fn derive_key(
+        &self,
+        master_password: &str,
+        keyfile_digest: Option<&SafeVec>,
+    ) -> Result<SafeVec, PasswordError> {
+        let keyfile_digest = match (self.keyfile, keyfile_digest) {
+            (false, _) => None,
+            (true, Some(keyfile_digest)) => Some(keyfile_digest),
+            (true, None) => return Err(PasswordError::KeyfileRequiredError),
+        };
+        Ok(generate_encryption_key(
+            master_password,
+            keyfile_digest,
+            self.salt,
+            &self.kdf,
+        ))
+    }
+}
+
+/// A copy of the data key that encrypts the passwords, encrypted with a key derived from one of
+/// the master passwords.
+#[derive(Clone)]
+pub struct KeySlot {
+    name: String,
+    derivation: KeyDerivation,
+    nonce: [u8; NONCE_LEN],
+    /// The data key, AES-256-GCM encrypted with the slot fields as associated data
+    wrapped_key: Vec<u8>,
+}
+
+impl KeySlot {
+    fn new(name: String, derivation: KeyDerivation, key: &SafeVec, data_key: &SafeVec) -> KeySlot {
+        let mut slot = KeySlot {
+            name,
+            derivation,
+            // This never fails, the random number generator panics instead.
+            nonce: generate_random_nonce().unwrap(),
+            wrapped_key: Vec::new(),
+        };
+        // Writing to a `Vec` can't fail, and neither can AES-GCM with a 256 bits key.
+        slot.wrapped_key = aes::encrypt_authenticated(
+            data_key.as_ref(),
+            slot.associated_data().unwrap().as_slice(),
+            key.as_ref(),
+            slot.nonce.as_ref(),
+        )
+        .unwrap();
+        slot
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kdf(&self) -> &Kdf {
+        &self.derivation.kdf
+    }
+
+    pub fn has_keyfile(&self) -> bool {
+        self.derivation.keyfile
+    }
+
+    /// The slot fields but the wrapped key, which are authenticated along with it.
+    fn associated_data(&self) -> IoResult<Vec<u8>> {
+        let mut output: Vec<u8> = Vec::new();
+        write_header_field(&mut output, FIELD_KEY_SLOT_NAME, self.name.as_bytes())?;
+        write_kdf_field(&mut output, &self.derivation.kdf)?;
+        write_header_field(&mut output, FIELD_SALT, &self.derivation.salt)?;
+        write_header_field(&mut output, FIELD_NONCE, &self.nonce)?;
+        if self.derivation.keyfile {
+            write_header_field(&mut output, FIELD_KEYFILE, &[])?;
+        }
+        Ok(output)
+    }
+
+    fn to_bytes(&self) -> IoResult<Vec<u8>> {
+        let mut output = self.associated_data()?;
+        write_header_field(&mut output, FIELD_WRAPPED_KEY, self.wrapped_key.as_slice())?;
+        write_header_field(&mut output, FIELD_END, &[])?;
+        Ok(output)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<KeySlot, PasswordError> {
+        let mut reader = Cursor::new(bytes);
+        let fields = read_header_fields(&mut reader, true)?;
+        if reader.position() as usize != bytes.len() {
+            return Err(PasswordError::CorruptionError);
+        }
+        let derivation = fields.derivation()?;
+        match (fields.name, derivation, fields.nonce, fields.wrapped_key) {
+            (Some(name), Some(derivation), Some(nonce), Some(wrapped_key)) => Ok(KeySlot {
+                name,
+                derivation,
+                nonce,
+                wrapped_key,
+            }),
+            _ => Err(PasswordError::CorruptionError),
+        }
+    }
+
+    /// Decrypts the data key with the master password of this slot.
+    fn unwrap_key(
+        &self,
+        master_password: &str,
+        keyfile_digest: Option<&SafeVec>,
+    ) -> Result<SafeVec, PasswordError> {
+        let key = self.derivation.derive_key(master_password, keyfile_digest)?;
+        aes::decrypt_authenticated(
+            self.wrapped_key.as_slice(),
+            self.associated_data()?.as_slice(),
+            key.as_ref(),
+            self.nonce.as_ref(),
+        )
+        .map_err(|_| PasswordError::DecryptionError)
+    }
+}
+
+/// Finds the key slot of the master password, and decrypts the data key in it.
+fn open_key_slot(
+    slots: &[KeySlot],
+    master_password: &str,
+    keyfile_digest: Option<&SafeVec>,
+) -> Result<(usize, SafeVec), PasswordError> {
+    // Only ask for the keyfile if no master password could be enough.
+    if keyfile_digest.is_none() && slots.iter().all(|slot| slot.derivation.keyfile) {
+        return Err(PasswordError::KeyfileRequiredError);
+    }
+    for (i, slot) in slots.iter().enumerate() {
+        match slot.unwrap_key(master_password, keyfile_digest) {
+            Ok(data_key) => return Ok((i, data_key)),
+            Err(PasswordError::DecryptionError) | Err(PasswordError::KeyfileRequiredError) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Err(PasswordError::DecryptionError)
+}
+
+/// The unencrypted part of the Rooster file.
+///
+/// It is authenticated along with the encrypted blob, so that it can't be tampered with.
+struct Header {
+    /// How the key is derived from the master password, unless the password file has key slots
+    derivation: Option<KeyDerivation>,
+    slots: Vec<KeySlot>,
+    nonce: [u8; NONCE_LEN],
+}
+
+fn write_header_field(output: &mut Vec<u8>, field_type: u8, value: &[u8]) -> IoResult<()> {
+    output.write_u8(field_type)?;
+    let len = u16::try_from(value.len())
+        .map_err(|_| IoError::new(ErrorKind::InvalidInput, "header field too long"))?;
+    output.write_u16::<BigEndian>(len)?;
+    output.write_all(value)
+}
+
+fn write_kdf_field(output: &mut Vec<u8>, kdf: &Kdf) -> IoResult<()> {
+    let mut kdf_params: Vec<u8> = Vec::new();
+    match *kdf {
+        Kdf::Scrypt { log2_n, r, p } => {
+            kdf_params.write_u8(log2_n)?;
+            kdf_params.write_u32::<BigEndian>(r)?;
+            kdf_params.write_u32::<BigEndian>(p)?;
+            write_header_field(output, FIELD_SCRYPT_PARAMS, kdf_params.as_slice())
+        }
+        Kdf::Argon2id {
+            memory_kib,
+            iterations,
+            parallelism,
+        } => {
+            kdf_params.write_u32::<BigEndian>(memory_kib)?;
+            kdf_params.write_u32::<BigEndian>(iterations)?;
+            kdf_params.write_u32::<BigEndian>(parallelism)?;
+            write_header_field(output, FIELD_ARGON2ID_PARAMS, kdf_params.as_slice())
+        }
+    }
+}
+
+/// The fields read from the header or from a key slot, before checking that they make sense.
+#[derive(Default)]
+struct HeaderFields {
+    kdf: Option<Kdf>,
+    salt: Option<[u8; SALT_LEN]>,
+    nonce: Option<[u8; NONCE_LEN]>,
+    keyfile: bool,
+    slots: Vec<KeySlot>,
+    name: Option<String>,
+    wrapped_key: Option<Vec<u8>>,
+}
+
+impl HeaderFields {
+    fn derivation(&self) -> Result<Option<KeyDerivation>, PasswordError> {
+        match (self.kdf, self.salt) {
+            (Some(kdf), Some(salt)) => {
+                // Don't let a tampered file make us panic or allocate unreasonable amounts
+                // of memory, the header is only authenticated after deriving the key.
+                kdf.validate().map_err(|_| PasswordError::CorruptionError)?;
+                Ok(Some(KeyDerivation {
+                    kdf,
+                    salt,
+                    keyfile: self.keyfile,
+                }))
+            }
+            (None, None) if !self.keyfile => Ok(None),
+            _ => Err(PasswordError::CorruptionError),
+        }
+    }
+}
+
+/// Reads header fields up to the end field, `in_slot` being whether they are the fields of a key
+/// slot.
+fn read_header_fields(
+    reader: &mut Cursor<&[u8]>,
+    in_slot: bool,
+) -> Result<HeaderFields, PasswordError> {
+    let mut fields = HeaderFields::default();
+
+    loop {
+        let field_type = reader.read_u8()?;
+        let mut value = vec![0u8; reader.read_u16::<BigEndian>()? as usize];
+        reader.read_exact(value.as_mut_slice())?;
+
+        let mut value_reader = Cursor::new(value.as_slice());
+        match field_type {
+            FIELD_END => break,
+            FIELD_SCRYPT_PARAMS | FIELD_ARGON2ID_PARAMS if fields.kdf.is_some() => {
+                return Err(PasswordError::CorruptionError);
+            }
+            FIELD_SCRYPT_PARAMS => {
+                fields.kdf = Some(Kdf::Scrypt {
+                    log2_n: value_reader.read_u8()?,
+                    r: value_reader.read_u32::<BigEndian>()?,
+                    p: value_reader.read_u32::<BigEndian>()?,
+                });
+            }
+            FIELD_ARGON2ID_PARAMS => {
+                fields.kdf = Some(Kdf::Argon2id {
+                    memory_kib: value_reader.read_u32::<BigEndian>()?,
+                    iterations: value_reader.read_u32::<BigEndian>()?,
+                    parallelism: value_reader.read_u32::<BigEndian>()?,
+                });
+            }
+            FIELD_SALT => {
+                fields.salt = Some(
+                    <[u8; SALT_LEN]>::try_from(value.as_slice())
+                        .map_err(|_| PasswordError::CorruptionError)?,
+                );
+            }
+            FIELD_NONCE => {
+                fields.nonce = Some(
+                    <[u8; NONCE_LEN]>::try_from(value.as_slice())
+                        .map_err(|_| PasswordError::CorruptionError)?,
+                );
+            }
+            FIELD_KEYFILE => fields.keyfile = true,
+            // Key slots don't nest, so a tampered file can't make us recurse forever.
+            FIELD_KEY_SLOT if in_slot => return Err(PasswordError::CorruptionError),
+            FIELD_KEY_SLOT => fields.slots.push(KeySlot::from_bytes(value.as_slice())?),
+            FIELD_KEY_SLOT_NAME => {
+                fields.name =
+                    Some(String::from_utf8(value).map_err(|_| PasswordError::CorruptionError)?);
+            }
+            FIELD_WRAPPED_KEY => fields.wrapped_key = Some(value),
+            // This field was added by a newer version of Rooster, we don't know what it means.
+            _ => return Err(PasswordError::OutdatedRoosterBinaryError),
+        }
+    }
+
+    Ok(fields)
+}
+
+impl Header {
+    fn to_bytes(&self) -> IoResult<Vec<u8>> {
+        let mut output: Vec<u8> = Vec::new();
+        output.write_u32::<BigEndian>(VERSION)?;
+
+        if let Some(ref derivation) = self.derivation {
+            write_kdf_field(&mut output, &derivation.kdf)?;
+            write_header_field(&mut output, FIELD_SALT, &derivation.salt)?;
+        }
+        for slot in self.slots.iter() {
+            write_header_field(&mut output, FIELD_KEY_SLOT, slot.to_bytes()?.as_slice())?;
+        }
+        write_header_field(&mut output, FIELD_NONCE, &self.nonce)?;
+        if self.derivation.is_some_and(|derivation| derivation.keyfile) {
+            write_header_field(&mut output, FIELD_KEYFILE, &[])?;
+        }
+        write_header_field(&mut output, FIELD_END, &[])?;
+
+        Ok(output)
+    }
+
+    /// Reads the header fields, right after the version.
+    fn from_reader(reader: &mut Cursor<&[u8]>) -> Result<Header, PasswordError> {
+        let fields = read_header_fields(reader, false)?;
+        if fields.name.is_some() || fields.wrapped_key.is_some() {
+            return Err(PasswordError::CorruptionError);
+        }
+
+        // The key comes either from the master password or from a key slot, not both.
+        let derivation = fields.derivation()?;
+        match (derivation, fields.slots.is_empty(), fields.nonce) {
+            (Some(_), true, Some(nonce)) | (None, false, Some(nonce)) => Ok(Header {
+                derivation,
+                slots: fields.slots,
+                nonce,
+            }),
+            _ => Err(PasswordError::CorruptionError),
+        }
+    }
+
+    /// Whether no master password can open the password file without a keyfile.
+    fn keyfile_required(&self) -> bool {
+        match self.derivation {
+            Some(derivation) => derivation.keyfile,
+            None => self.slots.iter().all(|slot| slot.derivation.keyfile),
+        }
+    }
+
+    /// Identifies how the key is derived, see `PasswordStore::key_id`.
+    fn key_id(mut self) -> [u8; KEY_ID_LEN] {
+        // The header without its nonce, which changes on every save
+        self.nonce = [0; NONCE_LEN];
+        // Writing to a `Vec` can't fail.
+        let bytes = self.to_bytes().unwrap();
+        Sha256::digest(bytes.as_slice()).into()
+    }
+}
+
+/// Reads the version and the header fields, which come before the encrypted blob.
+fn read_version_and_header(reader: &mut Cursor<&[u8]>) -> Result<Header, PasswordError> {
+    // Version taken from network byte order (big endian).
+    let version = reader.read_u32::<BigEndian>()?;
+    if version > VERSION {
+        return Err(PasswordError::OutdatedRoosterBinaryError);
+    } else if version == 2 {
+        return Err(PasswordError::NeedUpgradeErrorFromV2);
+    } else if version < 2 {
+        return Err(PasswordError::NeedUpgradeErrorFromV1);
+    }
+
+    Header::from_reader(reader)
+}
+
+/// The format of the encrypted JSON content in the password file v3.
+///
+/// ```json
+/// {
+///     "passwords": [
+///         "name": "YouTube",
+///         "username": "conradk",
+///         "password": "xxxxxxxx",
+///         "created_at": 23145436,
+///         "updated_at": 23145546,
+///     ]
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Schema {
+    passwords: Vec<Password>,
+}
+
+impl Schema {
+    fn new() -> Schema {
+        Schema {
+            passwords: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Password {
+    pub name: String,
+    pub username: String,
+    pub password: SafeString,
+    pub created_at: ffi::time_t,
+    pub updated_at: ffi::time_t,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<SafeString>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_fields: Vec<CustomField>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otp: Option<Totp>,
+    /// Previous passwords, most recent first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<PreviousPassword>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// How the password was generated, if it was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation_policy: Option<GenerationPolicy>,
+}
+
+/// How many previous passwords we keep for each app
+pub const HISTORY_LEN: usize = 10;
+
+/// A password that was replaced by a newer one.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PreviousPassword {
+    pub password: SafeString,
+    pub replaced_at: ffi::time_t,
+}
+
+/// Extra information about an account, like a recovery code or the answer to a security question.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CustomField {
+    pub name: String,
+    pub value: SafeString,
+    /// Concealed fields are treated like passwords and only shown with `--show`.
+    #[serde(default)]
+    pub concealed: bool,
+}
+
+impl Password {
+    /// Whether this is the password for the given app and username, without regard to casing.
+    pub fn is_account(&self, name: &str, username: &str) -> bool {
+        self.name.to_lowercase() == name.to_lowercase()
+            && self.username.to_lowercase() == username.to_lowercase()
+    }
+
+    /// Whether the password has the tag, without regard to casing.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase())
+    }
+
+    pub fn new<IS1: Into<String>, IS2: Into<String>, ISS: Into<SafeString>>(
+        name: IS1,
+        username: IS2,
+        password: ISS,
+    ) -> Password {
+        let timestamp = ffi::time();
+        Password {
+            name: name.into(),
+            username: username.into(),
+            password: password.into(),
+            created_at: timestamp,
+            updated_at: timestamp,
+            url: None,
+            notes: None,
+            custom_fields: Vec::new(),
+            otp: None,
+            history: Vec::new(),
+            tags: Vec::new(),
+            generation_policy: None,
+        }
+    }
+}
+
+pub struct PasswordStore {
+    /// Encrypts the passwords: derived from the master password, or the data key of the key slots
+    key: SafeVec,
+    /// How the key is derived from the master password, unless the password file has key slots
+    derivation: Option<KeyDerivation>,
+    slots: Vec<KeySlot>,
+    /// The key slot of the master password that opened the password file, if known
+    slot: Option<usize>,
+    schema: Schema,
+    /// Unknown when the password file was opened with a key from the agent
+    master_password: Option<SafeString>,
+    /// Unknown when the password file was opened with a key from the agent
+    keyfile_digest: Option<SafeVec>,
+}
+
+/// Read and writes to a Rooster file
+///
+/// The Rooster file has the following format:
+/// - rooster version: u32, big endian
+/// - header fields, each made of:
+///   - field type:    u8
+///   - field length:  u16, big endian
+///   - field value:   variable length
+/// - encrypted blob:  variable length, AES-256-GCM with a 128 bits tag at the end
+///
+/// The header fields are, in any order:
+/// - scrypt params:   log2n as u8, r and p as u32, big endian
+/// - argon2id params: memory in KiB, iterations and parallelism as u32, big endian
+/// - salt:            256 bits
+/// - nonce:           96 bits
+/// - keyfile:         empty, only there when a keyfile is needed along with the master password
+/// - key slot:        header fields, see below, once per key slot
+/// - end:             empty, always last
+///
+/// Without key slots, the key of the blob is derived from the master password with the key
+/// derivation function and the salt. With key slots, it is a random data key and there is no key
+/// derivation function or salt outside of the key slots. Each key slot has its own:
+/// - name:            UTF-8
+/// - scrypt or argon2id params, salt, nonce and keyfile, as above
+/// - wrapped key:     the data key, AES-256-GCM encrypted with the key derived from the master
+///   password of the slot, and the other fields of the slot as associated data
+/// - end
+///
+/// So removing a key slot or adding one leaves the data key and the other key slots alone.
+///
+/// The version and the header fields are authenticated as associated data, so they can't be
+/// changed without the decryption failing.
+impl PasswordStore {
+    pub fn new(master_password: SafeString) -> IoResult<PasswordStore> {
+        PasswordStore::with_kdf(master_password, Kdf::default_scrypt())
+    }
+
+    pub fn with_kdf(master_password: SafeString, kdf: Kdf) -> IoResult<PasswordStore> {
+        PasswordStore::create(master_password, kdf, None)
+    }
+
+    /// Creates a password file that needs the keyfile, if any, along with the master password.
+    pub fn with_keyfile(
+        master_password: SafeString,
+        keyfile_digest: Option<SafeVec>,
+    ) -> IoResult<PasswordStore> {
+        PasswordStore::create(master_password, Kdf::default_scrypt(), keyfile_digest)
+    }
+
+    fn create(
+        master_password: SafeString,
+        kdf: Kdf,
+        keyfile_digest: Option<SafeVec>,
+    ) -> IoResult<PasswordStore> {
+        let salt = generate_random_salt()?;
+        let key = generate_encryption_key(
+            master_password.deref(),
+            keyfile_digest.as_ref(),
+            salt,
+            &kdf,
+        );
+
+        Ok(PasswordStore {
+            key,
+            derivation: Some(KeyDerivation {
+                kdf,
+                salt,
+                keyfile: keyfile_digest.is_some(),
+            }),
+            slots: Vec::new(),
+            slot: None,
+            schema: Schema::new(),
+            master_password: Some(master_password),
+            keyfile_digest,
+        })
+    }
+
+    /// Opens a password file. The keyfile is ignored when the master password doesn't need one.
+    pub fn from_input(
+        master_password: SafeString,
+        keyfile_digest: Option<SafeVec>,
+        input: SafeVec,
+    ) -> Result<PasswordStore, PasswordError> {
+        let mut slot = None;
+        let mut store = PasswordStore::decrypt(&input, |header| match header.derivation {
+            // Derive a 256 bits encryption key from the password.
+            Some(derivation) => {
+                derivation.derive_key(master_password.deref(), keyfile_digest.as_ref())
+            }
+            None => {
+                let (i, data_key) = open_key_slot(
+                    header.slots.as_slice(),
+                    master_password.deref(),
+                    keyfile_digest.as_ref(),
+                )?;
+                slot = Some(i);
+                Ok(data_key)
+            }
+        })?;
+        store.slot = slot;
+        store.master_password = Some(master_password);
+        if store.master_derivation()?.keyfile {
+            store.keyfile_digest = keyfile_digest;
+        }
+        Ok(store)
+    }
+
+    /// Opens the password file with a key that was derived earlier, for instance by the agent.
+    ///
+    /// The master password is unknown, so the key derivation function can't be changed.
+    pub fn from_input_with_key(
+        key: SafeVec,
+        input: SafeVec,
+    ) -> Result<PasswordStore, PasswordError> {
+        PasswordStore::decrypt(&input, |_| Ok(key))
+    }
+
+    /// Whether the password file needs a keyfile along with the master password.
+    pub fn keyfile_required_from_input(input: &[u8]) -> Result<bool, PasswordError> {
+        let mut reader = Cursor::new(input);
+        Ok(read_version_and_header(&mut reader)?.keyfile_required())
+    }
+
+    /// Identifies the password file and how its key is derived, so that the agent can find the
+    /// key. It reveals nothing about the key, and stays the same when the file is saved again.
+    pub fn key_id_from_input(input: &[u8]) -> Result<[u8; KEY_ID_LEN], PasswordError> {
+        let mut reader = Cursor::new(input);
+        Ok(read_version_and_header(&mut reader)?.key_id())
+    }
+
+    pub fn key_id(&self) -> [u8; KEY_ID_LEN] {
+        self.header([0; NONCE_LEN]).key_id()
+    }
+
+    pub fn key(&self) -> &SafeVec {
+        &self.key
+    }
+
+    fn header(&self, nonce: [u8; NONCE_LEN]) -> Header {
+        Header {
+            derivation: self.derivation,
+            slots: self.slots.clone(),
+            nonce,
+        }
+    }
+
+    fn decrypt(
+        input: &SafeVec,
+        derive_key: impl FnOnce(&Header) -> Result<SafeVec, PasswordError>,
+    ) -> Result<PasswordStore, PasswordError> {
+        let mut reader = Cursor::new(&input[..]);
+        let header = read_version_and_header(&mut reader)?;
+
+        // Everything up to the encrypted blob is authenticated.
+        let header_len = reader.position() as usize;
+        let associated_data = &input[..header_len];
+        let blob = &input[header_len..];
+
+        let key = derive_key(&header)?;
+
+        // Decrypt the data. This fails both if the password is wrong and if the file has been
+        // tampered with, there is no way to tell the difference.
+        let passwords = match aes::decrypt_authenticated(
+            blob,
+            associated_data,
+            key.as_ref(),
+            header.nonce.as_ref(),
+        ) {
+            Ok(decrypted) => {
+                let encoded = SafeString::from_string(
+                    String::from_utf8_lossy(decrypted.as_ref()).into_owned(),
+                );
+                let s: Result<Schema, Error> = serde_json::from_str(encoded.deref());
+                match s {
+                    Ok(json) => json.passwords,
+                    Err(_) => {
+                        return Err(PasswordError::InvalidJsonError);
+                    }
+                }
+            }
+            Err(_) => {
+                return Err(PasswordError::DecryptionError);
+            }
+        };
+
+        Ok(PasswordStore {
+            key,
+            derivation: header.derivation,
+            slots: header.slots,
+            slot: None,
+            schema: Schema { passwords },
+            master_password: None,
+            keyfile_digest: None,
+        })
+    }
+
+    /// Encodes the password store into the Rooster file format.
+    fn serialize(&self) -> Result<Vec<u8>, PasswordError> {
+        // This should never fail. The structs are all encodable.
+        let json_schema = match serde_json::to_string(&self.schema) {
+            Ok(json_schema) => json_schema,
+            Err(_) => {
+                return Err(PasswordError::InvalidJsonError);
+            }
+        };
+        let json_schema = SafeString::from_string(json_schema);
+
+        // Encrypt the data with a new nonce.
+        let header = self.header(generate_random_nonce()?);
+        let mut output = header.to_bytes()?;
+        let encrypted = match aes::encrypt_authenticated(
+            json_schema.deref().as_bytes(),
+            output.as_slice(),
+            self.key.as_ref(),
+            header.nonce.as_ref(),
+        ) {
+            Ok(val) => val,
+            Err(_) => return Err(PasswordError::EncryptionError),
+        };
+
+        // Write the encrypted password data.
+        output.write_all(encrypted.as_slice())?;
+
+        Ok(output)
+    }
+
+    /// Saves the password store to disk.
+    ///
+    /// The file is replaced atomically, so that a crash or a full disk never leaves a half
+    /// written password file behind.
+    pub fn sync(&self, path: &Path) -> Result<(), PasswordError> {
+        let output = self.serialize()?;
+        atomic_file::write(path, output.as_slice())?;
+        Ok(())
+    }
+
+    pub fn get_all_passwords(&self) -> Vec<&Password> {
+        let mut passwords: Vec<&Password> = self.schema.passwords.iter().collect();
+
+        passwords.sort_by_key(|p| (p.name.to_lowercase(), p.username.to_lowercase()));
+
+        passwords
+    }
+
+    /// Adds a password to the file.
+    pub fn add_password(&mut self, password: Password) -> Result<(), PasswordError> {
+        if password.password.deref().is_empty() {
+            return Err(PasswordError::EmptyPasswordError);
+        }
+        if self.has_password(&password.name, &password.username) {
+            return Err(PasswordError::AppExistsError);
+        }
+        self.schema.passwords.push(password);
+        Ok(())
+    }
+
+    pub fn delete_password(
+        &mut self,
+        name: &str,
+        username: &str,
+    ) -> Result<Password, PasswordError> {
+        let i = self
+            .schema
+            .passwords
+            .iter()
+            .position(|p| p.is_account(name, username))
+            .ok_or(PasswordError::NoSuchAppError)?;
+        Ok(self.schema.passwords.remove(i))
+    }
+
+    pub fn search_passwords(&self, name: &str) -> Vec<&Password> {
+        // Fuzzy search password app names.
+        let keys = self
+            .schema
+            .passwords
+            .iter()
+            .map(|p| p.name.to_lowercase())
+            .collect::<Vec<String>>();
+
+        let mut search_results = vec![];
+        // Check if each app name can be matched against the search query.
+        //
+        // It's fine if there are some characters left out in the query. For instance, you can
+        // search for the app "Facebook" with just "fcbk".
+        for app_name in keys.iter().map(|s| s.as_str()) {
+            let mut matches_query = true;
+            let mut last_i = 0;
+            for c in name.chars() {
+                let c_lowercase = format!("{}", c).to_lowercase();
+                match app_name[last_i..].find(c_lowercase.as_str()) {
+                    // Query chars must be present in the app name in the right order.
+                    Some(ic) => {
+                        last_i += ic + 1;
+                    }
+                    // Query char is not present, no match.
+                    None => {
+                        matches_query = false;
+                        break;
+                    }
+                }
+            }
+
+            if matches_query {
+                search_results.push(app_name.to_owned());
+            }
+        }
+
+        let mut passwords = vec![];
+        for p in self.schema.passwords.iter() {
+            if search_results.contains(&p.name.to_lowercase()) {
+                passwords.push(p);
+            }
+        }
+
+        passwords.sort_by_key(|p| (p.name.to_lowercase(), p.username.to_lowercase()));
+
+        passwords
+    }
+
+    /// Finds the password for an app and username, without regard to casing.
+    pub fn get_password(&self, name: &str, username: &str) -> Option<Password> {
+        self.schema
+            .passwords
+            .iter()
+            .find(|p| p.is_account(name, username))
+            .cloned()
+    }
+
+    pub fn has_password(&self, name: &str, username: &str) -> bool {
+        self.get_password(name, username).is_some()
+    }
+
+    pub fn change_password(
+        &mut self,
+        app_name: &str,
+        username: &str,
+        closure: &dyn Fn(Password) -> Password,
+    ) -> Result<Password, PasswordError> {
+        let old_password = self.delete_password(app_name, username)?;
+        let mut new_password = closure(old_password.clone());
+
+        // Keep the replaced password around, in case the new one doesn't work out
+        if new_password.password != old_password.password {
+            new_password.history.insert(
+                0,
+                PreviousPassword {
+                    password: old_password.password.clone(),
+                    replaced_at: ffi::time(),
+                },
+            );
+            new_password.history.truncate(HISTORY_LEN);
+        }
+
+        match self.add_password(new_password.clone()) {
+            Ok(_) => Ok(new_password),
+            Err(err) => {
+                // Password was not added, we'll add the old one back
+                self.add_password(old_password)?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Makes a previous password the current one again, `index` being its position in `history`.
+    ///
+    /// The current password goes to the history, like with any other change.
+    pub fn restore_previous_password(
+        &mut self,
+        app_name: &str,
+        username: &str,
+        index: usize,
+    ) -> Result<Password, PasswordError> {
+        let previous = self
+            .get_password(app_name, username)
+            .ok_or(PasswordError::NoSuchAppError)?
+            .history
+            .get(index)
+            .ok_or(PasswordError::NoSuchPreviousPasswordError)?
+            .password
+            .clone();
+
+        self.change_password(app_name, username, &|mut old_password: Password| {
+            old_password.history.remove(index);
+            Password {
+                password: previous.clone(),
+                updated_at: ffi::time(),
+                ..old_password
+            }
+        })
+    }
+
+    /// How the key of the master password that opened the password file is derived.
+    ///
+    /// It is unknown when the password file was opened with a key from the agent, or when the
+    /// key slot of the master password was removed.
+    fn master_derivation(&self) -> Result<KeyDerivation, PasswordError> {
+        match (self.slot, self.derivation) {
+            (Some(i), _) => Ok(self.slots[i].derivation),
+            (None, Some(derivation)) => Ok(derivation),
+            _ => Err(PasswordError::MasterPasswordUnknownError),
+        }
+    }
+
+    /// The keyfile, if the master password needs one, which must have been given when opening.
+    fn keyfile_digest(&self) -> Result<Option<&SafeVec>, PasswordError> {
+        if !self.master_derivation()?.keyfile {
+            return Ok(None);
+        }
+        self.keyfile_digest
+            .as_ref()
+            .map(Some)
+            .ok_or(PasswordError::KeyfileRequiredError)
+    }
+
+    /// The master password that opened the password file.
+    fn master_password(&self) -> Result<SafeString, PasswordError> {
+        self.master_password
+            .clone()
+            .ok_or(PasswordError::MasterPasswordUnknownError)
+    }
+
+    pub fn has_keyfile(&self) -> Result<bool, PasswordError> {
+        Ok(self.master_derivation()?.keyfile)
+    }
+
+    /// The key derivation function of the master password that opened the password file.
+    pub fn kdf(&self) -> Result<Kdf, PasswordError> {
+        Ok(self.master_derivation()?.kdf)
+    }
+
+    /// Derives the key of the master password again, after any of its parts changed.
+    ///
+    /// With key slots, only the key slot of the master password changes.
+    fn rekey(
+        &mut self,
+        master_password: &str,
+        keyfile_digest: Option<SafeVec>,
+        derivation: KeyDerivation,
+    ) {
+        let key = generate_encryption_key(
+            master_password,
+            keyfile_digest.as_ref(),
+            derivation.salt,
+            &derivation.kdf,
+        );
+        match self.slot {
+            Some(i) => {
+                let name = self.slots[i].name.clone();
+                self.slots[i] = KeySlot::new(name, derivation, &key, &self.key);
+            }
+            None => {
+                self.key = key;
+                self.derivation = Some(derivation);
+            }
+        }
+        self.master_password = Some(SafeString::from_string(master_password.to_owned()));
+        self.keyfile_digest = keyfile_digest;
+    }
+
+    /// Changes the master password.
+    ///
+    /// The password file must have been opened with its keyfile, if it needs one.
+    pub fn change_master_password(&mut self, master_password: &str) -> Result<(), PasswordError> {
+        let keyfile_digest = self.keyfile_digest()?.cloned();
+        let derivation = self.master_derivation()?;
+        self.rekey(master_password, keyfile_digest, derivation);
+        Ok(())
+    }
+
+    /// Changes the key derivation function or its parameters.
+    ///
+    /// The parameters must have been checked with `Kdf::validate` first, and the password file
+    /// must have been opened with the master password and its keyfile, if it needs one.
+    pub fn change_kdf(&mut self, kdf: Kdf) -> Result<(), PasswordError> {
+        let master_password = self.master_password()?;
+        let keyfile_digest = self.keyfile_digest()?.cloned();
+        let derivation = KeyDerivation {
+            kdf,
+            ..self.master_derivation()?
+        };
+        self.rekey(&master_password, keyfile_digest, derivation);
+        Ok(())
+    }
+
+    /// Makes the password file need this keyfile along with the master password, or no keyfile.
+    ///
+    /// The password file must have been opened with the master password.
+    pub fn change_keyfile(&mut self, keyfile_digest: Option<SafeVec>) -> Result<(), PasswordError> {
+        let master_password = self.master_password()?;
+        let derivation = KeyDerivation {
+            keyfile: keyfile_digest.is_some(),
+            ..self.master_derivation()?
+        };
+        self.rekey(&master_password, keyfile_digest, derivation);
+        Ok(())
+    }
+
+    /// The key slots, in the order they are tried. There are none until one is added.
+    pub fn key_slots(&self) -> &[KeySlot] {
+        self.slots.as_slice()
+    }
+
+    /// The name of the key slot of the master password that opened the password file.
+    pub fn current_key_slot(&self) -> Option<&str> {
+        self.slot.map(|i| self.slots[i].name.as_str())
+    }
+
+    fn key_slot_position(&self, name: &str) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|slot| slot.name.to_lowercase() == name.to_lowercase())
+    }
+
+    /// Checks that a key slot named `name` can be added, so that commands can tell before they
+    /// ask for its master password.
+    pub fn check_new_key_slot_name(&self, name: &str) -> Result<(), PasswordError> {
+        if name.is_empty() || name.len() > MAX_KEY_SLOT_NAME_LEN {
+            return Err(PasswordError::InvalidKeySlotNameError);
+        }
+        // Adding the first key slot also adds the default one
+        if self.key_slot_position(name).is_some()
+            || (self.slots.is_empty() && name.to_lowercase() == DEFAULT_KEY_SLOT)
+        {
+            return Err(PasswordError::KeySlotExistsError);
+        }
+        Ok(())
+    }
+
+    /// Lets another master password open the password file, with the same key derivation
+    /// function and keyfile as the current one.
+    ///
+    /// Adding the first key slot moves the passwords to a random data key, and the current
+    /// master password to a key slot named `DEFAULT_KEY_SLOT`. The password file must have been
+    /// opened with the master password.
+    pub fn add_key_slot(&mut self, name: &str, master_password: &str) -> Result<(), PasswordError> {
+        self.check_new_key_slot_name(name)?;
+        let current = self.master_derivation()?;
+        // A keyfile is a second factor, the new master password must not replace it.
+        let keyfile_digest = self.keyfile_digest()?.cloned();
+
+        if self.slots.is_empty() {
+            let data_key = generate_random_data_key()?;
+            // The key of the master password now only encrypts the data key.
+            self.slots.push(KeySlot::new(
+                DEFAULT_KEY_SLOT.to_owned(),
+                current,
+                &self.key,
+                &data_key,
+            ));
+            self.key = data_key;
+            self.derivation = None;
+            self.slot = Some(0);
+        }
+
+        let derivation = KeyDerivation {
+            salt: generate_random_salt()?,
+            ..current
+        };
+        let key = generate_encryption_key(
+            master_password,
+            keyfile_digest.as_ref(),
+            derivation.salt,
+            &derivation.kdf,
+        );
+        self.slots
+            .push(KeySlot::new(name.to_owned(), derivation, &key, &self.key));
+        Ok(())
+    }
+
+    /// Stops a master password from opening the password file. The data key and the other key
+    /// slots stay the same, so there is nothing else to change.
+    pub fn remove_key_slot(&mut self, name: &str) -> Result<(), PasswordError> {
+        let i = self
+            .key_slot_position(name)
+            .ok_or(PasswordError::NoSuchKeySlotError)?;
+        if self.slots.len() == 1 {
+            return Err(PasswordError::LastKeySlotError);
+        }
+        self.slots.remove(i);
+
+        match self.slot {
+            Some(slot) if slot == i => {
+                self.slot = None;
+                self.master_password = None;
+                self.keyfile_digest = None;
+            }
+            Some(slot) if slot > i => self.slot = Some(slot - 1),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::atomic_file;
+    use crate::password::kdf::Kdf;
+    use crate::password::v3::{
+        generate_encryption_key, generate_random_nonce, generate_random_salt, CustomField,
+        Password, PasswordStore, FIELD_END, FIELD_KEY_SLOT, HISTORY_LEN, MAX_KEY_SLOT_NAME_LEN,
+        VERSION,
+    };
+    use crate::password::PasswordError;
+    use rtoolbox::safe_string::SafeString;
+    use rtoolbox::safe_vec::SafeVec;
+    use std::io::{Error as IoError, ErrorKind as IoErrorKind, Write};
+
+    #[test]
+    fn test_generate_random_nonce_has_right_length() {
+        assert_eq!(generate_random_nonce().unwrap().len(), 12);
+    }
+
+    #[test]
+    fn test_generate_random_salt_has_right_length() {
+        assert_eq!(generate_random_salt().unwrap().len(), 32);
+    }
+
+    #[test]
+    fn test_generate_encryption_key_returns_256_bits_key() {
+        assert_eq!(
+            generate_encryption_key(
+                "hello world",
+                None,
+                generate_random_salt().unwrap(),
+                &Kdf::default_scrypt()
+            )
+            .len(),
+            32
+        );
+    }
+
+    #[test]
+    fn test_serialize_and_read_back() {
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        assert!(store
+            .add_password(Password::new("name", "username", "password"))
+            .is_ok());
+        let output = store.serialize().unwrap();
+
+        let store = PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            None,
+            SafeVec::new(output.clone()),
+        )
+        .unwrap();
+        let password = store.get_password("name", "username").unwrap();
+        assert_eq!(password.username, "username");
+        assert_eq!(password.password, "password".into());
+
+        // Wrong master password
+        match PasswordStore::from_input(
+            SafeString::from_string("xxxx".to_owned()),
+            None,
+            SafeVec::new(output),
+        ) {
+            Err(PasswordError::DecryptionError) => {}
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_password_extra_fields_are_optional() {
+        // Passwords saved before URLs, notes and custom fields existed
+        let password: Password = serde_json::from_str(
+            "{\"name\":\"name\",\"username\":\"username\",\"password\":\"password\",\"created_at\":1605554169,\"updated_at\":1605554169}",
+        )
+        .unwrap();
+        assert_eq!(password.url, None);
+        assert_eq!(password.notes, None);
+        assert!(password.custom_fields.is_empty());
+        assert!(!serde_json::to_string(&password).unwrap().contains("url"));
+
+        let password = Password {
+            url: Some("https://example.com".to_owned()),
+            notes: Some("notes".into()),
+            custom_fields: vec![CustomField {
+                name: "PIN".to_owned(),
+                value: "1234".into(),
+                concealed: true,
+            }],
+            ..password
+        };
+        let json = serde_json::to_string(&password).unwrap();
+        assert_eq!(serde_json::from_str::<Password>(&json).unwrap(), password);
+    }
+
+    #[test]
+    fn test_change_kdf() {
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        assert!(store
+            .add_password(Password::new("name", "username", "password"))
+            .is_ok());
+        let argon2id = Kdf::Argon2id {
+            memory_kib: 1024,
+            iterations: 1,
+            parallelism: 1,
+        };
+        store.change_kdf(argon2id).unwrap();
+        let output = store.serialize().unwrap();
+        // The first header field is the argon2id parameters.
+        assert_eq!(
+            &output[4..19],
+            &[4, 0, 12, 0, 0, 4, 0, 0, 0, 0, 1, 0, 0, 0, 1]
+        );
+
+        let store = PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            None,
+            SafeVec::new(output),
+        )
+        .unwrap();
+        assert_eq!(store.get_all_passwords().len(), 1);
+    }
+
+    #[test]
+    fn test_tampered_header_is_detected() {
+        let store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        let output = store.serialize().unwrap();
+
+        // The scrypt log2n parameter is right after the version, field type and field length.
+        let mut tampered = output.clone();
+        tampered[7] = 11;
+        match PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            None,
+            SafeVec::new(tampered),
+        ) {
+            Err(PasswordError::DecryptionError) => {}
+            _ => panic!(),
+        }
+
+        // Parameters that would need terabytes of memory are refused before deriving the key
+        let mut tampered = output.clone();
+        tampered[7] = 40;
+        match PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            None,
+            SafeVec::new(tampered),
+        ) {
+            Err(PasswordError::CorruptionError) => {}
+            _ => panic!(),
+        }
+
+        // Truncated header
+        match PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            None,
+            SafeVec::new(output[..20].to_vec()),
+        ) {
+            Err(PasswordError::Io(_)) => {}
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_nested_key_slots_are_refused() {
+        // Deep enough to overflow the stack, if key slots were read recursively
+        let mut slot = vec![FIELD_END, 0, 0];
+        while slot.len() + 3 <= u16::MAX as usize {
+            let mut outer = vec![FIELD_KEY_SLOT];
+            outer.extend_from_slice(&(slot.len() as u16).to_be_bytes());
+            outer.extend_from_slice(&slot);
+            slot = outer;
+        }
+        let mut input = VERSION.to_be_bytes().to_vec();
+        input.extend_from_slice(&slot);
+
+        match PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            None,
+            SafeVec::new(input),
+        ) {
+            Err(PasswordError::CorruptionError) => {}
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_versions() {
+        let store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        let output = store.serialize().unwrap();
+
+        let mut newer = output.clone();
+        newer[3] = 4;
+        match PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            None,
+            SafeVec::new(newer),
+        ) {
+            Err(PasswordError::OutdatedRoosterBinaryError) => {}
+            _ => panic!(),
+        }
+
+        let mut older = output.clone();
+        older[3] = 2;
+        match PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            None,
+            SafeVec::new(older),
+        ) {
+            Err(PasswordError::NeedUpgradeErrorFromV2) => {}
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_unknown_header_field_needs_newer_binary() {
+        let store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        let output = store.serialize().unwrap();
+
+        let mut newer = output[..4].to_vec();
+        newer.extend_from_slice(&[42, 0, 1, 0]);
+        newer.extend_from_slice(&output[4..]);
+        match PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            None,
+            SafeVec::new(newer),
+        ) {
+            Err(PasswordError::OutdatedRoosterBinaryError) => {}
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_keyfile() {
+        let keyfile = SafeVec::new(vec![1; 32]);
+        let mut store = PasswordStore::with_keyfile(
+            SafeString::from_string("****".to_owned()),
+            Some(keyfile.clone()),
+        )
+        .unwrap();
+        let output = store.serialize().unwrap();
+        assert_eq!(
+            PasswordStore::keyfile_required_from_input(&output).unwrap(),
+            true
+        );
+
+        match PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            None,
+            SafeVec::new(output.clone()),
+        ) {
+            Err(PasswordError::KeyfileRequiredError) => {}
+            _ => panic!(),
+        }
+        match PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            Some(SafeVec::new(vec![2; 32])),
+            SafeVec::new(output.clone()),
+        ) {
+            Err(PasswordError::DecryptionError) => {}
+            _ => panic!(),
+        }
+        assert!(PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            Some(keyfile),
+            SafeVec::new(output),
+        )
+        .is_ok());
+
+        // Without a keyfile, the password file opens whether one is given or not
+        store.change_keyfile(None).unwrap();
+        let output = store.serialize().unwrap();
+        assert_eq!(
+            PasswordStore::keyfile_required_from_input(&output).unwrap(),
+            false
+        );
+        assert!(PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            Some(SafeVec::new(vec![2; 32])),
+            SafeVec::new(output),
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_key_slots() {
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        store
+            .add_password(Password::new("name", "username", "password"))
+            .unwrap();
+        store.add_key_slot("alice", "aaaa").unwrap();
+        store.add_key_slot("bob", "bbbb").unwrap();
+        match store.add_key_slot("Bob", "cccc") {
+            Err(PasswordError::KeySlotExistsError) => {}
+            _ => panic!(),
+        }
+        let output = store.serialize().unwrap();
+
+        for (master_password, slot) in [("****", "default"), ("aaaa", "alice"), ("bbbb", "bob")] {
+            let store = PasswordStore::from_input(
+                SafeString::from_string(master_password.to_owned()),
+                None,
+                SafeVec::new(output.clone()),
+            )
+            .unwrap();
+            assert_eq!(store.current_key_slot(), Some(slot));
+            assert_eq!(store.get_all_passwords().len(), 1);
+        }
+
+        // Removing a key slot leaves the data key and the other key slots alone
+        let mut store = PasswordStore::from_input(
+            SafeString::from_string("aaaa".to_owned()),
+            None,
+            SafeVec::new(output),
+        )
+        .unwrap();
+        let data_key = store.key().clone();
+        let bob = store.key_slots()[2].wrapped_key.clone();
+        store.remove_key_slot("default").unwrap();
+        assert_eq!(store.key(), &data_key);
+        assert_eq!(store.key_slots()[1].wrapped_key, bob);
+        assert_eq!(store.current_key_slot(), Some("alice"));
+        let output = store.serialize().unwrap();
+        match PasswordStore::from_input(
+            SafeString::from_string("****".to_owned()),
+            None,
+            SafeVec::new(output.clone()),
+        ) {
+            Err(PasswordError::DecryptionError) => {}
+            _ => panic!(),
+        }
+
+        // Changing a master password only changes its own key slot
+        let mut store = PasswordStore::from_input(
+            SafeString::from_string("bbbb".to_owned()),
+            None,
+            SafeVec::new(output),
+        )
+        .unwrap();
+        store.change_master_password("dddd").unwrap();
+        let output = store.serialize().unwrap();
+        for master_password in ["aaaa", "dddd"] {
+            assert!(PasswordStore::from_input(
+                SafeString::from_string(master_password.to_owned()),
+                None,
+                SafeVec::new(output.clone()),
+            )
+            .is_ok());
+        }
+
+        // Removing the key slot of the current master password leaves nothing to derive from
+        let mut other_store = PasswordStore::from_input(
+            SafeString::from_string("dddd".to_owned()),
+            None,
+            SafeVec::new(output),
+        )
+        .unwrap();
+        other_store.remove_key_slot("bob").unwrap();
+        match other_store.change_master_password("eeee") {
+            Err(PasswordError::MasterPasswordUnknownError) => {}
+            _ => panic!(),
+        }
+        match other_store.add_key_slot("carol", "cccc") {
+            Err(PasswordError::MasterPasswordUnknownError) => {}
+            _ => panic!(),
+        }
+
+        store.remove_key_slot("alice").unwrap();
+        match store.remove_key_slot("bob") {
+            Err(PasswordError::LastKeySlotError) => {}
+            _ => panic!(),
+        }
+        match store.remove_key_slot("carol") {
+            Err(PasswordError::NoSuchKeySlotError) => {}
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_key_slots_keep_the_keyfile() {
+        let keyfile = SafeVec::new(vec![1; 32]);
+        let mut store = PasswordStore::with_keyfile(
+            SafeString::from_string("****".to_owned()),
+            Some(keyfile.clone()),
+        )
+        .unwrap();
+        store.add_key_slot("alice", "aaaa").unwrap();
+        assert!(store.key_slots().iter().all(|slot| slot.has_keyfile()));
+        let output = store.serialize().unwrap();
+
+        match PasswordStore::from_input(
+            SafeString::from_string("aaaa".to_owned()),
+            None,
+            SafeVec::new(output.clone()),
+        ) {
+            Err(PasswordError::KeyfileRequiredError) => {}
+            _ => panic!(),
+        }
+        let store = PasswordStore::from_input(
+            SafeString::from_string("aaaa".to_owned()),
+            Some(keyfile),
+            SafeVec::new(output),
+        )
+        .unwrap();
+        assert_eq!(store.current_key_slot(), Some("alice"));
+    }
+
+    #[test]
+    fn test_key_slot_names_are_checked() {
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        for name in ["".to_owned(), "a".repeat(MAX_KEY_SLOT_NAME_LEN + 1)] {
+            match store.add_key_slot(&name, "aaaa") {
+                Err(PasswordError::InvalidKeySlotNameError) => {}
+                _ => panic!(),
+            }
+        }
+        assert!(store
+            .add_key_slot(&"a".repeat(MAX_KEY_SLOT_NAME_LEN), "aaaa")
+            .is_ok());
+        assert!(store.serialize().is_ok());
+    }
+
+    #[test]
+    fn test_create_password_store() {
+        let store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        assert_eq!(store.get_all_passwords().len(), 0);
+    }
+
+    #[test]
+    fn test_add_password() {
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+
+        assert!(store
+            .add_password(Password::new("name", "username", "password"))
+            .is_ok());
+
+        // need a wrap around the immutable borrow so the borrow checker is happy
+        {
+            // only the 1 password is here
+            let passwords = store.get_all_passwords();
+            assert_eq!(passwords.len(), 1);
+
+            // is had the right information
+            let p = passwords[0];
+            assert_eq!(p.name, "name");
+            assert_eq!(p.username, "username");
+            assert_eq!(p.password, "password".into());
+            assert_eq!(p.updated_at, p.created_at);
+        }
+
+        // cant add two passwords with same app name and username
+        match store.add_password(Password::new("NAME", "USERNAME", "password")) {
+            Err(PasswordError::AppExistsError) => {}
+            _ => panic!(),
+        }
+
+        // but different accounts for the same app are fine
+        assert!(store
+            .add_password(Password::new("name", "other username", "password"))
+            .is_ok());
+        assert_eq!(store.get_all_passwords().len(), 2);
+        assert_eq!(store.get_all_passwords()[0].username, "other username");
+
+        // empty password => not allowed
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        assert!(store
+            .add_password(Password::new("name", "username", ""))
+            .is_err());
+    }
+
+    #[test]
+    fn test_change_password() {
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+
+        assert!(store
+            .add_password(Password::new("name", "username", "password"))
+            .is_ok());
+        let changed = store
+            .change_password("name", "username", &|p| {
+                // change app name and password, keep username
+                Password::new("newname", p.username, "newpassword")
+            })
+            .unwrap();
+        assert_eq!(
+            changed,
+            Password {
+                history: changed.history.clone(),
+                ..Password::new("newname", "username", "newpassword")
+            }
+        );
+        assert_eq!(changed.history.len(), 1);
+        assert_eq!(changed.history[0].password, "password".into());
+        assert_eq!(store.get_all_passwords().len(), 1);
+        assert_eq!(store.get_all_passwords()[0].name, "newname");
+        assert_eq!(store.get_all_passwords()[0].username, "username");
+        assert_eq!(store.get_all_passwords()[0].password, "newpassword".into());
+
+        // case insensitive works too
+        let changed = store
+            .change_password("newname", "username", &|p| p)
+            .unwrap();
+        assert_eq!(
+            changed,
+            Password {
+                history: changed.history.clone(),
+                ..Password::new("newname", "username", "newpassword")
+            }
+        );
+        // the password didn't change, so the history didn't either
+        assert_eq!(changed.history.len(), 1);
+        assert_eq!(store.get_all_passwords().len(), 1);
+        assert_eq!(store.get_all_passwords()[0].name, "newname");
+        assert_eq!(store.get_all_passwords()[0].username, "username");
+        assert_eq!(store.get_all_passwords()[0].password, "newpassword".into());
+
+        // empty password => do not change anything
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        assert!(store
+            .add_password(Password::new("name", "username", "password"))
+            .is_ok());
+        assert!(store
+            .change_password("name", "username", &|p| {
+                // change app name and password, keep username
+                Password::new(p.username.clone(), p.username.clone(), "")
+            })
+            .is_err());
+        assert_eq!(store.get_all_passwords()[0].name, "name");
+        assert_eq!(store.get_all_passwords()[0].username, "username");
+        assert_eq!(store.get_all_passwords()[0].password, "password".into());
+    }
+
+    #[test]
+    fn test_password_history() {
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        assert!(store
+            .add_password(Password::new("name", "username", "password0"))
+            .is_ok());
+
+        for i in 1..=(HISTORY_LEN + 2) {
+            store
+                .change_password("name", "username", &|p| Password {
+                    password: format!("password{}", i).into(),
+                    ..p
+                })
+                .unwrap();
+        }
+
+        // Most recent first, and only the last few
+        let password = store.get_password("name", "username").unwrap();
+        assert_eq!(
+            password.password,
+            format!("password{}", HISTORY_LEN + 2).into()
+        );
+        assert_eq!(password.history.len(), HISTORY_LEN);
+        assert_eq!(
+            password.history[0].password,
+            format!("password{}", HISTORY_LEN + 1).into()
+        );
+        assert_eq!(
+            password.history[HISTORY_LEN - 1].password,
+            "password2".into()
+        );
+
+        // Restoring swaps the restored password with the current one
+        let restored = store
+            .restore_previous_password("name", "username", 1)
+            .unwrap();
+        assert_eq!(restored.password, format!("password{}", HISTORY_LEN).into());
+        assert_eq!(restored.history.len(), HISTORY_LEN);
+        assert_eq!(
+            restored.history[0].password,
+            format!("password{}", HISTORY_LEN + 2).into()
+        );
+        assert_eq!(
+            restored.history[1].password,
+            format!("password{}", HISTORY_LEN + 1).into()
+        );
+        assert!(!restored
+            .history
+            .iter()
+            .any(|previous| previous.password == restored.password));
+
+        match store.restore_previous_password("name", "username", HISTORY_LEN) {
+            Err(PasswordError::NoSuchPreviousPasswordError) => {}
+            _ => panic!(),
+        }
+        match store.restore_previous_password("nope", "username", 0) {
+            Err(PasswordError::NoSuchAppError) => {}
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_delete_password() {
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+
+        assert!(store
+            .add_password(Password::new("name1", "username", "password"))
+            .is_ok());
+        assert!(store
+            .add_password(Password::new("name2", "username", "password"))
+            .is_ok());
+        assert_eq!(store.get_all_passwords().len(), 2);
+
+        assert_eq!(
+            store.delete_password("name1", "username").unwrap(),
+            Password::new("name1", "username", "password")
+        );
+        assert!(store.get_password("name1", "username").is_none());
+        assert_eq!(store.get_all_passwords().len(), 1);
+        // case insensitive works too
+        assert_eq!(
+            store.delete_password("NAME2", "USERNAME").unwrap(),
+            Password::new("name2", "username", "password")
+        );
+        assert!(store.get_password("name2", "username").is_none());
+        assert_eq!(store.get_all_passwords().len(), 0);
+
+        // only the account with the right username is deleted
+        assert!(store
+            .add_password(Password::new("name", "username1", "password"))
+            .is_ok());
+        assert!(store
+            .add_password(Password::new("name", "username2", "password"))
+            .is_ok());
+        assert!(store.delete_password("name", "username3").is_err());
+        assert!(store.delete_password("name", "username2").is_ok());
+        assert!(store.has_password("name", "username1"));
+        assert_eq!(store.get_all_passwords().len(), 1);
+    }
+
+    #[test]
+    fn test_get_password() {
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+
+        assert_eq!(store.get_password("name", "username"), None);
+        assert!(store
+            .add_password(Password::new("name", "username", "password"))
+            .is_ok());
+        assert_eq!(
+            store.get_password("name", "username").unwrap(),
+            Password::new("name", "username", "password")
+        );
+        assert_eq!(
+            store.get_password("NaMe", "UserName").unwrap(),
+            Password::new("name", "username", "password")
+        );
+    }
+
+    #[test]
+    fn test_has_password() {
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+
+        assert!(!store.has_password("name", "username"));
+        assert!(store
+            .add_password(Password::new("name", "username", "password"))
+            .is_ok());
+        assert!(store.has_password("name", "username"));
+        assert!(!store.has_password("name", "other username"));
+    }
+
+    #[test]
+    fn test_interrupted_sync_keeps_previous_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("passwords.rooster");
+
+        let mut store = PasswordStore::new(SafeString::from_string("****".to_owned())).unwrap();
+        assert!(store
+            .add_password(Password::new("name1", "username", "password"))
+            .is_ok());
+        store.sync(&path).unwrap();
+
+        // Simulate the process dying halfway through saving the second password.
+        assert!(store
+            .add_password(Password::new("name2", "username", "password"))
+            .is_ok());
+        let output = store.serialize().unwrap();
+        assert!(atomic_file::write_with(&path, |file| {
+            file.write_all(&output[..output.len() / 2])?;
+            Err(IoError::new(IoErrorKind::Other, "killed"))
+        })
+        .is_err());
+
+        let input = SafeVec::new(std::fs::read(&path).unwrap());
+        let store =
+            PasswordStore::from_input(SafeString::from_string("****".to_owned()), None, input)
+                .unwrap();
+        assert_eq!(store.get_all_passwords().len(), 1);
+        assert_eq!(store.get_all_passwords()[0].name, "name1");
+    }
+}
@@ -0,0 +1,147 @@
+//! Running Rooster from scripts, cron or CI, where nobody is there to answer questions.
+
+use rclio::{CliInputOutput, OutputType};
+use rtoolbox::safe_string::SafeString;
+use rtoolbox::safe_vec::SafeVec;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result as IoResult};
+use std::path::Path;
+
+/// Passes everything through to the real input/output, except that questions fail right away
+/// when running with `--non-interactive`, instead of waiting for an answer that never comes.
+pub struct ScriptInputOutput<'a, T: CliInputOutput> {
+    inner: &'a mut T,
+    interactive: bool,
+    input_refused: bool,
+}
+
+impl<'a, T: CliInputOutput> ScriptInputOutput<'a, T> {
+    pub fn new(inner: &'a mut T, interactive: bool) -> ScriptInputOutput<'a, T> {
+        ScriptInputOutput {
+            inner,
+            interactive,
+            input_refused: false,
+        }
+    }
+
+    /// Whether a question failed because of `--non-interactive`.
+    pub fn input_refused(&self) -> bool {
+        self.input_refused
+    }
+
+    fn refuse(&mut self) -> Error {
+        self.input_refused = true;
+        Error::new(
+            ErrorKind::Unsupported,
+            "an answer is needed, but Rooster runs with --non-interactive",
+        )
+    }
+}
+
+impl<'a, T: CliInputOutput> CliInputOutput for ScriptInputOutput<'a, T> {
+    fn read_line(&mut self) -> IoResult<String> {
+        if !self.interactive {
+            return Err(self.refuse());
+        }
+        self.inner.read_line()
+    }
+
+    fn prompt_line(&mut self, prompt: impl ToString) -> IoResult<String> {
+        if !self.interactive {
+            return Err(self.refuse());
+        }
+        self.inner.prompt_line(prompt)
+    }
+
+    fn read_password(&mut self) -> IoResult<SafeString> {
+        if !self.interactive {
+            return Err(self.refuse());
+        }
+        self.inner.read_password()
+    }
+
+    fn prompt_password(&mut self, prompt: impl ToString) -> IoResult<SafeString> {
+        if !self.interactive {
+            return Err(self.refuse());
+        }
+        self.inner.prompt_password(prompt)
+    }
+
+    fn is_stdout_tty(&self) -> bool {
+        self.inner.is_stdout_tty()
+    }
+
+    fn nl(&mut self, output_type: OutputType) {
+        self.inner.nl(output_type)
+    }
+
+    fn write(&mut self, s: impl ToString, output_type: OutputType) {
+        self.inner.write(s, output_type)
+    }
+
+    fn writeln(&mut self, s: impl ToString, output_type: OutputType) {
+        self.inner.writeln(s, output_type)
+    }
+}
+
+/// Reads the master password, without the line break that usually ends files.
+fn read_master_password(mut reader: impl Read) -> IoResult<SafeString> {
+    let mut bytes = SafeVec::new(Vec::new());
+    reader.read_to_end(bytes.inner_mut())?;
+
+    let mut len = bytes.len();
+    if bytes[..len].ends_with(b"\n") {
+        len -= 1;
+        if bytes[..len].ends_with(b"\r") {
+            len -= 1;
+        }
+    }
+
+    let password = std::str::from_utf8(&bytes[..len])
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "the master password is not UTF-8"))?;
+    Ok(SafeString::from_string(password.to_owned()))
+}
+
+pub fn read_master_password_from_file(path: &Path) -> IoResult<SafeString> {
+    read_master_password(File::open(path)?)
+}
+
+/// Reads the master password from a file descriptor that the caller opened for us, and closes it.
+#[cfg(unix)]
+pub fn read_master_password_from_fd(fd: i32) -> IoResult<SafeString> {
+    use std::os::unix::io::FromRawFd;
+
+    // Make sure the descriptor is open, so that we don't take ownership of a random number.
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(Error::last_os_error());
+    }
+    read_master_password(unsafe { File::from_raw_fd(fd) })
+}
+
+#[cfg(test)]
+mod test {
+    use super::read_master_password;
+    use std::ops::Deref;
+
+    #[test]
+    fn test_read_master_password() {
+        assert_eq!(
+            read_master_password(&b"hunter2"[..]).unwrap().deref(),
+            "hunter2"
+        );
+        assert_eq!(
+            read_master_password(&b"hunter2\n"[..]).unwrap().deref(),
+            "hunter2"
+        );
+        assert_eq!(
+            read_master_password(&b"hunter2\r\n"[..]).unwrap().deref(),
+            "hunter2"
+        );
+        // Only the last line break goes, the rest is part of the password
+        assert_eq!(
+            read_master_password(&b" hunter2 \n\n"[..]).unwrap().deref(),
+            " hunter2 \n"
+        );
+        assert!(read_master_password(&b"\xff\xfe"[..]).is_err());
+    }
+}
@@ -0,0 +1,715 @@
+//! Estimates how long it would take to guess a password, in the spirit of zxcvbn.
+//!
+//! A password is split into the parts an attacker would try first: common passwords, dictionary
+//! words (also with l33t substitutions), rows of keys, repeats, sequences and dates. Whatever is
+//! left is guessed character by character. The estimate is the number of guesses needed for the
+//! cheapest way to split the password.
+
+use crate::exit_code;
+use crate::ffi;
+use crate::generate::wordlist;
+use rclio::{CliInputOutput, OutputType};
+use std::collections::HashMap;
+
+/// Common passwords, most common first.
+const COMMON_PASSWORDS: &str = include_str!("wordlists/common_passwords.txt");
+
+/// How many guesses per second an attacker makes against a stolen password file. This is for a
+/// slow key derivation function like scrypt or Argon2id, on a few GPUs.
+const GUESSES_PER_SECOND: f64 = 1e4;
+
+/// Guesses per character that is not part of any pattern.
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+
+/// The least number of guesses for a pattern that is only part of the password.
+const MIN_SUBMATCH_GUESSES: f64 = 50.0;
+
+/// Repeats of longer parts aren't looked for, so that very long passwords are analyzed quickly.
+const MAX_REPEAT_BASE_LEN: usize = 64;
+
+const KEYBOARD_LINES: &[&str] = &[
+    // Rows
+    "`1234567890-=",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+    // Columns
+    "1qaz",
+    "2wsx",
+    "3edc",
+    "4rfv",
+    "5tgb",
+    "6yhn",
+    "7ujm",
+    "8ik,",
+    "9ol.",
+    "0p;/",
+];
+const SHIFTED_KEYS: &str = "~!@#$%^&*()_+{}|:\"<>?";
+const UNSHIFTED_KEYS: &str = "`1234567890-=[]\\;',./";
+
+/// Keys a keyboard pattern can start from.
+const KEYBOARD_STARTS: f64 = 47.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pattern {
+    CommonPassword,
+    DictionaryWord,
+    UserInput,
+    Keyboard,
+    Repeat,
+    Sequence,
+    Date,
+}
+
+impl Pattern {
+    fn warning(&self) -> &'static str {
+        match *self {
+            Pattern::CommonPassword => "It is one of the most common passwords.",
+            Pattern::DictionaryWord => {
+                "Dictionary words are easy to guess, even with s0me l3tters swapped or in CAPITALS."
+            }
+            Pattern::UserInput => "It contains the name of the app or your username.",
+            Pattern::Keyboard => "Rows of keys on the keyboard, like qwerty, are easy to guess.",
+            Pattern::Repeat => "Repeats like \"aaa\" or \"abcabc\" are easy to guess.",
+            Pattern::Sequence => "Sequences like \"abc\" or \"6543\" are easy to guess.",
+            Pattern::Date => "Dates and years are easy to guess.",
+        }
+    }
+}
+
+/// A part of the password that follows a pattern.
+#[derive(Clone, Debug)]
+struct Match {
+    start: usize,
+    end: usize,
+    guesses_log10: f64,
+    pattern: Pattern,
+}
+
+struct Dictionaries {
+    /// Lowercase word to rank and kind of word
+    words: HashMap<String, (usize, Pattern)>,
+    /// The length of the longest word, in characters
+    max_len: usize,
+}
+
+impl Dictionaries {
+    fn new(user_inputs: &[&str]) -> Dictionaries {
+        let mut words = HashMap::new();
+        // The wordlist isn't sorted by frequency, so each word is about as likely as the others
+        let dictionary = wordlist();
+        let dictionary_rank = dictionary.len() / 2;
+        for word in dictionary {
+            words.insert(word.to_string(), (dictionary_rank, Pattern::DictionaryWord));
+        }
+        for (rank, password) in COMMON_PASSWORDS.lines().enumerate() {
+            words.insert(password.to_string(), (rank + 1, Pattern::CommonPassword));
+        }
+        for (rank, input) in user_inputs.iter().enumerate() {
+            let input = input.to_lowercase();
+            if input.chars().count() >= 3 {
+                words.insert(input, (rank + 1, Pattern::UserInput));
+            }
+        }
+        let max_len = words.keys().map(|word| word.chars().count()).max().unwrap_or(0);
+        Dictionaries { words, max_len }
+    }
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// How many ways there are to capitalize a word, counting the usual ones only once.
+fn uppercase_variations(word: &[char]) -> f64 {
+    let upper = word.iter().filter(|c| c.is_uppercase()).count();
+    let lower = word.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    let first_only = word[0].is_uppercase() && upper == 1;
+    let last_only = word[word.len() - 1].is_uppercase() && upper == 1;
+    if lower == 0 || first_only || last_only {
+        return 2.0;
+    }
+    (1..=upper.min(lower))
+        .map(|k| binomial(upper + lower, k))
+        .sum()
+}
+
+fn unleet(c: char, one_as: char) -> char {
+    match c {
+        '4' | '@' => 'a',
+        '8' => 'b',
+        '(' | '{' | '[' | '<' => 'c',
+        '3' => 'e',
+        '6' | '9' => 'g',
+        '1' | '!' | '|' => one_as,
+        '0' => 'o',
+        '$' | '5' => 's',
+        '7' | '+' => 't',
+        '2' => 'z',
+        c => c,
+    }
+}
+
+fn dictionary_matches(password: &[char], dictionaries: &Dictionaries, matches: &mut Vec<Match>) {
+    let lowercase: Vec<char> = password
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    for start in 0..password.len() {
+        for end in start + 3..=password.len().min(start + dictionaries.max_len) {
+            let original = &password[start..end];
+            let word = &lowercase[start..end];
+            let variations = uppercase_variations(original);
+
+            let mut candidates = vec![(word.iter().collect::<String>(), 1.0)];
+            candidates.push((word.iter().rev().collect::<String>(), 2.0));
+            for one_as in ['i', 'l'].iter() {
+                let unleeted: String = word.iter().map(|c| unleet(*c, *one_as)).collect();
+                let substitutions = word.iter().filter(|c| unleet(**c, *one_as) != **c).count();
+                if substitutions > 0 {
+                    candidates.push((unleeted, 2.0 * substitutions as f64));
+                }
+            }
+
+            for (candidate, factor) in candidates {
+                if let Some(&(rank, pattern)) = dictionaries.words.get(&candidate) {
+                    matches.push(Match {
+                        start,
+                        end,
+                        guesses_log10: (rank as f64 * variations * factor).log10(),
+                        pattern,
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn unshift(c: char) -> (char, bool) {
+    match SHIFTED_KEYS.find(c) {
+        Some(index) => (UNSHIFTED_KEYS.as_bytes()[index] as char, true),
+        None => {
+            let lower = c.to_ascii_lowercase();
+            (lower, lower != c)
+        }
+    }
+}
+
+fn keyboard_matches(password: &[char], matches: &mut Vec<Match>) {
+    let keys: Vec<(char, bool)> = password.iter().map(|c| unshift(*c)).collect();
+
+    for start in 0..keys.len() {
+        for end in start + 3..=keys.len() {
+            let run: String = keys[start..end].iter().map(|(c, _)| *c).collect();
+            let reversed: String = run.chars().rev().collect();
+            if !KEYBOARD_LINES
+                .iter()
+                .any(|line| line.contains(&run) || line.contains(&reversed))
+            {
+                // Longer runs won't match either
+                break;
+            }
+            let shifted = keys[start..end].iter().filter(|(_, s)| *s).count();
+            let len = (end - start) as f64;
+            // Where to start, which way to go and for how long, and which keys are shifted
+            let guesses = KEYBOARD_STARTS * 4.0 * len * if shifted > 0 { 2.0 } else { 1.0 };
+            matches.push(Match {
+                start,
+                end,
+                guesses_log10: guesses.log10(),
+                pattern: Pattern::Keyboard,
+            });
+        }
+    }
+}
+
+fn repeat_matches(password: &[char], dictionaries: &Dictionaries, matches: &mut Vec<Match>) {
+    for start in 0..password.len() {
+        for base_len in 1..=((password.len() - start) / 2).min(MAX_REPEAT_BASE_LEN) {
+            let base = &password[start..start + base_len];
+            if start >= base_len && &password[start - base_len..start] == base {
+                // Already part of a longer repeat
+                continue;
+            }
+            if (1..base_len).any(|d| base_len % d == 0 && base[d..] == base[..base_len - d]) {
+                // A repeat of a shorter base is cheaper
+                continue;
+            }
+            let mut count = 1;
+            while start + (count + 1) * base_len <= password.len()
+                && &password[start + count * base_len..start + (count + 1) * base_len] == base
+            {
+                count += 1;
+            }
+            if count < 2 || count * base_len < 3 {
+                continue;
+            }
+            let (base_guesses_log10, _) = minimum_guesses(base, dictionaries);
+            matches.push(Match {
+                start,
+                end: start + count * base_len,
+                guesses_log10: base_guesses_log10 + (count as f64).log10(),
+                pattern: Pattern::Repeat,
+            });
+        }
+    }
+}
+
+fn sequence_matches(password: &[char], matches: &mut Vec<Match>) {
+    let same_class = |a: char, b: char| {
+        (a.is_ascii_lowercase() && b.is_ascii_lowercase())
+            || (a.is_ascii_uppercase() && b.is_ascii_uppercase())
+            || (a.is_ascii_digit() && b.is_ascii_digit())
+    };
+
+    let mut start = 0;
+    while start + 2 < password.len() {
+        let delta = password[start + 1] as i64 - password[start] as i64;
+        let mut end = start + 1;
+        while end < password.len()
+            && (delta == 1 || delta == -1)
+            && password[end] as i64 - password[end - 1] as i64 == delta
+            && same_class(password[end], password[start])
+        {
+            end += 1;
+        }
+
+        if end - start >= 3 {
+            let first = password[start];
+            let base = if "aAzZ019".contains(first) {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            let direction = if delta < 0 { 2.0 } else { 1.0 };
+            matches.push(Match {
+                start,
+                end,
+                guesses_log10: (base * (end - start) as f64 * direction).log10(),
+                pattern: Pattern::Sequence,
+            });
+            start = end - 1;
+        } else {
+            start += 1;
+        }
+    }
+}
+
+fn current_year() -> i64 {
+    1970 + ffi::time() as i64 / 31_556_952
+}
+
+fn full_year(year: i64, digits: usize) -> Option<i64> {
+    match digits {
+        2 if year > 50 => Some(1900 + year),
+        2 => Some(2000 + year),
+        4 if (1900..=2099).contains(&year) => Some(year),
+        _ => None,
+    }
+}
+
+/// Splits digits like 311299 or 1999-12-31 into a year, and checks the rest is a day and month.
+fn parse_date(parts: &[&str]) -> Option<i64> {
+    let numbers: Vec<i64> = parts
+        .iter()
+        .map(|part| part.parse::<i64>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let is_day_month = |a: i64, b: i64| (1..=31).contains(&a) && (1..=12).contains(&b);
+
+    // Year last (31 12 1999, 12 31 99) or first (1999 12 31)
+    if let Some(year) = full_year(numbers[2], parts[2].len()) {
+        if is_day_month(numbers[0], numbers[1]) || is_day_month(numbers[1], numbers[0]) {
+            return Some(year);
+        }
+    }
+    if let Some(year) = full_year(numbers[0], parts[0].len()) {
+        if is_day_month(numbers[2], numbers[1]) || is_day_month(numbers[1], numbers[2]) {
+            return Some(year);
+        }
+    }
+    None
+}
+
+fn date_guesses_log10(year: i64, with_day: bool, with_separator: bool) -> f64 {
+    let year_space = (year - current_year()).abs().max(20) as f64;
+    let mut guesses = year_space;
+    if with_day {
+        guesses *= 365.0;
+    }
+    if with_separator {
+        guesses *= 4.0;
+    }
+    guesses.log10()
+}
+
+fn date_matches(password: &[char], matches: &mut Vec<Match>) {
+    let is_separator = |c: char| " /\\-._".contains(c);
+
+    for start in 0..password.len() {
+        for end in start + 4..=password.len().min(start + 10) {
+            let candidate: String = password[start..end].iter().collect();
+
+            let year = if candidate.chars().all(|c| c.is_ascii_digit()) {
+                match candidate.len() {
+                    4 => full_year(candidate.parse().unwrap(), 4).map(|year| (year, false)),
+                    6 => parse_date(&[&candidate[..2], &candidate[2..4], &candidate[4..]])
+                        .map(|year| (year, true)),
+                    8 => parse_date(&[&candidate[..2], &candidate[2..4], &candidate[4..]])
+                        .or_else(|| {
+                            parse_date(&[&candidate[..4], &candidate[4..6], &candidate[6..]])
+                        })
+                        .map(|year| (year, true)),
+                    _ => None,
+                }
+                .map(|(year, with_day)| date_guesses_log10(year, with_day, false))
+            } else {
+                let parts: Vec<&str> = candidate.split(is_separator).collect();
+                let separators: Vec<char> =
+                    candidate.chars().filter(|c| is_separator(*c)).collect();
+                if parts.len() == 3
+                    && separators.len() == 2
+                    && separators[0] == separators[1]
+                    && parts
+                        .iter()
+                        .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+                {
+                    parse_date(&parts).map(|year| date_guesses_log10(year, true, true))
+                } else {
+                    None
+                }
+            };
+
+            if let Some(guesses_log10) = year {
+                matches.push(Match {
+                    start,
+                    end,
+                    guesses_log10,
+                    pattern: Pattern::Date,
+                });
+            }
+        }
+    }
+}
+
+/// The fewest guesses needed to find the password, in log10, and the patterns it is made of.
+fn minimum_guesses(password: &[char], dictionaries: &Dictionaries) -> (f64, Vec<Match>) {
+    let mut matches = Vec::new();
+    dictionary_matches(password, dictionaries, &mut matches);
+    keyboard_matches(password, &mut matches);
+    repeat_matches(password, dictionaries, &mut matches);
+    sequence_matches(password, &mut matches);
+    date_matches(password, &mut matches);
+
+    let mut matches_by_end: Vec<Vec<Match>> = vec![Vec::new(); password.len() + 1];
+    for m in matches {
+        let end = m.end;
+        matches_by_end[end].push(m);
+    }
+
+    // best[i] is the cheapest way to guess the first i characters
+    let mut best: Vec<(f64, Option<Match>)> = vec![(0.0, None); password.len() + 1];
+    for end in 1..=password.len() {
+        best[end] = (best[end - 1].0 + BRUTEFORCE_CARDINALITY.log10(), None);
+        for m in matches_by_end[end].iter() {
+            let whole_password = m.start == 0 && m.end == password.len();
+            let guesses_log10 = if whole_password {
+                m.guesses_log10
+            } else {
+                m.guesses_log10.max(MIN_SUBMATCH_GUESSES.log10())
+            };
+            if best[m.start].0 + guesses_log10 < best[end].0 {
+                best[end] = (best[m.start].0 + guesses_log10, Some(m.clone()));
+            }
+        }
+    }
+
+    let mut patterns = Vec::new();
+    let mut end = password.len();
+    while end > 0 {
+        match best[end].1 {
+            Some(ref m) => {
+                patterns.push(m.clone());
+                end = m.start;
+            }
+            None => end -= 1,
+        }
+    }
+    patterns.reverse();
+
+    (best[password.len()].0, patterns)
+}
+
+/// How strong a password is.
+#[derive(Debug)]
+pub struct Estimate {
+    pub guesses_log10: f64,
+    /// From 0, too guessable, to 4, very unguessable
+    pub score: u8,
+    /// What makes the password weak, if anything in particular
+    pub warning: Option<&'static str>,
+}
+
+impl Estimate {
+    pub fn is_weak(&self) -> bool {
+        self.score < 3
+    }
+
+    /// About how long an attacker would need to guess the password offline.
+    pub fn crack_time(&self) -> String {
+        let seconds = 10f64.powf(self.guesses_log10) / GUESSES_PER_SECOND;
+        let units = [
+            (60.0 * 60.0 * 24.0 * 365.0 * 100.0, "century", "centuries"),
+            (60.0 * 60.0 * 24.0 * 365.0, "year", "years"),
+            (60.0 * 60.0 * 24.0 * 30.0, "month", "months"),
+            (60.0 * 60.0 * 24.0, "day", "days"),
+            (60.0 * 60.0, "hour", "hours"),
+            (60.0, "minute", "minutes"),
+            (1.0, "second", "seconds"),
+        ];
+
+        if seconds < 1.0 {
+            return String::from("less than a second");
+        }
+        if seconds >= units[0].0 * 100.0 {
+            return String::from("centuries");
+        }
+        for (length, singular, plural) in units.iter() {
+            if seconds >= *length {
+                let count = (seconds / length).round() as u64;
+                return format!("{} {}", count, if count == 1 { singular } else { plural });
+            }
+        }
+        unreachable!()
+    }
+}
+
+/// Estimates the strength of a password. The user inputs are words that an attacker would try
+/// first, like the name of the app.
+pub fn estimate(password: &str, user_inputs: &[&str]) -> Estimate {
+    let chars: Vec<char> = password.chars().collect();
+    let dictionaries = Dictionaries::new(user_inputs);
+
+    let (guesses_log10, patterns) = minimum_guesses(&chars, &dictionaries);
+
+    let score = match guesses_log10 {
+        g if g < 3.0 => 0,
+        g if g < 6.0 => 1,
+        g if g < 8.0 => 2,
+        g if g < 10.0 => 3,
+        _ => 4,
+    };
+
+    let warning = patterns
+        .iter()
+        .max_by_key(|m| m.end - m.start)
+        .map(|m| m.pattern.warning())
+        .or(if chars.len() < 8 {
+            Some("It is short.")
+        } else {
+            None
+        });
+
+    Estimate {
+        guesses_log10,
+        score,
+        warning,
+    }
+}
+
+/// Warns about weak passwords and asks whether to use them anyway.
+pub fn confirm_strength(
+    password: &str,
+    user_inputs: &[&str],
+    what: &str,
+    io: &mut impl CliInputOutput,
+) -> Result<(), i32> {
+    let estimate = estimate(password, user_inputs);
+    if !estimate.is_weak() {
+        return Ok(());
+    }
+
+    io.warning(
+        format!(
+            "Hmm, this {} is weak. Someone who gets a copy of your password file could guess it in about {}.",
+            what,
+            estimate.crack_time()
+        ),
+        OutputType::Error,
+    );
+    if let Some(warning) = estimate.warning {
+        io.warning(warning, OutputType::Error);
+    }
+    io.info(
+        format!("Use this {} anyway? [y/n]", what),
+        OutputType::Standard,
+    );
+
+    loop {
+        match io.read_line() {
+            Ok(line) => {
+                if line.starts_with('y') {
+                    return Ok(());
+                } else if line.starts_with('n') {
+                    io.error(
+                        format!("Okay, pick a stronger {} then.", what),
+                        OutputType::Error,
+                    );
+                    return Err(exit_code::ERROR);
+                } else {
+                    io.info(
+                        format!("I did not get that. Use this {} anyway? [y/n]", what),
+                        OutputType::Standard,
+                    );
+                }
+            }
+            Err(err) => {
+                io.error(
+                    format!(
+                        "Woops, an error occured while reading your response (reason: {:?}).",
+                        err
+                    ),
+                    OutputType::Error,
+                );
+                return Err(exit_code::ERROR);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{estimate, uppercase_variations};
+
+    fn score(password: &str) -> u8 {
+        estimate(password, &[]).score
+    }
+
+    #[test]
+    fn test_common_passwords_are_weak() {
+        for password in [
+            "password",
+            "123456",
+            "qwerty",
+            "letmein",
+            "P@ssw0rd",
+            "iloveyou1",
+        ]
+        .iter()
+        {
+            assert_eq!(score(password), 0, "{}", password);
+        }
+    }
+
+    #[test]
+    fn test_patterns_are_weak() {
+        for password in [
+            // Dictionary words, also with l33t and capitals
+            "correct",
+            "C0rr3ct",
+            "ELEPHANT",
+            // Keyboard
+            "qwertyuiop",
+            "1qaz2wsx",
+            "zxcvbnm,./",
+            // Repeats
+            "aaaaaaaaaaaa",
+            "abcabcabcabc",
+            // Sequences
+            "abcdefgh",
+            "987654321",
+            // Dates
+            "31121999",
+            "1999-12-31",
+            "1987",
+        ]
+        .iter()
+        {
+            assert!(estimate(password, &[]).is_weak(), "{}", password);
+        }
+    }
+
+    #[test]
+    fn test_warnings() {
+        assert_eq!(
+            estimate("password", &[]).warning,
+            Some("It is one of the most common passwords.")
+        );
+        assert_eq!(
+            estimate("zxcvbnm,./", &[]).warning,
+            Some("Rows of keys on the keyboard, like qwerty, are easy to guess.")
+        );
+        assert_eq!(
+            estimate("19991231", &[]).warning,
+            Some("Dates and years are easy to guess.")
+        );
+        assert_eq!(
+            estimate("youtube2024", &["YouTube", "me@example.com"]).warning,
+            Some("It contains the name of the app or your username.")
+        );
+    }
+
+    #[test]
+    fn test_user_inputs_are_weak() {
+        assert!(!estimate("GrumpyOtter71", &[]).is_weak());
+        assert!(estimate("grumpyotter", &["grumpyotter"]).is_weak());
+    }
+
+    #[test]
+    fn test_random_passwords_are_strong() {
+        for password in [
+            "v8$Lq!2zR#mW",
+            "correct-horse-battery-staple",
+            "Tr0ub4dour&3-xkcd-kettle",
+            "kX9#pQ2!vL7@",
+        ]
+        .iter()
+        {
+            assert_eq!(score(password), 4, "{}", password);
+        }
+    }
+
+    #[test]
+    fn test_uppercase_variations() {
+        assert_eq!(
+            uppercase_variations(&"word".chars().collect::<Vec<_>>()),
+            1.0
+        );
+        assert_eq!(
+            uppercase_variations(&"Word".chars().collect::<Vec<_>>()),
+            2.0
+        );
+        assert_eq!(
+            uppercase_variations(&"WORD".chars().collect::<Vec<_>>()),
+            2.0
+        );
+        assert!(uppercase_variations(&"wOrD".chars().collect::<Vec<_>>()) > 2.0);
+    }
+
+    #[test]
+    fn test_crack_time() {
+        assert_eq!(estimate("password", &[]).crack_time(), "less than a second");
+        assert_eq!(estimate("v8$Lq!2zR#mW", &[]).crack_time(), "3 years");
+        assert_eq!(
+            estimate("correct-horse-battery-staple", &[]).crack_time(),
+            "centuries"
+        );
+    }
+
+    #[test]
+    fn test_long_passwords() {
+        assert!(estimate(&"x".repeat(1000), &[]).is_weak());
+        assert!(estimate(&"é".repeat(1000), &[]).is_weak());
+        assert!(estimate(&"abc".repeat(300), &[]).is_weak());
+        assert!(estimate(&"qwertyuiop".repeat(100), &[]).is_weak());
+        assert!(estimate(&"0123456789".repeat(100), &[]).is_weak());
+        assert!(!estimate(&"correct horse battery staple ".repeat(3), &[]).is_weak());
+    }
+}
@@ -0,0 +1,352 @@
+//! Time-based one-time passwords, as described in RFC 6238.
+
+use hmac::{Hmac, Mac};
+use rtoolbox::safe_string::SafeString;
+use rtoolbox::safe_vec::SafeVec;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Deref;
+
+pub const DEFAULT_DIGITS: u32 = 6;
+pub const DEFAULT_PERIOD: u32 = 30;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl Algorithm {
+    fn from_name(name: &str) -> Option<Algorithm> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha1" => Some(Algorithm::Sha1),
+            "sha256" => Some(Algorithm::Sha256),
+            "sha512" => Some(Algorithm::Sha512),
+            _ => None,
+        }
+    }
+}
+
+/// The part of the one-time code parameters that is invalid.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TotpError {
+    Secret,
+    Uri,
+    Digits,
+    Period,
+    Algorithm,
+}
+
+impl fmt::Display for TotpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let what = match *self {
+            TotpError::Secret => "secret, it should be base32",
+            TotpError::Uri => "URI, it should start with otpauth://totp/",
+            TotpError::Digits => "number of digits, it should be 6, 7 or 8",
+            TotpError::Period => "period, it should be more than 0 seconds",
+            TotpError::Algorithm => "algorithm, it should be SHA1, SHA256 or SHA512",
+        };
+        write!(f, "invalid {}", what)
+    }
+}
+
+/// What we need to compute one-time codes for an account.
+///
+/// Deserializing goes through `Totp::new` too, so that imported files can't make us panic.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "TotpFields")]
+pub struct Totp {
+    /// The shared secret, base32 encoded, like authenticator apps show it
+    pub secret: SafeString,
+    pub digits: u32,
+    pub period: u32,
+    pub algorithm: Algorithm,
+}
+
+/// The fields of `Totp` as found in JSON, before validation.
+#[derive(Deserialize)]
+struct TotpFields {
+    secret: SafeString,
+    digits: u32,
+    period: u32,
+    algorithm: Algorithm,
+}
+
+impl TryFrom<TotpFields> for Totp {
+    type Error = TotpError;
+
+    fn try_from(fields: TotpFields) -> Result<Totp, TotpError> {
+        Totp::new(
+            fields.secret.deref(),
+            fields.digits,
+            fields.period,
+            fields.algorithm,
+        )
+    }
+}
+
+/// Decodes base32 (RFC 4648), ignoring case, spaces and padding.
+fn base32_decode(input: &str) -> Option<SafeVec> {
+    let mut output = SafeVec::new(Vec::with_capacity(input.len() * 5 / 8));
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+
+    for c in input.chars() {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            ' ' | '-' | '=' => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.inner_mut().push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(output)
+}
+
+impl Totp {
+    pub fn new(
+        secret: &str,
+        digits: u32,
+        period: u32,
+        algorithm: Algorithm,
+    ) -> Result<Totp, TotpError> {
+        match base32_decode(secret) {
+            Some(ref key) if !key.is_empty() => {}
+            _ => return Err(TotpError::Secret),
+        }
+        if !(6..=8).contains(&digits) {
+            return Err(TotpError::Digits);
+        }
+        if period == 0 {
+            return Err(TotpError::Period);
+        }
+
+        Ok(Totp {
+            secret: SafeString::from_string(
+                secret
+                    .chars()
+                    .filter(|c| !matches!(c, ' ' | '-' | '='))
+                    .collect::<String>()
+                    .to_ascii_uppercase(),
+            ),
+            digits,
+            period,
+            algorithm,
+        })
+    }
+
+    /// Reads an `otpauth://totp/...` URI, as found in the QR codes websites show.
+    pub fn from_uri(uri: &str) -> Result<Totp, TotpError> {
+        let rest = uri.strip_prefix("otpauth://totp/").ok_or(TotpError::Uri)?;
+        let query = match rest.find('?') {
+            Some(index) => &rest[index + 1..],
+            None => return Err(TotpError::Uri),
+        };
+
+        let mut secret = None;
+        let mut digits = DEFAULT_DIGITS;
+        let mut period = DEFAULT_PERIOD;
+        let mut algorithm = Algorithm::Sha1;
+        for pair in query.split('&') {
+            let (key, value) = match pair.find('=') {
+                Some(index) => (&pair[..index], &pair[index + 1..]),
+                None => (pair, ""),
+            };
+            match key {
+                "secret" => secret = Some(value),
+                "digits" => digits = value.parse().map_err(|_| TotpError::Digits)?,
+                "period" => period = value.parse().map_err(|_| TotpError::Period)?,
+                "algorithm" => {
+                    algorithm = Algorithm::from_name(value).ok_or(TotpError::Algorithm)?
+                }
+                // Issuer, image, etc. are only for display
+                _ => {}
+            }
+        }
+
+        Totp::new(secret.ok_or(TotpError::Secret)?, digits, period, algorithm)
+    }
+
+    /// Reads either an `otpauth://` URI or a base32 secret with the most common parameters.
+    pub fn parse(
+        input: &str,
+        digits: u32,
+        period: u32,
+        algorithm: &str,
+    ) -> Result<Totp, TotpError> {
+        if input.starts_with("otpauth://") {
+            return Totp::from_uri(input);
+        }
+        let algorithm = Algorithm::from_name(algorithm).ok_or(TotpError::Algorithm)?;
+        Totp::new(input, digits, period, algorithm)
+    }
+
+    /// The code for a given UNIX timestamp.
+    pub fn code_at(&self, timestamp: u64) -> String {
+        let key = base32_decode(self.secret.deref()).expect("secret was validated");
+        totp(
+            &key,
+            timestamp / self.period as u64,
+            self.digits,
+            self.algorithm,
+        )
+    }
+
+    /// How many seconds the code for a given UNIX timestamp remains valid.
+    pub fn seconds_remaining(&self, timestamp: u64) -> u64 {
+        self.period as u64 - timestamp % self.period as u64
+    }
+}
+
+fn hmac(algorithm: Algorithm, key: &[u8], message: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length
+    match algorithm {
+        Algorithm::Sha1 => {
+            let mut mac = Hmac::<Sha1>::new_from_slice(key).unwrap();
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+        Algorithm::Sha256 => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+        Algorithm::Sha512 => {
+            let mut mac = Hmac::<Sha512>::new_from_slice(key).unwrap();
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+    }
+}
+
+/// HOTP (RFC 4226) with the given hash function, zero-padded to `digits`.
+fn totp(key: &[u8], counter: u64, digits: u32, algorithm: Algorithm) -> String {
+    let hash = hmac(algorithm, key, &counter.to_be_bytes());
+
+    // Dynamic truncation
+    let offset = (hash[hash.len() - 1] & 0xf) as usize;
+    let binary = ((hash[offset] as u32 & 0x7f) << 24)
+        | ((hash[offset + 1] as u32) << 16)
+        | ((hash[offset + 2] as u32) << 8)
+        | (hash[offset + 3] as u32);
+
+    format!(
+        "{:0width$}",
+        binary as u64 % 10u64.pow(digits),
+        width = digits as usize
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{base32_decode, totp, Algorithm, Totp, TotpError};
+
+    const SEED_SHA1: &[u8] = b"12345678901234567890";
+    const SEED_SHA256: &[u8] = b"12345678901234567890123456789012";
+    const SEED_SHA512: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    // Test vectors from RFC 6238, appendix B
+    const VECTORS: &[(u64, &str, &str, &str)] = &[
+        (59, "94287082", "46119246", "90693936"),
+        (1111111109, "07081804", "68084774", "25091201"),
+        (1111111111, "14050471", "67062674", "99943326"),
+        (1234567890, "89005924", "91819424", "93441116"),
+        (2000000000, "69279037", "90698825", "38618901"),
+        (20000000000, "65353130", "77737706", "47863826"),
+    ];
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        for &(time, sha1, sha256, sha512) in VECTORS {
+            assert_eq!(totp(SEED_SHA1, time / 30, 8, Algorithm::Sha1), sha1);
+            assert_eq!(totp(SEED_SHA256, time / 30, 8, Algorithm::Sha256), sha256);
+            assert_eq!(totp(SEED_SHA512, time / 30, 8, Algorithm::Sha512), sha512);
+        }
+    }
+
+    #[test]
+    fn test_base32_decode() {
+        assert_eq!(
+            &base32_decode("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap()[..],
+            SEED_SHA1
+        );
+        assert_eq!(
+            &base32_decode("gezd gnbv gy3t qojq gezd gnbv gy3t qojq").unwrap()[..],
+            SEED_SHA1
+        );
+        assert_eq!(&base32_decode("MZXW6===").unwrap()[..], b"foo");
+        assert!(base32_decode("not base32!").is_none());
+    }
+
+    #[test]
+    fn test_code_at() {
+        let totp = Totp::new("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ", 8, 30, Algorithm::Sha1).unwrap();
+        assert_eq!(totp.code_at(59), "94287082");
+        assert_eq!(totp.seconds_remaining(59), 1);
+        assert_eq!(totp.seconds_remaining(60), 30);
+    }
+
+    #[test]
+    fn test_from_uri() {
+        let totp = Totp::from_uri(
+            "otpauth://totp/Example:alice@example.com?secret=JBSWY3DPEHPK3PXP&issuer=Example&algorithm=SHA256&digits=8&period=60",
+        )
+        .unwrap();
+        assert_eq!(totp.secret, "JBSWY3DPEHPK3PXP".into());
+        assert_eq!(totp.digits, 8);
+        assert_eq!(totp.period, 60);
+        assert_eq!(totp.algorithm, Algorithm::Sha256);
+
+        let totp = Totp::from_uri("otpauth://totp/Example?secret=jbswy3dpehpk3pxp").unwrap();
+        assert_eq!(totp.secret, "JBSWY3DPEHPK3PXP".into());
+        assert_eq!(totp.digits, 6);
+        assert_eq!(totp.period, 30);
+        assert_eq!(totp.algorithm, Algorithm::Sha1);
+
+        assert_eq!(
+            Totp::from_uri("otpauth://hotp/Example?secret=JBSWY3DPEHPK3PXP&counter=0"),
+            Err(TotpError::Uri)
+        );
+        assert_eq!(
+            Totp::from_uri("otpauth://totp/Example?issuer=Example"),
+            Err(TotpError::Secret)
+        );
+        assert_eq!(
+            Totp::from_uri("otpauth://totp/Example?secret=JBSWY3DPEHPK3PXP&algorithm=MD5"),
+            Err(TotpError::Algorithm)
+        );
+        assert_eq!(
+            Totp::from_uri("otpauth://totp/Example?secret=JBSWY3DPEHPK3PXP&period=0"),
+            Err(TotpError::Period)
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Totp::parse("JBSW Y3DP EHPK 3PXP", 6, 30, "sha1"),
+            Totp::new("JBSWY3DPEHPK3PXP", 6, 30, Algorithm::Sha1)
+        );
+        assert_eq!(
+            Totp::parse("JBSWY3DPEHPK3PXP", 4, 30, "sha1"),
+            Err(TotpError::Digits)
+        );
+        assert_eq!(
+            Totp::parse("JBSWY3DPEHPK3PXP", 6, 30, "md5"),
+            Err(TotpError::Algorithm)
+        );
+        assert_eq!(Totp::parse("", 6, 30, "sha1"), Err(TotpError::Secret));
+    }
+}
@@ -1,8 +1,27 @@
 pub mod prelude {
 
     pub use rclio::CursorInputOutput;
-    pub use rooster::main_with_args;
+    use rclio::CliInputOutput;
+    use std::sync::Once;
+
+    static SET_UP: Once = Once::new();
+
+    /// Sets up the environment for all the tests, before anything reads it.
+    fn set_up() {
+        // Tests must not leave processes behind that wipe the clipboard later
+        SET_UP.call_once(|| std::env::set_var("ROOSTER_CLIPBOARD_TIMEOUT", "0"));
+    }
+
+    pub fn main_with_args(
+        args: &[&str],
+        io: &mut impl CliInputOutput,
+        rooster_file_path: &PathBuf,
+    ) -> i32 {
+        set_up();
+        rooster::main_with_args(args, io, rooster_file_path)
+    }
     pub fn tempfile() -> PathBuf {
+        set_up();
         tempfile::NamedTempFile::new().unwrap().path().to_path_buf()
     }
     pub use std::io::Cursor;
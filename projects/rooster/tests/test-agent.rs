@@ -0,0 +1,118 @@
+#![cfg(unix)]
+
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn get(rooster_file: &PathBuf, ttyin: &str) -> i32 {
+    main_with_args(
+        &["rooster", "get", "-s", "youtube"],
+        &mut CursorInputOutput::new("", ttyin),
+        rooster_file,
+    )
+}
+
+// Environment variables are shared by the whole process, so everything is in one test
+#[test]
+fn test_agent() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket_path = dir.path().join("agent.sock");
+    std::env::set_var("ROOSTER_AGENT_SOCK", &socket_path);
+
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // Without an agent, the master password is needed every time
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "yt@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nv8$Lq!2zR#mW\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(1, get(&rooster_file, ""));
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "lock"],
+            &mut CursorInputOutput::new("", ""),
+            &rooster_file
+        )
+    );
+
+    std::thread::spawn(|| {
+        main_with_args(
+            &["rooster", "agent"],
+            &mut CursorInputOutput::new("", ""),
+            &PathBuf::new(),
+        )
+    });
+    while !socket_path.exists() {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    // Typed once, then remembered
+    assert_eq!(0, get(&rooster_file, "xxxx\n"));
+    assert_eq!(0, get(&rooster_file, ""));
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Github", "alice"],
+            &mut CursorInputOutput::new("", "kX9#pQ2!vL7@\n"),
+            &rooster_file
+        )
+    );
+
+    // Changing the master password needs the current one, and the agent learns the new one
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "set-master-password"],
+            &mut CursorInputOutput::new("", ""),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "set-master-password"],
+            &mut CursorInputOutput::new(
+                "",
+                "xxxx\ncorrect-horse-battery-staple\ncorrect-horse-battery-staple\n"
+            ),
+            &rooster_file
+        )
+    );
+    assert_eq!(0, get(&rooster_file, ""));
+
+    // Only one agent at a time
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "agent"],
+            &mut CursorInputOutput::new("", ""),
+            &rooster_file
+        )
+    );
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "lock"],
+            &mut CursorInputOutput::new("", ""),
+            &rooster_file
+        )
+    );
+    assert_eq!(1, get(&rooster_file, ""));
+    assert_eq!(0, get(&rooster_file, "correct-horse-battery-staple\n"));
+}
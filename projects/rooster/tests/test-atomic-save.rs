@@ -0,0 +1,99 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::fs;
+
+#[test]
+fn test_leftover_temporary_file_does_not_break_password_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let rooster_file = dir.path().join("passwords.rooster");
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "yt@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // Simulate a process that was killed while saving: only half of the new file was written.
+    let contents = fs::read(&rooster_file).unwrap();
+    fs::write(
+        dir.path().join(".passwords.rooster.abc123.tmp"),
+        &contents[..contents.len() / 2],
+    )
+    .unwrap();
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "get", "-s", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("abcd"));
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Google", "google@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nefgh\ny\n"),
+            &rooster_file
+        )
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlinked_password_file_stays_a_symlink() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let real_file = dir.path().join("passwords.rooster");
+    let symlink = dir.path().join("link.rooster");
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &real_file
+        )
+    );
+    std::os::unix::fs::symlink(&real_file, &symlink).unwrap();
+    fs::set_permissions(&real_file, fs::Permissions::from_mode(0o640)).unwrap();
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "yt@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
+            &symlink
+        )
+    );
+
+    assert!(fs::symlink_metadata(&symlink)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(
+        fs::metadata(&real_file).unwrap().permissions().mode() & 0o777,
+        0o640
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "get", "-s", "youtube"], &mut io, &real_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("abcd"));
+}
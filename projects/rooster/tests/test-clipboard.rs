@@ -0,0 +1,81 @@
+#![cfg(unix)]
+
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// Makes a fake clipboard tool that saves what it reads on stdin to a file.
+fn fake_clipboard_command(dir: &Path) -> String {
+    let command = dir.join("fake-clipboard");
+    fs::write(
+        &command,
+        format!(
+            "#!/bin/sh\ncat > '{}'\n",
+            dir.join("clipboard").to_str().unwrap()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&command, fs::Permissions::from_mode(0o755)).unwrap();
+    command.to_str().unwrap().to_string()
+}
+
+// Environment variables are shared by the whole process, so everything is in one test
+#[test]
+fn test_clipboard_command() {
+    let dir = tempfile::tempdir().unwrap();
+    std::env::set_var("ROOSTER_CLIPBOARD_CMD", fake_clipboard_command(dir.path()));
+
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    let password = "v8$Lq!2zR#'mW $(id)";
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "Youtube", "yt@example.com"],
+            &mut CursorInputOutput::new("", &format!("xxxx\n{}\n", password)),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("clipboard")).unwrap(),
+        password
+    );
+
+    fs::remove_file(dir.path().join("clipboard")).unwrap();
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "get", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("copied to clipboard"));
+    assert!(!output_as_string.contains(password));
+    assert!(!output_as_string.contains("wipe"));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("clipboard")).unwrap(),
+        password
+    );
+
+    // A failing clipboard command
+    std::env::set_var("ROOSTER_CLIPBOARD_CMD", "false");
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "get", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("something went wrong"));
+}
@@ -9,7 +9,7 @@ fn test_command_add() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -18,7 +18,7 @@ fn test_command_add() {
         0,
         main_with_args(
             &["rooster", "add", "-s", "Youtube", "yt@example.com"],
-            &mut CursorInputOutput::new("", "xxxx\nabcd\n"),
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
             &rooster_file
         )
     );
@@ -48,8 +48,102 @@ fn test_command_add() {
         1,
         main_with_args(
             &["rooster", "add", "-s", "Empty", "empty@example.com"],
-            &mut CursorInputOutput::new("", "xxxx\n\n"),
+            &mut CursorInputOutput::new("", "xxxx\n\ny\n"),
+            &rooster_file
+        )
+    );
+}
+
+#[test]
+fn test_command_add_weak_password() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // Weak passwords need a confirmation
+    let mut io = CursorInputOutput::new("", "xxxx\nyoutube123\nn\n");
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "yt@example.com"],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stderr_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("this password is weak"));
+    assert!(output_as_string.contains("It contains the name of the app or your username."));
+    assert_eq!(
+        9,
+        main_with_args(
+            &["rooster", "get", "-s", "youtube"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+
+    // Strong passwords don't
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "yt@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nv8$Lq!2zR#mW\n"),
+            &rooster_file
+        )
+    );
+}
+
+#[test]
+fn test_command_add_with_fields() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "add",
+                "-s",
+                "Youtube",
+                "yt@example.com",
+                "--url",
+                "https://youtube.com",
+                "--notes",
+                "recovery codes: 1234 5678",
+                "--field",
+                "Account number=42",
+                "--concealed-field",
+                "Security answer"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\nfluffy\n"),
+            &rooster_file
+        )
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "get", "-s", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("URL: https://youtube.com"));
+    assert!(output_as_string.contains("Notes: recovery codes: 1234 5678"));
+    assert!(output_as_string.contains("Account number: 42"));
+    assert!(output_as_string.contains("Security answer: fluffy"));
 }
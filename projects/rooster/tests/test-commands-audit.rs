@@ -0,0 +1,226 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+fn import_passwords(passwords_json: &str, rooster_file: &PathBuf) {
+    let import_file_json = tempfile();
+    File::create(import_file_json.clone())
+        .unwrap()
+        .write_all(format!("{{\"passwords\":[{}]}}", passwords_json).as_bytes())
+        .unwrap();
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "import",
+                "json",
+                import_file_json.as_path().to_str().unwrap()
+            ],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            rooster_file
+        )
+    );
+}
+
+fn audit(args: &[&str], rooster_file: &PathBuf) -> (i32, String) {
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    let code = main_with_args(args, &mut io, rooster_file);
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    (
+        code,
+        String::from_utf8_lossy(output_as_vecu8.as_slice()).into_owned(),
+    )
+}
+
+#[test]
+fn test_command_audit() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // Nothing to report
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "yt@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nv8$Lq!2zR#mW\n"),
+            &rooster_file
+        )
+    );
+    let (code, output) = audit(&["rooster", "audit"], &rooster_file);
+    assert_eq!(0, code);
+    assert!(output.contains("none of your passwords are reused, weak, old or breached"));
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    import_passwords(
+        &format!(
+            "{{\"name\":\"Github\",\"username\":\"alice\",\"password\":\"v8$Lq!2zR#mW\",\"created_at\":{now},\"updated_at\":{now}}},\
+             {{\"name\":\"Gitlab\",\"username\":\"alice\",\"password\":\"abcd\",\"created_at\":{now},\"updated_at\":{now}}},\
+             {{\"name\":\"Reddit\",\"username\":\"alice\",\"password\":\"kX9#pQ2!vL7@\",\"created_at\":1605554169,\"updated_at\":1605554169}}",
+            now = now
+        ),
+        &rooster_file,
+    );
+
+    let (code, output) = audit(&["rooster", "audit"], &rooster_file);
+    assert_eq!(2, code);
+    assert!(
+        output.contains("The same password is used for Github (alice), Youtube (yt@example.com)")
+    );
+    assert!(output.contains("Gitlab (alice): could be guessed in about"));
+    assert!(output.contains("Reddit (alice): last changed"));
+    assert!(!output.contains("Youtube (yt@example.com):"));
+    for password in ["v8$Lq!2zR#mW", "abcd", "kX9#pQ2!vL7@"].iter() {
+        assert!(!output.contains(password));
+    }
+
+    let (code, output) = audit(&["rooster", "audit", "--show"], &rooster_file);
+    assert_eq!(2, code);
+    assert!(output.contains("Gitlab (alice, password: abcd)"));
+
+    // Old passwords can be allowed
+    let (code, output) = audit(&["rooster", "audit", "--max-age", "100000"], &rooster_file);
+    assert_eq!(2, code);
+    assert!(!output.contains("Reddit"));
+}
+
+#[test]
+fn test_command_audit_json() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+    import_passwords(
+        "{\"name\":\"Github\",\"username\":\"alice\",\"password\":\"abcd\",\"created_at\":1605554169,\"updated_at\":1605554169},\
+         {\"name\":\"Gitlab\",\"username\":\"alice\",\"password\":\"abcd\",\"created_at\":1605554169,\"updated_at\":1605554169}",
+        &rooster_file,
+    );
+
+    let (code, output) = audit(&["rooster", "audit", "--format", "json"], &rooster_file);
+    assert_eq!(2, code);
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(report["reused"].as_array().unwrap().len(), 1);
+    assert_eq!(report["reused"][0][0]["name"], "Github");
+    assert_eq!(report["reused"][0][1]["name"], "Gitlab");
+    assert_eq!(report["weak"].as_array().unwrap().len(), 2);
+    assert_eq!(report["weak"][0]["score"], 0);
+    assert_eq!(report["stale"][0]["updated_at"], 1605554169);
+    assert!(report["stale"][0]["password"].is_null());
+
+    let (code, output) = audit(
+        &["rooster", "audit", "--format", "json", "--show"],
+        &rooster_file,
+    );
+    assert_eq!(2, code);
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(report["stale"][0]["password"], "abcd");
+
+    // A lower threshold lets weak passwords through
+    let (_, output) = audit(
+        &["rooster", "audit", "--format", "json", "--min-score", "0"],
+        &rooster_file,
+    );
+    let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert!(report["weak"].as_array().unwrap().is_empty());
+
+    // --json still works for the scripts that use it
+    let (code, json_output) = audit(&["rooster", "audit", "--json"], &rooster_file);
+    assert_eq!(2, code);
+    assert_eq!(
+        json_output,
+        audit(&["rooster", "audit", "--format", "json"], &rooster_file).1
+    );
+}
+
+#[test]
+fn test_command_audit_breach_db() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Github", "alice"],
+            &mut CursorInputOutput::new("", "xxxx\nletmein\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "yt@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nv8$Lq!2zR#mW\n"),
+            &rooster_file
+        )
+    );
+
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    for breach_db in ["hibp-sorted.txt", "hibp-ranges"].iter() {
+        let breach_db = fixtures.join(breach_db);
+        let (code, output) = audit(
+            &[
+                "rooster",
+                "audit",
+                "--format",
+                "json",
+                "--breach-db",
+                breach_db.to_str().unwrap(),
+            ],
+            &rooster_file,
+        );
+        assert_eq!(2, code);
+        let report: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(report["breached"].as_array().unwrap().len(), 1);
+        assert_eq!(report["breached"][0]["name"], "Github");
+        assert_eq!(report["breached"][0]["count"], 511200);
+
+        let (_, output) = audit(
+            &[
+                "rooster",
+                "audit",
+                "--breach-db",
+                breach_db.to_str().unwrap(),
+            ],
+            &rooster_file,
+        );
+        assert!(output.contains("Github (alice): seen 511200 times in data breaches"));
+        assert!(!output.contains("letmein"));
+    }
+
+    // The breach database must exist
+    let (code, _) = audit(
+        &[
+            "rooster",
+            "audit",
+            "--breach-db",
+            fixtures.join("missing").to_str().unwrap(),
+        ],
+        &rooster_file,
+    );
+    assert_eq!(1, code);
+}
@@ -1,6 +1,8 @@
 mod helpers;
 
 use crate::helpers::prelude::*;
+use std::fs::File;
+use std::io::Write;
 
 #[test]
 fn test_command_change() {
@@ -9,7 +11,7 @@ fn test_command_change() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -27,7 +29,7 @@ fn test_command_change() {
         0,
         main_with_args(
             &["rooster", "change", "-s", "youtube"],
-            &mut CursorInputOutput::new("", "xxxx\nabcd\n"),
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
             &rooster_file
         )
     );
@@ -47,8 +49,152 @@ fn test_command_change() {
         1,
         main_with_args(
             &["rooster", "change", "-s", "youtube"],
-            &mut CursorInputOutput::new("", "xxxx\n\n"),
+            &mut CursorInputOutput::new("", "xxxx\n\ny\n"),
             &rooster_file
         )
     );
 }
+
+#[test]
+fn test_command_change_fields() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "add",
+                "-s",
+                "Youtube",
+                "yt@example.com",
+                "--url",
+                "https://youtube.com",
+                "--field",
+                "PIN=1234"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // Changing fields only doesn't ask for a new password
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "change",
+                "-s",
+                "youtube",
+                "--url",
+                "",
+                "--notes",
+                "some notes",
+                "--remove-field",
+                "PIN",
+                "--concealed-field",
+                "Backup code"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\n9876\n"),
+            &rooster_file
+        )
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "get", "-s", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("Password: abcd"));
+    assert!(!output_as_string.contains("URL:"));
+    assert!(output_as_string.contains("Notes: some notes"));
+    assert!(!output_as_string.contains("PIN"));
+    assert!(output_as_string.contains("Backup code: 9876"));
+
+    // Unknown fields can't be removed
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "change", "youtube", "--remove-field", "PIN"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+}
+
+#[test]
+fn test_command_change_fields_keeps_updated_at() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    let import_file_json = tempfile();
+    File::create(import_file_json.clone()).unwrap().write_all(
+        "{\"passwords\":[{\"name\":\"Youtube\",\"username\":\"yt@example.com\",\"password\":\"abcd\",\"created_at\":1605554169,\"updated_at\":1605554169}]}".as_bytes()
+    ).unwrap();
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "import",
+                "json",
+                import_file_json.as_path().to_str().unwrap()
+            ],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "change", "youtube", "--notes", "some notes"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "export", "json"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("\"updated_at\":1605554169"));
+
+    // A new password does count as an update
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "change", "youtube"],
+            &mut CursorInputOutput::new("", "xxxx\nefgh\ny\n"),
+            &rooster_file
+        )
+    );
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "export", "json"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(!output_as_string.contains("\"updated_at\":1605554169"));
+}
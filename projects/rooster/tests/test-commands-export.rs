@@ -10,7 +10,7 @@ fn test_command_export_json() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -19,7 +19,7 @@ fn test_command_export_json() {
         0,
         main_with_args(
             &["rooster", "add", "-s", "Youtube", "yt@example.com"],
-            &mut CursorInputOutput::new("", "xxxx\nabcd\n"),
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
             &rooster_file
         )
     );
@@ -64,7 +64,7 @@ fn test_command_export_csv() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -73,7 +73,7 @@ fn test_command_export_csv() {
         0,
         main_with_args(
             &["rooster", "add", "-s", "Youtube", "yt@example.com"],
-            &mut CursorInputOutput::new("", "xxxx\nabcd\n"),
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
             &rooster_file
         )
     );
@@ -95,7 +95,7 @@ fn test_command_export_1password() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -104,7 +104,7 @@ fn test_command_export_1password() {
         0,
         main_with_args(
             &["rooster", "add", "-s", "Youtube", "yt@example.com"],
-            &mut CursorInputOutput::new("", "xxxx\nabcd\n"),
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
             &rooster_file
         )
     );
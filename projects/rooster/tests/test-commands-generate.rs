@@ -9,7 +9,7 @@ fn test_command_generate() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -42,3 +42,150 @@ fn test_command_generate() {
     let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
     assert!(output_as_string.contains("yt@example.com"));
 }
+
+#[test]
+fn test_command_generate_words() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "generate",
+                "-s",
+                "--words",
+                "5",
+                "--separator",
+                ".",
+                "Youtube",
+                "yt@example.com"
+            ],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("This password has about 64 bits of entropy."));
+
+    let prefix = "Here is your password: ";
+    let start = output_as_string.find(prefix).unwrap() + prefix.len();
+    let passphrase = output_as_string[start..].lines().next().unwrap();
+    assert_eq!(passphrase.split('.').count(), 5);
+
+    // Too few words
+    assert_eq!(
+        1,
+        main_with_args(
+            &[
+                "rooster",
+                "generate",
+                "-s",
+                "--words",
+                "2",
+                "Dropbox",
+                "me@example.com"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+}
+
+#[test]
+fn test_command_generate_character_classes() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "generate",
+                "-s",
+                "--length",
+                "12",
+                "--symbols",
+                "!@#",
+                "--no-ambiguous",
+                "--min-digits",
+                "4",
+                "--min-symbols",
+                "2",
+                "Youtube",
+                "yt@example.com"
+            ],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    let prefix = "Here is your password: ";
+    let start = output_as_string.find(prefix).unwrap() + prefix.len();
+    let password: String = output_as_string[start..].chars().take(12).collect();
+    assert!(password.chars().filter(|c| c.is_ascii_digit()).count() >= 4);
+    assert!(password.chars().filter(|c| "!@#".contains(*c)).count() >= 2);
+    assert!(password
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "!@#".contains(c)));
+    assert!(!password.chars().any(|c| "0O1lI|".contains(c)));
+
+    // Too short for the required characters
+    assert_eq!(
+        1,
+        main_with_args(
+            &[
+                "rooster",
+                "generate",
+                "-s",
+                "--length",
+                "6",
+                "--min-digits",
+                "4",
+                "Dropbox",
+                "me@example.com"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+
+    // No symbols left to pick from
+    assert_eq!(
+        1,
+        main_with_args(
+            &[
+                "rooster",
+                "generate",
+                "-s",
+                "--symbols",
+                "|",
+                "--no-ambiguous",
+                "Dropbox",
+                "me@example.com"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+}
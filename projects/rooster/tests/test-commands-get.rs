@@ -9,7 +9,7 @@ fn test_command_get() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -18,7 +18,7 @@ fn test_command_get() {
         0,
         main_with_args(
             &["rooster", "add", "-s", "First Website", "first@example.com"],
-            &mut CursorInputOutput::new("", "xxxx\nabcd\n"),
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
             &rooster_file
         )
     );
@@ -32,7 +32,7 @@ fn test_command_get() {
                 "Second Website",
                 "second@example.com"
             ],
-            &mut CursorInputOutput::new("", "xxxx\nefgh\n"),
+            &mut CursorInputOutput::new("", "xxxx\nefgh\ny\n"),
             &rooster_file
         )
     );
@@ -11,7 +11,7 @@ fn test_command_import_json() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -116,7 +116,7 @@ fn test_command_import_csv() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -160,7 +160,7 @@ fn test_command_import_1password() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -196,3 +196,144 @@ fn test_command_import_1password() {
     assert!(output_as_string.contains("yt@example.com"));
     assert!(output_as_string.contains("Youtube"));
 }
+
+#[test]
+fn test_command_import_json_with_fields() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "add",
+                "-s",
+                "Youtube",
+                "yt@example.com",
+                "--url",
+                "https://youtube.com",
+                "--notes",
+                "some notes",
+                "--field",
+                "PIN=1234",
+                "--concealed-field",
+                "Backup code"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n9876\n"),
+            &rooster_file
+        )
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "export", "json"], &mut io, &rooster_file)
+    );
+    let export = io.stdout_cursor.into_inner();
+
+    // Import the export into a fresh password file
+    let import_file_json = tempfile();
+    File::create(import_file_json.clone())
+        .unwrap()
+        .write_all(&export)
+        .unwrap();
+    let other_rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &other_rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "import",
+                "json",
+                import_file_json.as_path().to_str().unwrap()
+            ],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &other_rooster_file
+        )
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "get", "-s", "youtube"],
+            &mut io,
+            &other_rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("URL: https://youtube.com"));
+    assert!(output_as_string.contains("Notes: some notes"));
+    assert!(output_as_string.contains("PIN: 1234"));
+    assert!(output_as_string.contains("Backup code: 9876"));
+}
+
+#[test]
+fn test_command_import_json_with_invalid_otp() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // A period of 0 would make computing codes divide by zero
+    let import_file_json = tempfile();
+    File::create(import_file_json.clone())
+        .unwrap()
+        .write_all(
+            br#"{"passwords":[{"name":"Youtube","username":"yt@example.com","password":"abcd","created_at":0,"updated_at":0,"otp":{"secret":"JBSWY3DPEHPK3PXP","digits":6,"period":0,"algorithm":"sha1"}}]}"#,
+        )
+        .unwrap();
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        1,
+        main_with_args(
+            &[
+                "rooster",
+                "import",
+                "json",
+                import_file_json.as_path().to_str().unwrap()
+            ],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stderr_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("invalid period"));
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "list", "--format", "json"],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    assert_eq!(
+        String::from_utf8_lossy(output_as_vecu8.as_slice()).trim(),
+        "[]"
+    );
+}
@@ -0,0 +1,33 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+
+#[test]
+fn test_command_init_weak_master_password() {
+    let rooster_file = tempfile();
+
+    let mut io = CursorInputOutput::new("", "\nrooster2024\nn\n");
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stderr_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("this master password is weak"));
+    assert!(!rooster_file.exists());
+
+    // Strong master passwords are accepted right away
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\ncorrect-horse-battery-staple\n"),
+            &rooster_file
+        )
+    );
+    assert!(rooster_file.exists());
+}
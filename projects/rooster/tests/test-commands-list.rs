@@ -9,7 +9,7 @@ fn test_command_list() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -17,7 +17,11 @@ fn test_command_list() {
     let mut io = CursorInputOutput::new("", "xxxx\n");
     assert_eq!(
         0,
-        main_with_args(&["rooster", "list"], &mut io, &rooster_file)
+        main_with_args(
+            &["rooster", "list", "--format", "text"],
+            &mut io,
+            &rooster_file
+        )
     );
     let output_as_vecu8 = io.stdout_cursor.into_inner();
     let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
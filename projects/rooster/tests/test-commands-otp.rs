@@ -0,0 +1,130 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+
+fn code_from_output(output: &str) -> String {
+    let start = output.find("code for Youtube: ").unwrap() + "code for Youtube: ".len();
+    output[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect()
+}
+
+#[test]
+fn test_command_otp() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "yt@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // No secret yet
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        1,
+        main_with_args(&["rooster", "otp", "-s", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stderr_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("rooster change 'Youtube' --otp"));
+
+    // Invalid secrets are refused
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "change", "youtube", "--otp"],
+            &mut CursorInputOutput::new("", "xxxx\nnot base32!\n"),
+            &rooster_file
+        )
+    );
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "change",
+                "-s",
+                "youtube",
+                "--otp",
+                "--otp-digits",
+                "8"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\nJBSW Y3DP EHPK 3PXP\n"),
+            &rooster_file
+        )
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "otp", "-s", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert_eq!(code_from_output(&output_as_string).len(), 8);
+
+    // The password itself didn't change, and the secret isn't shown
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "get", "-s", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("Password: abcd"));
+    assert!(output_as_string.contains("rooster otp 'Youtube'"));
+    assert!(!output_as_string.contains("JBSWY3DPEHPK3PXP"));
+
+    // otpauth:// URIs come with their own parameters
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "change", "youtube", "--otp"],
+            &mut CursorInputOutput::new(
+                "",
+                "xxxx\notpauth://totp/Youtube:yt@example.com?secret=JBSWY3DPEHPK3PXP&issuer=Youtube&digits=6\n"
+            ),
+            &rooster_file
+        )
+    );
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "otp", "-s", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert_eq!(code_from_output(&output_as_string).len(), 6);
+
+    // An empty secret removes it
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "change", "youtube", "--otp"],
+            &mut CursorInputOutput::new("", "xxxx\n\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "otp", "-s", "youtube"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+}
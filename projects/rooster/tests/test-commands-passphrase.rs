@@ -0,0 +1,74 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+
+fn get_passphrase(io: CursorInputOutput) -> String {
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice()).into_owned();
+    assert!(output_as_string.contains("bits of entropy"));
+
+    let prefix = "Here is your passphrase: ";
+    let start = output_as_string.find(prefix).unwrap() + prefix.len();
+    // Without the color codes
+    output_as_string[start..]
+        .split('\u{1b}')
+        .next()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn test_command_passphrase() {
+    // No password file needed
+    let rooster_file = tempfile();
+
+    let mut io = CursorInputOutput::new("", "");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "passphrase", "-s"], &mut io, &rooster_file)
+    );
+    let passphrase = get_passphrase(io);
+    // Some words of the list have a dash too
+    assert!(passphrase.split('-').count() >= 6);
+    assert!(passphrase
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c == '-'));
+
+    let mut io = CursorInputOutput::new("", "");
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "passphrase",
+                "-s",
+                "--words",
+                "4",
+                "--separator",
+                " ",
+                "--capitalize",
+                "--digit"
+            ],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let passphrase = get_passphrase(io);
+    let words: Vec<&str> = passphrase.split(' ').collect();
+    assert_eq!(words.len(), 4);
+    assert!(words
+        .iter()
+        .all(|word| word.starts_with(|c: char| c.is_ascii_uppercase())));
+    assert_eq!(passphrase.chars().filter(|c| c.is_ascii_digit()).count(), 1);
+
+    // Too few words
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "passphrase", "-s", "--words", "3"],
+            &mut CursorInputOutput::new("", ""),
+            &rooster_file
+        )
+    );
+    assert!(!rooster_file.exists());
+}
@@ -9,7 +9,7 @@ fn test_command_regenerate() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -68,3 +68,196 @@ fn test_command_regenerate() {
 
     assert_ne!(output_1_as_string, output_2_as_string);
 }
+
+#[test]
+fn test_command_regenerate_words() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "generate", "-s", "Youtube", "yt@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "regenerate",
+                "-s",
+                "--words",
+                "4",
+                "--capitalize",
+                "--separator",
+                "_",
+                "Youtube"
+            ],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("bits of entropy"));
+
+    let prefix = "Password: ";
+    let start = output_as_string.find(prefix).unwrap() + prefix.len();
+    let passphrase = output_as_string[start..].lines().next().unwrap();
+    let words: Vec<&str> = passphrase.split('_').collect();
+    assert_eq!(words.len(), 4);
+    assert!(words
+        .iter()
+        .all(|word| word.starts_with(|c: char| c.is_ascii_uppercase())));
+}
+
+fn get_password(rooster_file: &std::path::PathBuf) -> String {
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "get", "-s", "youtube"], &mut io, rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    let prefix = "Password: ";
+    let start = output_as_string.find(prefix).unwrap() + prefix.len();
+    // Without the color codes
+    output_as_string[start..]
+        .split('\u{1b}')
+        .next()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn test_command_regenerate_remembers_policy() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "generate",
+                "-s",
+                "--alnum",
+                "--length",
+                "16",
+                "Youtube",
+                "yt@example.com"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    let password = get_password(&rooster_file);
+    assert_eq!(password.len(), 16);
+
+    // Same kind of password as last time
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "regenerate", "-s", "Youtube"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    let regenerated = get_password(&rooster_file);
+    assert_ne!(regenerated, password);
+    assert_eq!(regenerated.len(), 16);
+    assert!(regenerated.chars().all(|c| c.is_ascii_alphanumeric()));
+
+    // Options update the policy, and are remembered too
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "regenerate", "-s", "--length", "20", "Youtube"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(get_password(&rooster_file).len(), 20);
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "regenerate", "-s", "Youtube"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    let regenerated = get_password(&rooster_file);
+    assert_eq!(regenerated.len(), 20);
+    assert!(regenerated.chars().all(|c| c.is_ascii_alphanumeric()));
+}
+
+#[test]
+fn test_command_regenerate_pattern() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "generate",
+                "-s",
+                "--pattern",
+                "A\\CME-9{6}",
+                "Youtube",
+                "yt@example.com"
+            ],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("This password has about 19 bits of entropy."));
+
+    for _ in 0..2 {
+        let password = get_password(&rooster_file);
+        assert_eq!(password.len(), 11);
+        assert!(password.starts_with("ACME-"));
+        assert!(password[5..].chars().all(|c| c.is_ascii_digit()));
+
+        // The pattern is remembered
+        assert_eq!(
+            0,
+            main_with_args(
+                &["rooster", "regenerate", "-s", "Youtube"],
+                &mut CursorInputOutput::new("", "xxxx\n"),
+                &rooster_file
+            )
+        );
+    }
+}
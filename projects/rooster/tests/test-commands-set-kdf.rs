@@ -0,0 +1,97 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::fs::File;
+use std::io::Read;
+
+fn read_rooster_file(rooster_file: &std::path::Path) -> Vec<u8> {
+    let mut rooster_file_contents = Vec::new();
+    File::open(rooster_file)
+        .unwrap()
+        .read_to_end(&mut rooster_file_contents)
+        .unwrap();
+    rooster_file_contents
+}
+
+#[test]
+fn test_command_set_kdf() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "yt@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
+            &rooster_file
+        )
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "set-kdf", "argon2id", "4194304", "2", "1"],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stderr_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("Run with --force to force"));
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "set-kdf", "argon2id", "1", "2", "1"],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stderr_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("invalid"));
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "set-kdf", "argon2id", "2048", "3", "1"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        &read_rooster_file(&rooster_file)[4..19],
+        &[4, 0, 12, 0, 0, 8, 0, 0, 0, 0, 3, 0, 0, 0, 1]
+    );
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "get", "-s", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("abcd"));
+
+    // Back to scrypt
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "set-scrypt-params", "12", "8", "1"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        &read_rooster_file(&rooster_file)[4..16],
+        &[1, 0, 9, 12, 0, 0, 0, 8, 0, 0, 0, 1]
+    );
+}
@@ -0,0 +1,137 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::fs;
+
+#[test]
+fn test_command_set_keyfile() {
+    let dir = tempfile::tempdir().unwrap();
+    let keyfile = dir.path().join("keyfile");
+    fs::write(&keyfile, b"\x8a\x01\xf3\x5c random bytes").unwrap();
+    let keyfile = keyfile.to_str().unwrap();
+    let other_keyfile = dir.path().join("other-keyfile");
+    fs::write(&other_keyfile, b"\x17\xc2\x9e\x40 other bytes").unwrap();
+    let other_keyfile = other_keyfile.to_str().unwrap();
+
+    let rooster_file = tempfile();
+    let mut io = CursorInputOutput::new("", "\nxxxx\ny\n");
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests", "--keyfile", keyfile],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("Keep a backup of the keyfile"));
+
+    // Without the keyfile, the master password isn't even asked for
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        11,
+        main_with_args(&["rooster", "list"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stderr_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("needs a keyfile"));
+
+    assert_eq!(
+        4,
+        main_with_args(
+            &["rooster", "--keyfile", other_keyfile, "list"],
+            &mut CursorInputOutput::new("", "xxxx\nxxxx\nxxxx\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "--keyfile", keyfile, "list"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+
+    // Switch to another keyfile
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "--keyfile",
+                keyfile,
+                "set-keyfile",
+                other_keyfile
+            ],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "--keyfile", other_keyfile, "list"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+
+    // Back to the master password alone
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "--keyfile",
+                other_keyfile,
+                "set-keyfile",
+                "--remove"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "list"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "set-keyfile", "--remove"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+
+    // The environment variable isn't enough to create a password file with a keyfile
+    let other_rooster_file = tempfile();
+    std::env::set_var("ROOSTER_KEYFILE", keyfile);
+    let mut io = CursorInputOutput::new("", "\nxxxx\ny\n");
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut io,
+            &other_rooster_file
+        )
+    );
+    std::env::remove_var("ROOSTER_KEYFILE");
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(!output_as_string.contains("Keep a backup of the keyfile"));
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "list"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &other_rooster_file
+        )
+    );
+}
@@ -9,7 +9,7 @@ fn test_command_set_master_password() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -18,7 +18,7 @@ fn test_command_set_master_password() {
         0,
         main_with_args(
             &["rooster", "set-master-password"],
-            &mut CursorInputOutput::new("", "xxxx\nabcd\nabcd\n"),
+            &mut CursorInputOutput::new("", "xxxx\nabcd\nabcd\ny\n"),
             &rooster_file
         )
     );
@@ -40,3 +40,34 @@ fn test_command_set_master_password() {
         )
     );
 }
+
+#[test]
+fn test_command_set_master_password_weak() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // Refusing a weak master password keeps the old one
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "set-master-password"],
+            &mut CursorInputOutput::new("", "xxxx\nqwerty123\nqwerty123\nn\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "list"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+}
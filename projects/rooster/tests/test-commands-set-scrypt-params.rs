@@ -11,7 +11,7 @@ fn test_command_set_scrypt_params() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -21,7 +21,7 @@ fn test_command_set_scrypt_params() {
         .unwrap()
         .read_to_end(&mut rooster_file_contents)
         .unwrap();
-    assert_eq!(&rooster_file_contents[4..13], &[12, 0, 0, 0, 8, 0, 0, 0, 1]);
+    assert_eq!(&rooster_file_contents[7..16], &[12, 0, 0, 0, 8, 0, 0, 0, 1]);
 
     let mut io = CursorInputOutput::new("", "xxxx\n");
     assert_eq!(
@@ -63,5 +63,141 @@ fn test_command_set_scrypt_params() {
         .unwrap()
         .read_to_end(&mut rooster_file_contents)
         .unwrap();
-    assert_eq!(&rooster_file_contents[4..13], &[6, 0, 0, 0, 4, 0, 0, 0, 2]);
+    assert_eq!(&rooster_file_contents[7..16], &[6, 0, 0, 0, 4, 0, 0, 0, 2]);
+}
+
+fn read_scrypt_params(rooster_file: &std::path::Path) -> Vec<u8> {
+    let mut rooster_file_contents = Vec::new();
+    File::open(rooster_file)
+        .unwrap()
+        .read_to_end(&mut rooster_file_contents)
+        .unwrap();
+    rooster_file_contents[7..16].to_vec()
+}
+
+#[test]
+fn test_command_set_scrypt_params_target_ms() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "set-scrypt-params", "--force", "6", "4", "2"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+
+    // A tiny target time and memory budget gives us the default parameters. Saying no changes
+    // nothing.
+    let mut io = CursorInputOutput::new("", "xxxx\nn\n");
+    assert_eq!(
+        1,
+        main_with_args(
+            &[
+                "rooster",
+                "set-scrypt-params",
+                "--target-ms",
+                "1",
+                "--max-memory-mb",
+                "4"
+            ],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("With log2n = 12, r = 8, p = 1, unlocking"));
+    assert!(output_as_string.contains("4 MiB of memory"));
+    assert!(!output_as_string.contains("more memory than you allowed"));
+    assert_eq!(
+        read_scrypt_params(&rooster_file),
+        &[6, 0, 0, 0, 4, 0, 0, 0, 2]
+    );
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "set-scrypt-params",
+                "--target-ms",
+                "1",
+                "--max-memory-mb",
+                "4"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\nmaybe\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        read_scrypt_params(&rooster_file),
+        &[12, 0, 0, 0, 8, 0, 0, 0, 1]
+    );
+}
+
+#[test]
+fn test_command_set_scrypt_params_target_ms_limits() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // Even the default parameters need 4 MiB
+    let mut io = CursorInputOutput::new("", "xxxx\nn\n");
+    assert_eq!(
+        1,
+        main_with_args(
+            &[
+                "rooster",
+                "set-scrypt-params",
+                "--target-ms",
+                "1",
+                "--max-memory-mb",
+                "1"
+            ],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("more memory than you allowed"));
+
+    // Calibrating doesn't switch from Argon2id to scrypt
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "set-kdf", "argon2id", "1024", "1", "1", "--force"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    let argon2id_params = read_scrypt_params(&rooster_file);
+    let mut io = CursorInputOutput::new("", "xxxx\ny\n");
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "set-scrypt-params", "--target-ms", "1"],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stderr_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("uses Argon2id"));
+    assert_eq!(read_scrypt_params(&rooster_file), argon2id_params);
 }
@@ -0,0 +1,148 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+
+fn run(args: &[&str], stdin: &str, rooster_file: &std::path::PathBuf) -> (i32, String) {
+    let mut io = CursorInputOutput::new("", stdin);
+    let code = main_with_args(args, &mut io, rooster_file);
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    (
+        code,
+        String::from_utf8_lossy(output_as_vecu8.as_slice()).into_owned(),
+    )
+}
+
+#[test]
+fn test_command_slot() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "oncall@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nv8$Lq!2zR#mW\n"),
+            &rooster_file
+        )
+    );
+
+    let (code, output) = run(&["rooster", "slot", "list"], "xxxx\n", &rooster_file);
+    assert_eq!(0, code);
+    assert!(output.contains("No key slots yet"));
+
+    // The first key slot keeps the current master password in the default key slot
+    assert_eq!(
+        0,
+        run(
+            &["rooster", "slot", "add", "alice"],
+            "xxxx\naaaa\naaaa\ny\n",
+            &rooster_file
+        )
+        .0
+    );
+    assert_eq!(
+        0,
+        run(
+            &["rooster", "slot", "add", "bob"],
+            "aaaa\nbbbb\nbbbb\ny\n",
+            &rooster_file
+        )
+        .0
+    );
+    assert_eq!(
+        1,
+        run(&["rooster", "slot", "add", "Bob"], "xxxx\n", &rooster_file).0
+    );
+
+    let (code, output) = run(&["rooster", "slot", "list"], "bbbb\n", &rooster_file);
+    assert_eq!(0, code);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(3, lines.len());
+    assert!(lines[0].starts_with("default  scrypt"));
+    assert!(lines[1].starts_with("alice    scrypt"));
+    assert!(lines[2].starts_with("bob      scrypt") && lines[2].ends_with("(yours)"));
+
+    for master_password in ["xxxx\n", "aaaa\n", "bbbb\n"] {
+        let (code, output) = run(
+            &["rooster", "get", "-s", "youtube"],
+            master_password,
+            &rooster_file,
+        );
+        assert_eq!(0, code);
+        assert!(output.contains("v8$Lq!2zR#mW"));
+    }
+
+    // Rotating a master password doesn't change the others
+    assert_eq!(
+        0,
+        run(
+            &["rooster", "set-master-password"],
+            "aaaa\ncccc\ncccc\ny\n",
+            &rooster_file
+        )
+        .0
+    );
+    assert_eq!(
+        4,
+        run(&["rooster", "list"], "aaaa\naaaa\naaaa\n", &rooster_file).0
+    );
+    assert_eq!(0, run(&["rooster", "list"], "cccc\n", &rooster_file).0);
+    assert_eq!(0, run(&["rooster", "list"], "bbbb\n", &rooster_file).0);
+
+    assert_eq!(
+        0,
+        run(
+            &["rooster", "slot", "remove", "default"],
+            "bbbb\n",
+            &rooster_file
+        )
+        .0
+    );
+    assert_eq!(
+        4,
+        run(&["rooster", "list"], "xxxx\nxxxx\nxxxx\n", &rooster_file).0
+    );
+    assert_eq!(
+        1,
+        run(
+            &["rooster", "slot", "remove", "default"],
+            "bbbb\n",
+            &rooster_file
+        )
+        .0
+    );
+
+    assert_eq!(
+        0,
+        run(
+            &["rooster", "slot", "remove", "alice"],
+            "bbbb\n",
+            &rooster_file
+        )
+        .0
+    );
+    let (code, output) = run(
+        &["rooster", "get", "-s", "youtube"],
+        "bbbb\n",
+        &rooster_file,
+    );
+    assert_eq!(0, code);
+    assert!(output.contains("v8$Lq!2zR#mW"));
+
+    // Somebody must still be able to open the password file
+    assert_eq!(
+        1,
+        run(
+            &["rooster", "slot", "remove", "bob"],
+            "bbbb\n",
+            &rooster_file
+        )
+        .0
+    );
+}
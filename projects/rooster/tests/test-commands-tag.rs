@@ -0,0 +1,149 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::fs::File;
+use std::io::Write;
+
+fn get_output(args: &[&str], ttyin: &str, rooster_file: &std::path::PathBuf) -> String {
+    let mut io = CursorInputOutput::new("", ttyin);
+    assert_eq!(0, main_with_args(args, &mut io, rooster_file));
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    String::from_utf8_lossy(output_as_vecu8.as_slice()).into_owned()
+}
+
+#[test]
+fn test_command_tag() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    for (app, password) in &[
+        ("Gitlab", "gitlab"),
+        ("Github", "github"),
+        ("Google", "google"),
+    ] {
+        assert_eq!(
+            0,
+            main_with_args(
+                &["rooster", "add", "-s", app, "me@example.com"],
+                &mut CursorInputOutput::new("", &format!("xxxx\n{}\ny\n", password)),
+                &rooster_file
+            )
+        );
+    }
+
+    let output = get_output(
+        &["rooster", "tag", "add", "github", "work"],
+        "xxxx\n",
+        &rooster_file,
+    );
+    assert!(output.contains("Github is now tagged: work"));
+    get_output(
+        &["rooster", "tag", "add", "gitlab", "Work"],
+        "xxxx\n",
+        &rooster_file,
+    );
+    get_output(
+        &["rooster", "tag", "add", "gitlab", "oss"],
+        "xxxx\n",
+        &rooster_file,
+    );
+
+    // Tags are compared without regard to casing
+    let output = get_output(
+        &["rooster", "tag", "add", "gitlab", "WORK"],
+        "xxxx\n",
+        &rooster_file,
+    );
+    assert!(output.contains("already tagged"));
+
+    let output = get_output(
+        &["rooster", "list", "--tag", "work"],
+        "xxxx\n",
+        &rooster_file,
+    );
+    assert!(output.contains("Github"));
+    assert!(output.contains("Gitlab"));
+    assert!(!output.contains("Google"));
+
+    let output = get_output(
+        &["rooster", "list", "--tag", "personal", "--format", "text"],
+        "xxxx\n",
+        &rooster_file,
+    );
+    assert!(output.contains("No passwords tagged \"personal\" yet."));
+
+    // "g" matches all 3 apps, "oss" only one of them
+    let output = get_output(
+        &["rooster", "get", "-s", "g", "--tag", "oss"],
+        "xxxx\n1\n",
+        &rooster_file,
+    );
+    assert!(output.contains("Password: gitlab"));
+    assert!(output.contains("Tags: Work, oss"));
+
+    assert_eq!(
+        9,
+        main_with_args(
+            &["rooster", "get", "-s", "google", "--tag", "oss"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+
+    let output = get_output(
+        &["rooster", "tag", "remove", "gitlab", "work"],
+        "xxxx\n",
+        &rooster_file,
+    );
+    assert!(output.contains("Gitlab is now tagged: oss"));
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "tag", "remove", "gitlab", "work"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+
+    // Tags survive an export and import
+    let export = get_output(&["rooster", "export", "json"], "xxxx\n", &rooster_file);
+    assert!(export.contains("\"tags\":[\"oss\"]"));
+    let import_file_json = tempfile();
+    File::create(import_file_json.clone())
+        .unwrap()
+        .write_all(export.as_bytes())
+        .unwrap();
+    let other_rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &other_rooster_file
+        )
+    );
+    get_output(
+        &[
+            "rooster",
+            "import",
+            "json",
+            import_file_json.as_path().to_str().unwrap(),
+        ],
+        "xxxx\n",
+        &other_rooster_file,
+    );
+    let output = get_output(
+        &["rooster", "list", "--tag", "oss"],
+        "xxxx\n",
+        &other_rooster_file,
+    );
+    assert!(output.contains("Gitlab"));
+    assert!(!output.contains("Github"));
+}
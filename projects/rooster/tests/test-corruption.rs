@@ -11,12 +11,14 @@ fn test_corruption() {
     // Creates corrupted file
     //
     // Corrupting a file can be done with:
-    //     bbe -e 'r 77 X' original.rooster > corrupted.rooster
-    // where X is a different char than the one that was at index 77 (the signature start)
+    //     bbe -e 'r 4 X' original.rooster > corrupted.rooster
+    // where X is a different char than the one that was at index 4 (the scrypt log2n parameter).
+    // A corrupted signature or blob can't be told apart from a wrong master password, since the
+    // signature is checked with the key derived from it.
     File::create(rooster_file.clone())
         .unwrap()
         .write_all(&[
-            0o000, 0o000, 0o000, 0o002, 0o014, 0o000, 0o000, 0o000, 0o010, 0o000, 0o000, 0o000,
+            0o000, 0o000, 0o000, 0o002, 0o100, 0o000, 0o000, 0o000, 0o010, 0o000, 0o000, 0o000,
             0o001, 0o106, 0o147, 0o156, 0o171, 0o125, 0o131, 0o203, 0o076, 0o153, 0o235, 0o076,
             0o010, 0o323, 0o004, 0o356, 0o144, 0o264, 0o115, 0o336, 0o243, 0o114, 0o055, 0o223,
             0o045, 0o054, 0o146, 0o247, 0o204, 0o167, 0o354, 0o026, 0o171, 0o356, 0o052, 0o316,
@@ -35,7 +37,7 @@ fn test_corruption() {
 
     let mut io = CursorInputOutput::new("", "xxxx\n");
     assert_eq!(
-        1,
+        6,
         main_with_args(&["rooster", "list"], &mut io, &rooster_file)
     );
     let output_as_vecu8 = io.stderr_cursor.into_inner();
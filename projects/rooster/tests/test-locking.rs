@@ -0,0 +1,93 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+#[test]
+fn test_locked_password_file() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "yt@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nabcd\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // Pretend another Rooster process is changing the password file.
+    let mut lock_file_path = rooster_file.clone().into_os_string();
+    lock_file_path.push(".lock");
+    let mut lock_file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&lock_file_path)
+        .unwrap();
+    lock_file.lock().unwrap();
+    lock_file.write_all(b"4242\n").unwrap();
+
+    let mut io = CursorInputOutput::new("", "xxxx\nefgh\n");
+    assert_eq!(
+        7,
+        main_with_args(
+            &["rooster", "add", "-s", "Google", "google@example.com"],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stderr_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("in use by PID 4242"));
+
+    assert_eq!(
+        7,
+        main_with_args(
+            &["rooster", "get", "-s", "youtube"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+
+    // Readers don't block other readers, only writers.
+    lock_file.unlock().unwrap();
+    lock_file.lock_shared().unwrap();
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "get", "-s", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("abcd"));
+
+    assert_eq!(
+        7,
+        main_with_args(
+            &["rooster", "add", "-s", "Google", "google@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nefgh\ny\n"),
+            &rooster_file
+        )
+    );
+
+    lock_file.unlock().unwrap();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Google", "google@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nefgh\ny\n"),
+            &rooster_file
+        )
+    );
+}
@@ -0,0 +1,185 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::fs::File;
+use std::io::Write;
+
+fn get_output(args: &[&str], ttyin: &str, rooster_file: &std::path::PathBuf) -> String {
+    let mut io = CursorInputOutput::new("", ttyin);
+    assert_eq!(0, main_with_args(args, &mut io, rooster_file));
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    String::from_utf8_lossy(output_as_vecu8.as_slice()).into_owned()
+}
+
+#[test]
+fn test_multiple_accounts_for_the_same_app() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Github", "alice"],
+            &mut CursorInputOutput::new("", "xxxx\nalice-password\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Github", "bob"],
+            &mut CursorInputOutput::new("", "xxxx\nbob-password\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // The same account can't be added twice
+    assert_eq!(
+        1,
+        main_with_args(
+            &["rooster", "add", "-s", "github", "ALICE"],
+            &mut CursorInputOutput::new("", "xxxx\nother-password\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // Accounts are sorted by username, so bob is the second one
+    let output = get_output(
+        &["rooster", "get", "-s", "github"],
+        "xxxx\n2\n",
+        &rooster_file,
+    );
+    assert!(output.contains("Which password would you like to see?"));
+    assert!(output.contains("Password: bob-password"));
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "change", "-s", "github"],
+            &mut CursorInputOutput::new("", "xxxx\n1\nnew-alice-password\ny\n"),
+            &rooster_file
+        )
+    );
+    let output = get_output(
+        &["rooster", "get", "-s", "github"],
+        "xxxx\n1\n",
+        &rooster_file,
+    );
+    assert!(output.contains("Password: new-alice-password"));
+    let output = get_output(
+        &["rooster", "get", "-s", "github"],
+        "xxxx\n2\n",
+        &rooster_file,
+    );
+    assert!(output.contains("Password: bob-password"));
+
+    // Renaming one account leaves the other one alone
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "rename", "github", "Github Enterprise"],
+            &mut CursorInputOutput::new("", "xxxx\n2\n"),
+            &rooster_file
+        )
+    );
+    let output = get_output(
+        &["rooster", "list", "--format", "text"],
+        "xxxx\n",
+        &rooster_file,
+    );
+    assert!(output.contains("Github            alice"));
+    assert!(output.contains("Github Enterprise bob"));
+
+    // Only one account is left for "github", so there is nothing to choose from
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "transfer", "github", "carol"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "delete", "github"],
+            &mut CursorInputOutput::new("", "xxxx\n"),
+            &rooster_file
+        )
+    );
+    let output = get_output(
+        &["rooster", "list", "--format", "text"],
+        "xxxx\n",
+        &rooster_file,
+    );
+    assert!(!output.contains("carol"));
+    assert!(output.contains("Github Enterprise bob"));
+}
+
+#[test]
+fn test_import_accounts_for_the_same_app() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Github", "alice"],
+            &mut CursorInputOutput::new("", "xxxx\nalice-password\ny\n"),
+            &rooster_file
+        )
+    );
+
+    let import_file_csv = tempfile();
+    File::create(import_file_csv.clone())
+        .unwrap()
+        .write_all("Github,alice,other-password\nGithub,bob,bob-password\n".as_bytes())
+        .unwrap();
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "import",
+                "csv",
+                import_file_csv.as_path().to_str().unwrap()
+            ],
+            &mut io,
+            &rooster_file
+        )
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("Imported: 1"));
+    let output_as_vecu8 = io.stderr_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("Github (alice), already in password store, skipping"));
+
+    let output = get_output(
+        &["rooster", "get", "-s", "github"],
+        "xxxx\n1\n",
+        &rooster_file,
+    );
+    assert!(output.contains("Password: alice-password"));
+    let output = get_output(
+        &["rooster", "get", "-s", "github"],
+        "xxxx\n2\n",
+        &rooster_file,
+    );
+    assert!(output.contains("Password: bob-password"));
+}
@@ -0,0 +1,199 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+
+fn run(args: &[&str], rooster_file: &PathBuf) -> (i32, String) {
+    // Nothing to read from: any question must fail instead of blocking
+    let mut io = CursorInputOutput::new("", "");
+    let code = main_with_args(args, &mut io, rooster_file);
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    (
+        code,
+        String::from_utf8_lossy(output_as_vecu8.as_slice()).into_owned(),
+    )
+}
+
+#[test]
+fn test_non_interactive() {
+    let dir = tempfile::tempdir().unwrap();
+    let master_password_file = dir.path().join("master-password");
+    fs::write(&master_password_file, "xxxx\n").unwrap();
+    let master_password_file = master_password_file.to_str().unwrap();
+    let wrong_password_file = dir.path().join("wrong-password");
+    fs::write(&wrong_password_file, "yyyy\n").unwrap();
+    let wrong_password_file = wrong_password_file.to_str().unwrap();
+
+    let rooster_file = tempfile();
+    assert_eq!(
+        5,
+        run(&["rooster", "--non-interactive", "list"], &rooster_file).0
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Youtube", "first@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nv8$Lq!2zR#mW\n"),
+            &rooster_file
+        )
+    );
+
+    // Asking for the master password is not allowed
+    assert_eq!(
+        10,
+        run(
+            &["rooster", "--non-interactive", "get", "-s", "youtube"],
+            &rooster_file
+        )
+        .0
+    );
+
+    let (code, output) = run(
+        &[
+            "rooster",
+            "--non-interactive",
+            "--master-password-file",
+            master_password_file,
+            "get",
+            "-s",
+            "youtube",
+        ],
+        &rooster_file,
+    );
+    assert_eq!(0, code);
+    assert!(output.contains("v8$Lq!2zR#mW"));
+
+    // A wrong master password fails right away, there is nobody to try again
+    assert_eq!(
+        4,
+        run(
+            &[
+                "rooster",
+                "--master-password-file",
+                wrong_password_file,
+                "get",
+                "-s",
+                "youtube",
+            ],
+            &rooster_file
+        )
+        .0
+    );
+
+    // Changes are saved too, as long as nothing needs to be asked
+    assert_eq!(
+        0,
+        run(
+            &[
+                "rooster",
+                "--yes",
+                "--master-password-file",
+                master_password_file,
+                "generate",
+                "-s",
+                "YouTube Music",
+                "second@example.com",
+            ],
+            &rooster_file
+        )
+        .0
+    );
+    assert_eq!(
+        10,
+        run(
+            &[
+                "rooster",
+                "--yes",
+                "--master-password-file",
+                master_password_file,
+                "add",
+                "Github",
+                "alice",
+            ],
+            &rooster_file
+        )
+        .0
+    );
+
+    // Choosing between several matches is a question too
+    assert_eq!(
+        10,
+        run(
+            &[
+                "rooster",
+                "--non-interactive",
+                "--master-password-file",
+                master_password_file,
+                "get",
+                "-s",
+                "yt",
+            ],
+            &rooster_file
+        )
+        .0
+    );
+    assert_eq!(
+        9,
+        run(
+            &[
+                "rooster",
+                "--non-interactive",
+                "--master-password-file",
+                master_password_file,
+                "get",
+                "-s",
+                "gitlab",
+            ],
+            &rooster_file
+        )
+        .0
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_master_password_fd() {
+    use std::os::unix::io::IntoRawFd;
+
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let master_password_file = dir.path().join("master-password");
+    fs::write(&master_password_file, "xxxx").unwrap();
+    let fd = fs::File::open(&master_password_file)
+        .unwrap()
+        .into_raw_fd()
+        .to_string();
+
+    assert_eq!(
+        0,
+        run(
+            &[
+                "rooster",
+                "--non-interactive",
+                "--master-password-fd",
+                &fd,
+                "list",
+            ],
+            &rooster_file
+        )
+        .0
+    );
+}
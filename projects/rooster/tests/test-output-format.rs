@@ -0,0 +1,110 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::path::PathBuf;
+
+fn get_output(args: &[&str], rooster_file: &PathBuf) -> String {
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(0, main_with_args(args, &mut io, rooster_file));
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    String::from_utf8_lossy(output_as_vecu8.as_slice()).into_owned()
+}
+
+#[test]
+fn test_output_format() {
+    let rooster_file = tempfile();
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "init", "--force-for-tests"],
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
+            &rooster_file
+        )
+    );
+
+    // Empty lists are still valid JSON
+    assert_eq!(
+        "[]\n",
+        get_output(&["rooster", "list", "--format", "json"], &rooster_file)
+    );
+    assert_eq!("", get_output(&["rooster", "list"], &rooster_file));
+
+    assert_eq!(
+        0,
+        main_with_args(
+            &[
+                "rooster",
+                "add",
+                "-s",
+                "Youtube",
+                "yt@example.com",
+                "--url",
+                "https://youtube.com"
+            ],
+            &mut CursorInputOutput::new("", "xxxx\nv8$Lq!2zR#mW\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Google", "google@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nkX9#pQ2!vL7@\n"),
+            &rooster_file
+        )
+    );
+
+    // Lists that don't go to a terminal are TSV, without padding
+    let output = get_output(&["rooster", "list"], &rooster_file);
+    let lines: Vec<Vec<&str>> = output
+        .lines()
+        .map(|line| line.split('\t').collect())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0][..4], ["Google", "google@example.com", "", ""]);
+    assert_eq!(
+        lines[1][..4],
+        ["Youtube", "yt@example.com", "https://youtube.com", ""]
+    );
+    assert_eq!(lines[1].len(), 6);
+    assert!(!output.contains("v8$Lq!2zR#mW"));
+
+    let output = get_output(&["rooster", "list", "--format", "text"], &rooster_file);
+    assert!(output.contains("Google  google@example.com"));
+
+    let output = get_output(&["rooster", "--format", "json", "list"], &rooster_file);
+    let records: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(records.as_array().unwrap().len(), 2);
+    assert_eq!(records[1]["app"], "Youtube");
+    assert_eq!(records[1]["username"], "yt@example.com");
+    assert_eq!(records[1]["url"], "https://youtube.com");
+    assert!(records[1]["created_at"].as_i64().unwrap() > 0);
+    assert!(records[1]["updated_at"].as_i64().unwrap() > 0);
+    assert!(records[1].get("password").is_none());
+
+    // Search lists every match instead of asking which one
+    let output = get_output(
+        &["rooster", "search", "o", "--format", "json"],
+        &rooster_file,
+    );
+    let records: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(records.as_array().unwrap().len(), 2);
+    let output = get_output(&["rooster", "search", "ytb"], &rooster_file);
+    assert!(output.starts_with("Youtube\tyt@example.com\t"));
+    assert_eq!(output.lines().count(), 1);
+
+    // The password is only there with --show
+    let output = get_output(
+        &["rooster", "get", "youtube", "--show", "--format", "json"],
+        &rooster_file,
+    );
+    let record: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(record["app"], "Youtube");
+    assert_eq!(record["password"], "v8$Lq!2zR#mW");
+    let output = get_output(
+        &["rooster", "get", "google", "--show", "--format", "tsv"],
+        &rooster_file,
+    );
+    assert!(output.starts_with("Google\tgoogle@example.com\t"));
+    assert!(output.ends_with("\tkX9#pQ2!vL7@\n"));
+}
@@ -9,7 +9,7 @@ fn test_password_retry_ok() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
@@ -31,14 +31,14 @@ fn test_password_retry_nok() {
         0,
         main_with_args(
             &["rooster", "init", "--force-for-tests"],
-            &mut CursorInputOutput::new("", "\nxxxx\n"),
+            &mut CursorInputOutput::new("", "\nxxxx\ny\n"),
             &rooster_file
         )
     );
 
     let mut io = CursorInputOutput::new("", "nok\nnok\nnok\n");
     assert_eq!(
-        1,
+        4,
         main_with_args(&["rooster", "list"], &mut io, &rooster_file)
     );
     let output_as_vecu8 = io.stderr_cursor.into_inner();
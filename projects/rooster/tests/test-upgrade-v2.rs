@@ -0,0 +1,102 @@
+mod helpers;
+
+use crate::helpers::prelude::*;
+use std::fs::File;
+use std::io::{Read, Write};
+
+fn read_version(rooster_file: &std::path::Path) -> Vec<u8> {
+    let mut rooster_file_contents = Vec::new();
+    File::open(rooster_file)
+        .unwrap()
+        .read_to_end(&mut rooster_file_contents)
+        .unwrap();
+    rooster_file_contents[0..4].to_vec()
+}
+
+#[test]
+fn test_upgrade_from_v2() {
+    let rooster_file = tempfile();
+
+    // Rooster file version 2, with master password "xxxx" and a password "abcd" for Youtube
+    File::create(rooster_file.clone())
+        .unwrap()
+        .write_all(&[
+            0o000, 0o000, 0o000, 0o002, 0o014, 0o000, 0o000, 0o000, 0o010, 0o000, 0o000, 0o000,
+            0o001, 0o302, 0o031, 0o356, 0o220, 0o225, 0o105, 0o316, 0o216, 0o274, 0o345, 0o172,
+            0o135, 0o175, 0o165, 0o326, 0o244, 0o316, 0o060, 0o132, 0o060, 0o375, 0o241, 0o044,
+            0o223, 0o111, 0o075, 0o377, 0o363, 0o212, 0o347, 0o250, 0o126, 0o054, 0o163, 0o363,
+            0o176, 0o226, 0o370, 0o376, 0o234, 0o122, 0o107, 0o164, 0o276, 0o033, 0o033, 0o050,
+            0o042, 0o043, 0o361, 0o375, 0o017, 0o060, 0o066, 0o351, 0o357, 0o074, 0o172, 0o221,
+            0o301, 0o012, 0o105, 0o355, 0o135, 0o202, 0o276, 0o040, 0o205, 0o124, 0o322, 0o114,
+            0o046, 0o136, 0o145, 0o156, 0o175, 0o056, 0o326, 0o027, 0o161, 0o064, 0o116, 0o003,
+            0o014, 0o007, 0o020, 0o104, 0o332, 0o064, 0o336, 0o363, 0o315, 0o331, 0o057, 0o306,
+            0o011, 0o071, 0o277, 0o003, 0o130, 0o102, 0o227, 0o374, 0o020, 0o105, 0o136, 0o361,
+            0o072, 0o073, 0o000, 0o324, 0o276, 0o354, 0o230, 0o026, 0o174, 0o263, 0o367, 0o130,
+            0o100, 0o213, 0o152, 0o257, 0o262, 0o312, 0o376, 0o046, 0o116, 0o001, 0o342, 0o256,
+            0o170, 0o352, 0o165, 0o116, 0o225, 0o260, 0o051, 0o324, 0o117, 0o254, 0o272, 0o313,
+            0o235, 0o344, 0o217, 0o332, 0o340, 0o332, 0o120, 0o004, 0o175, 0o355, 0o371, 0o225,
+            0o046, 0o221, 0o151, 0o055, 0o153, 0o052, 0o300, 0o113, 0o164, 0o274, 0o173, 0o334,
+            0o322, 0o101, 0o003, 0o265, 0o063, 0o073, 0o371, 0o251, 0o120, 0o351, 0o214, 0o374,
+            0o121, 0o010, 0o036, 0o034, 0o226, 0o054, 0o363, 0o113, 0o351, 0o363, 0o144, 0o200,
+            0o110, 0o072, 0o214, 0o120, 0o044, 0o235, 0o322, 0o127, 0o242, 0o045, 0o244, 0o217,
+            0o341, 0o017, 0o175, 0o311, 0o276, 0o325, 0o120, 0o377, 0o146, 0o234, 0o213, 0o356,
+            0o362, 0o267, 0o331, 0o050, 0o105, 0o373, 0o204, 0o255, 0o376, 0o000, 0o347, 0o270,
+            0o302, 0o341, 0o000, 0o244, 0o040, 0o201, 0o054, 0o106, 0o026, 0o130, 0o226, 0o322,
+            0o346, 0o231, 0o272, 0o064, 0o143, 0o254, 0o363, 0o316, 0o276, 0o237, 0o175, 0o000,
+            0o274, 0o064, 0o027, 0o240, 0o162,
+        ])
+        .unwrap();
+
+    // A wrong master password can be typed again
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "list"],
+            &mut CursorInputOutput::new("", "yyyy\nxxxx\n"),
+            &rooster_file
+        )
+    );
+
+    // Reading doesn't change the file
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "get", "-s", "youtube"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("abcd"));
+    assert!(output_as_string.contains("yt@example.com"));
+    assert_eq!(read_version(&rooster_file), &[0, 0, 0, 2]);
+
+    // The first change saves the file as version 3
+    assert_eq!(
+        0,
+        main_with_args(
+            &["rooster", "add", "-s", "Google", "google@example.com"],
+            &mut CursorInputOutput::new("", "xxxx\nefgh\ny\n"),
+            &rooster_file
+        )
+    );
+    assert_eq!(read_version(&rooster_file), &[0, 0, 0, 3]);
+
+    let mut io = CursorInputOutput::new("", "xxxx\n");
+    assert_eq!(
+        0,
+        main_with_args(&["rooster", "list"], &mut io, &rooster_file)
+    );
+    let output_as_vecu8 = io.stdout_cursor.into_inner();
+    let output_as_string = String::from_utf8_lossy(output_as_vecu8.as_slice());
+    assert!(output_as_string.contains("Youtube"));
+    assert!(output_as_string.contains("Google"));
+
+    // Wrong master passwords are still detected
+    assert_eq!(
+        4,
+        main_with_args(
+            &["rooster", "list"],
+            &mut CursorInputOutput::new("", "yyyy\nyyyy\nyyyy\n"),
+            &rooster_file
+        )
+    );
+}